@@ -0,0 +1,130 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Measures the cost of message loop turns that end without progress.
+//!
+//! Run with `cargo bench --bench no_progress`.
+
+use std::{
+    future::Future as _,
+    hint::black_box,
+    pin::pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use infect::{
+    consume_messages, process_message, unbounded_message_channel, EffectApplied, IntentHandled,
+    Message, MessagesConsumed, Model, ModelChanged, ModelRender, TaskContext, TaskExecutor,
+    UnboundedMpscTransport,
+};
+
+const TURNS: u32 = 1_000_000;
+
+#[derive(Debug)]
+struct NoOpModel;
+
+impl Model for NoOpModel {
+    type Intent = ();
+    type IntentRejected = ();
+    type Effect = ();
+    type Task = ();
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        (): Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        IntentHandled::accepted_unchanged()
+    }
+
+    fn apply_effect(
+        &mut self,
+        (): Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        EffectApplied::unchanged()
+    }
+}
+
+struct NoOpRender;
+
+impl ModelRender for NoOpRender {
+    type Model = NoOpModel;
+
+    fn render_model(
+        &mut self,
+        _model: &NoOpModel,
+        _hint: ModelChanged,
+    ) -> impl IntoIterator<Item = ()> {
+        None
+    }
+}
+
+struct NoOpTaskExecutor;
+
+impl TaskExecutor<Rc<NoOpTaskExecutor>, UnboundedMpscTransport> for NoOpTaskExecutor {
+    type Intent = ();
+    type Effect = ();
+    type Task = ();
+
+    fn spawn_task(
+        &self,
+        _context: TaskContext<Rc<NoOpTaskExecutor>, (), (), UnboundedMpscTransport>,
+        (): (),
+    ) {
+    }
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let nanos_per_turn = elapsed.as_secs_f64() * 1e9 / f64::from(TURNS);
+    let turns_per_sec = f64::from(TURNS) / elapsed.as_secs_f64();
+    println!("{name:<20} {nanos_per_turn:>8.1} ns/turn {turns_per_sec:>14.0} turns/s");
+}
+
+fn bench_process_message() {
+    let (message_port, _message_rx) = unbounded_message_channel().split();
+    let mut task_context = TaskContext::new(Rc::new(NoOpTaskExecutor), message_port);
+    let mut model = NoOpModel;
+    let mut render_model = NoOpRender;
+    let started = Instant::now();
+    for _ in 0..TURNS {
+        let message_processed = process_message(
+            &mut task_context,
+            &mut model,
+            &mut render_model,
+            black_box(Message::Effect(())),
+        );
+        black_box(message_processed);
+    }
+    report("process_message", started.elapsed());
+}
+
+fn bench_consume_messages() {
+    let (mut message_port, mut message_rx) = unbounded_message_channel().split();
+    let mut task_context = TaskContext::new(Rc::new(NoOpTaskExecutor), message_port.clone());
+    let mut model = NoOpModel;
+    let mut render_model = NoOpRender;
+    let mut cx = Context::from_waker(Waker::noop());
+    let started = Instant::now();
+    for _ in 0..TURNS {
+        message_port.submit_effect(()).unwrap();
+        let consumed = pin!(consume_messages(
+            &mut message_rx,
+            &mut task_context,
+            &mut model,
+            &mut render_model,
+        ))
+        .poll(&mut cx);
+        assert!(matches!(
+            consumed,
+            Poll::Ready(MessagesConsumed::NoProgress)
+        ));
+    }
+    report("consume_messages", started.elapsed());
+}
+
+fn main() {
+    bench_process_message();
+    bench_consume_messages();
+}
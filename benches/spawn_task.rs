@@ -0,0 +1,55 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Measures the cost of spawning trivial tasks that submit a single effect.
+//!
+//! Run with `cargo bench --bench spawn_task`.
+
+use std::{
+    hint::black_box,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use infect::{unbounded_message_channel, TaskContext, TaskExecutor, UnboundedMpscTransport};
+
+const TASKS: u32 = 1_000_000;
+
+struct SubmittingTaskExecutor;
+
+type Context = TaskContext<Rc<SubmittingTaskExecutor>, (), (), UnboundedMpscTransport>;
+
+impl TaskExecutor<Rc<SubmittingTaskExecutor>, UnboundedMpscTransport> for SubmittingTaskExecutor {
+    type Intent = ();
+    type Effect = ();
+    type Task = ();
+
+    fn spawn_task(&self, mut context: Context, (): ()) {
+        black_box(context.submit_effect(())).unwrap();
+    }
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let nanos_per_task = elapsed.as_secs_f64() * 1e9 / f64::from(TASKS);
+    let tasks_per_sec = f64::from(TASKS) / elapsed.as_secs_f64();
+    println!("{name:<20} {nanos_per_task:>8.1} ns/task {tasks_per_sec:>14.0} tasks/s");
+}
+
+fn bench_spawn_task(name: &str, with_task_tracking: bool) {
+    // The receiver is kept alive and collects all submitted effects.
+    let (message_port, _message_rx) = unbounded_message_channel().split();
+    let mut task_context = TaskContext::new(Rc::new(SubmittingTaskExecutor), message_port);
+    if with_task_tracking {
+        task_context = task_context.with_task_tracking();
+    }
+    let started = Instant::now();
+    for _ in 0..TASKS {
+        black_box(task_context.spawn_task(()));
+    }
+    report(name, started.elapsed());
+}
+
+fn main() {
+    bench_spawn_task("untracked", false);
+    bench_spawn_task("tracked", true);
+}
@@ -0,0 +1,388 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll},
+};
+
+use futures_channel::oneshot;
+
+use crate::{
+    ChannelClosed, Message, MessageChannel, MessagePort, MessageReceiver, MessageSender,
+    MessageTransport, MpscTransport, ProcessingObserver, TryModel, TryRecvError, TrySendError,
+};
+
+/// Acknowledgement of a submitted intent
+///
+/// See also: [`crate::MessagePort::submit_intent_ack()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ack {
+    /// The intent has been accepted and all resulting effects have been
+    /// applied
+    Accepted,
+
+    /// The intent has been rejected
+    Rejected,
+
+    /// The intent has been accepted, but applying the resulting effects
+    /// either failed or exceeded the maximum length of the effect chain
+    Failed,
+
+    /// The intent has not been handled
+    ///
+    /// Either the message has been lost or it has been received without
+    /// notifying [`IntentAcks`].
+    Dropped,
+}
+
+/// Sending side for acknowledging an intent
+pub struct AckSender(oneshot::Sender<Ack>);
+
+impl AckSender {
+    fn send(self, ack: Ack) {
+        // The receiver might have been dropped.
+        let _ = self.0.send(ack);
+    }
+}
+
+impl fmt::Debug for AckSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AckSender").finish_non_exhaustive()
+    }
+}
+
+/// Resolves with the [`Ack`] of a submitted intent
+///
+/// See also: [`crate::MessagePort::submit_intent_ack()`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct IntentAck(oneshot::Receiver<Ack>);
+
+impl IntentAck {
+    pub(crate) fn channel() -> (AckSender, Self) {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        (AckSender(ack_tx), Self(ack_rx))
+    }
+}
+
+impl Future for IntentAck {
+    type Output = Ack;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.get_mut().0)
+            .poll(cx)
+            .map(|ack| ack.unwrap_or(Ack::Dropped))
+    }
+}
+
+/// An intent with an optional acknowledgement
+///
+/// Carried by the inner transport of an [`AckTransport`].
+#[derive(Debug)]
+pub struct Acked<Intent> {
+    intent: Intent,
+    ack_tx: Option<AckSender>,
+}
+
+/// A [`MessageTransport`] that supports sending intents with an [`AckSender`]
+pub trait AckMessageTransport<Intent, Effect>: MessageTransport<Intent, Effect> {
+    /// Try to send an intent that is acknowledged after it has been handled
+    ///
+    /// # Errors
+    ///
+    /// Returns the message if the channel is either full or closed.
+    fn try_send_with_ack(
+        sender: &mut Self::Sender,
+        intent: Intent,
+        ack_tx: AckSender,
+    ) -> Result<(), TrySendError<Intent, Effect>>;
+}
+
+/// A [`MessageTransport`] for acknowledging intents after they have been handled
+///
+/// The receiver hands over the [`AckSender`] of each received intent to
+/// [`IntentAcks`] that must be passed as an observer when processing
+/// messages, e.g.
+///
+/// ```no_run
+/// # use infect::{
+/// #     ack_message_channel, consume_messages_with_observer, message_channel, Ack, AckTransport,
+/// #     MaybeDebug, ModelRender, ProcessingOptions, SubmitError, TaskContext, TaskExecutor,
+/// #     TryModel,
+/// # };
+/// # async fn example<M, R, T>(
+/// #     capacity: usize,
+/// #     task_executor: T,
+/// #     mut model: M,
+/// #     mut render_model: R,
+/// #     save: M::Intent,
+/// # ) -> Result<(), SubmitError<M::Intent, M::Effect>>
+/// # where
+/// #     M: TryModel + MaybeDebug,
+/// #     R: ModelRender<Model = M>,
+/// #     T: TaskExecutor<T, AckTransport, Intent = M::Intent, Effect = M::Effect, Task = M::Task>
+/// #         + Clone,
+/// # {
+/// let (mut message_port, mut message_rx) =
+///     ack_message_channel(message_channel(capacity)).split();
+/// let mut intent_acks = message_rx.intent_acks();
+/// let intent_ack = message_port.submit_intent_ack(save)?;
+/// # let mut task_context = TaskContext::new(task_executor, message_port);
+/// consume_messages_with_observer(
+///     &mut message_rx,
+///     &mut task_context,
+///     &mut model,
+///     &mut render_model,
+///     ProcessingOptions::default(),
+///     &mut intent_acks,
+/// )
+/// .await;
+/// assert_eq!(intent_ack.await, Ack::Accepted);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Requires the `std` feature.
+///
+/// See also: [`ack_message_channel()`]
+#[derive(Debug)]
+pub struct AckTransport<Transport = MpscTransport> {
+    _phantom: PhantomData<fn() -> Transport>,
+}
+
+/// A [`MessagePort`] for submitting intents with an acknowledgement
+///
+/// See also: [`AckTransport`]
+pub type AckMessagePort<Intent, Effect, Transport = MpscTransport> =
+    MessagePort<Intent, Effect, AckTransport<Transport>>;
+
+/// Wrap a message channel for acknowledging intents
+///
+/// See also: [`AckTransport`]
+#[must_use]
+pub fn ack_message_channel<Intent, Effect, Transport>(
+    channel: MessageChannel<Acked<Intent>, Effect, Transport>,
+) -> MessageChannel<Intent, Effect, AckTransport<Transport>>
+where
+    Transport: MessageTransport<Acked<Intent>, Effect>,
+{
+    let (sender, receiver) = channel.into_parts();
+    let message_rx = AckMessageReceiver {
+        receiver,
+        intent_acks: IntentAcks::default(),
+    };
+    MessageChannel::new(sender, message_rx)
+}
+
+/// Acknowledges the intents that are received by an [`AckMessageReceiver`]
+///
+/// Must be passed as a [`ProcessingObserver`] when processing messages.
+/// The intent is acknowledged after the whole message has been processed.
+/// All clones share the pending acknowledgement.
+#[derive(Debug, Clone, Default)]
+pub struct IntentAcks {
+    pending: Arc<Mutex<Option<PendingAck>>>,
+}
+
+#[derive(Debug)]
+struct PendingAck {
+    ack_tx: AckSender,
+    ack: Ack,
+}
+
+impl IntentAcks {
+    fn lock(&self) -> MutexGuard<'_, Option<PendingAck>> {
+        // The state remains consistent even if a panic occurred while locked.
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Replace the pending acknowledgement
+    ///
+    /// An unacknowledged intent that has been received before is
+    /// acknowledged as dropped.
+    fn received(&self, ack_tx: Option<AckSender>) {
+        let pending = ack_tx.map(|ack_tx| PendingAck {
+            ack_tx,
+            ack: Ack::Dropped,
+        });
+        let dropped = std::mem::replace(&mut *self.lock(), pending);
+        if let Some(PendingAck { ack_tx, ack: _ }) = dropped {
+            ack_tx.send(Ack::Dropped);
+        }
+    }
+
+    /// Update the outcome of the pending acknowledgement
+    fn update(&self, update_ack: impl FnOnce(Ack) -> Ack) {
+        if let Some(PendingAck { ack_tx: _, ack }) = &mut *self.lock() {
+            *ack = update_ack(*ack);
+        }
+    }
+
+    /// Applying the effects of an accepted intent failed
+    fn failed(&self) {
+        self.update(|ack| {
+            if ack == Ack::Accepted {
+                Ack::Failed
+            } else {
+                ack
+            }
+        });
+    }
+
+    fn ack(&self) {
+        let pending = self.lock().take();
+        if let Some(PendingAck { ack_tx, ack }) = pending {
+            ack_tx.send(ack);
+        }
+    }
+}
+
+impl<M: TryModel> ProcessingObserver<M> for IntentAcks {
+    fn on_intent_handled(&mut self) {
+        self.update(|_| Ack::Accepted);
+    }
+
+    fn on_intent_rejected(&mut self, _intent_rejected: &M::IntentRejected) {
+        self.update(|_| Ack::Rejected);
+    }
+
+    fn on_effect_failed(&mut self, _apply_error: &M::ApplyError) {
+        self.failed();
+    }
+
+    fn on_effect_chain_exceeded(&mut self) {
+        self.failed();
+    }
+
+    fn on_message_processed(&mut self) {
+        self.ack();
+    }
+}
+
+/// Receiver of an [`AckTransport`]
+pub struct AckMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Acked<Intent>, Effect>,
+{
+    receiver: MessageReceiver<Acked<Intent>, Effect, Transport>,
+    intent_acks: IntentAcks,
+}
+
+impl<Intent, Effect, Transport> AckMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Acked<Intent>, Effect>,
+{
+    /// The observer for acknowledging received intents
+    #[must_use]
+    pub fn intent_acks(&self) -> IntentAcks {
+        self.intent_acks.clone()
+    }
+
+    fn unwrap_received(&self, message: Message<Acked<Intent>, Effect>) -> Message<Intent, Effect> {
+        match message {
+            Message::Intent(Acked { intent, ack_tx }) => {
+                self.intent_acks.received(ack_tx);
+                Message::Intent(intent)
+            }
+            Message::Effect(effect) => Message::Effect(effect),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for AckMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Acked<Intent>, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            receiver: _,
+            intent_acks,
+        } = self;
+        f.debug_struct("AckMessageReceiver")
+            .field("intent_acks", intent_acks)
+            .finish_non_exhaustive()
+    }
+}
+
+fn unwrap_sent<Intent, Effect>(message: Message<Acked<Intent>, Effect>) -> Message<Intent, Effect> {
+    // The acknowledgement is dropped together with the message.
+    message.map_intent(|Acked { intent, ack_tx: _ }| intent)
+}
+
+fn unwrap_send_error<Intent, Effect>(
+    err: TrySendError<Acked<Intent>, Effect>,
+) -> TrySendError<Intent, Effect> {
+    match err {
+        TrySendError::Full(message) => TrySendError::Full(unwrap_sent(message)),
+        TrySendError::Disconnected(message) => TrySendError::Disconnected(unwrap_sent(message)),
+    }
+}
+
+impl<Intent, Effect, Transport> MessageTransport<Intent, Effect> for AckTransport<Transport>
+where
+    Transport: MessageTransport<Acked<Intent>, Effect>,
+{
+    type Sender = MessageSender<Acked<Intent>, Effect, Transport>;
+    type Receiver = AckMessageReceiver<Intent, Effect, Transport>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        let message = message.map_intent(|intent| Acked {
+            intent,
+            ack_tx: None,
+        });
+        Transport::try_send(sender, message).map_err(unwrap_send_error)
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        Transport::poll_ready(sender, cx)
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        Transport::is_closed(sender)
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Transport::pending_message_count(sender)
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        Transport::poll_next(&mut receiver.receiver, cx)
+            .map(|message| message.map(|message| receiver.unwrap_received(message)))
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        let message = Transport::try_recv(&mut receiver.receiver)?;
+        Ok(receiver.unwrap_received(message))
+    }
+}
+
+impl<Intent, Effect, Transport> AckMessageTransport<Intent, Effect> for AckTransport<Transport>
+where
+    Transport: MessageTransport<Acked<Intent>, Effect>,
+{
+    fn try_send_with_ack(
+        sender: &mut Self::Sender,
+        intent: Intent,
+        ack_tx: AckSender,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        let message = Message::Intent(Acked {
+            intent,
+            ack_tx: Some(ack_tx),
+        });
+        Transport::try_send(sender, message).map_err(unwrap_send_error)
+    }
+}
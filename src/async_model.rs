@@ -0,0 +1,369 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::vec::Vec;
+use core::{
+    future::{poll_fn, Future},
+    num::NonZeroUsize,
+};
+
+use crate::{
+    logging::{log_debug, log_error, log_trace, log_warn},
+    processing::{yield_now, RenderLoopDetector, YieldScheduler},
+    EffectApplied, IntentHandled, MaybeDebug, Message, MessageReceiver, MessageTransport,
+    MessagesConsumed, ModelRenderHint, ProcessingOptions, TaskContext, TaskExecutor, TryRecvError,
+};
+
+/// A stateful model with asynchronous reducers
+///
+/// Asynchronous variant of [`crate::Model`] for models that need to
+/// await before deciding whether to accept or reject an intent, e.g.
+/// for acquiring a lock or reading a cache. Prefer [`crate::Model`]
+/// unless awaiting is inevitable.
+///
+/// Both methods could be implemented as `async fn`.
+///
+/// See also: [`consume_messages_async`]
+pub trait AsyncModel {
+    /// An intent type that this model handles
+    type Intent: MaybeDebug;
+
+    /// The result of rejecting an intent
+    type IntentRejected: MaybeDebug;
+
+    /// An effect type that could be applied to this model
+    type Effect: MaybeDebug;
+
+    /// A task type for inducing side-effects
+    type Task: MaybeDebug;
+
+    /// A hint for rendering
+    type RenderHint: ModelRenderHint;
+
+    /// Handle an intent
+    ///
+    /// See also: [`crate::Model::handle_intent()`]
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> impl Future<
+        Output = IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint>,
+    >;
+
+    /// Apply an effect to the model
+    ///
+    /// See also: [`crate::Model::apply_effect()`]
+    fn apply_effect(
+        &mut self,
+        effect: Self::Effect,
+    ) -> impl Future<Output = EffectApplied<Self::Effect, Self::Task, Self::RenderHint>>;
+}
+
+/// Render an [`AsyncModel`] after changed
+///
+/// Rendering is synchronous, like [`crate::ModelRender`].
+pub trait AsyncModelRender {
+    /// The model
+    type Model: AsyncModel;
+
+    /// Decide if the model should be rendered
+    ///
+    /// Returns `true` by default.
+    ///
+    /// See also: [`crate::ModelRender::should_render()`]
+    #[must_use]
+    fn should_render(
+        &self,
+        model: &Self::Model,
+        hint: &<Self::Model as AsyncModel>::RenderHint,
+    ) -> bool {
+        let _ = (model, hint);
+        true
+    }
+
+    /// Render the model after changed
+    ///
+    /// See also: [`crate::ModelRender::render_model()`]
+    #[must_use]
+    fn render_model(
+        &mut self,
+        model: &Self::Model,
+        hint: <Self::Model as AsyncModel>::RenderHint,
+    ) -> impl IntoIterator<Item = <Self::Model as AsyncModel>::Intent>;
+}
+
+/// Receive and process messages of an [`AsyncModel`]
+///
+/// Uses the default [`ProcessingOptions`].
+///
+/// See also: [`consume_messages_async_with_options`]
+pub async fn consume_messages_async<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+) -> MessagesConsumed<M::IntentRejected>
+where
+    M: AsyncModel + MaybeDebug,
+    R: AsyncModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_async_with_options(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions::default(),
+    )
+    .await
+}
+
+/// Receive and process messages of an [`AsyncModel`] with custom options
+///
+/// Same stop conditions and options as [`crate::consume_messages_with_options`].
+///
+/// In contrast to [`crate::consume_messages`] this `async fn` is **not**
+/// _cancellation safe_. Dropping the returned future while awaiting the
+/// model might leave the model in an intermediate state and the current
+/// message is lost.
+pub async fn consume_messages_async_with_options<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+) -> MessagesConsumed<M::IntentRejected>
+where
+    M: AsyncModel + MaybeDebug,
+    R: AsyncModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let ProcessingOptions {
+        max_effect_chain_len,
+        max_consecutive_observed_intents,
+        coalesce_renders,
+        yield_interval,
+    } = options;
+    let mut yield_scheduler = YieldScheduler::new(yield_interval);
+    let mut render_loop_detector = RenderLoopDetector::new(max_consecutive_observed_intents);
+    let mut processed_count = 0;
+    loop {
+        log_trace!("Awaiting next message");
+        let Some(mut message) = poll_fn(|cx| Transport::poll_next(message_rx, cx)).await else {
+            log_debug!("Stopping after message channel closed");
+            return MessagesConsumed::ChannelClosed;
+        };
+        let mut render_hint = M::RenderHint::default();
+        let mut progressing = false;
+        // Process all messages that are ready until progress is observed.
+        let stopped = loop {
+            log_debug!("Processing message: {message:?}");
+            processed_count += 1;
+            let chain_applied =
+                match apply_message_async(task_context, model, message, max_effect_chain_len).await
+                {
+                    Ok(chain_applied) => chain_applied,
+                    Err(intent_rejected) => {
+                        log_debug!("Stopping after intent rejected: {intent_rejected:?}");
+                        break Some(MessagesConsumed::IntentRejected(intent_rejected));
+                    }
+                };
+            let ChainApplied {
+                render_hint: next_render_hint,
+                tasks_spawned,
+                effect_chain_exceeded,
+            } = chain_applied;
+            render_hint += next_render_hint;
+            progressing |= tasks_spawned;
+            if !coalesce_renders {
+                let observed_intent_count = render_model_async(
+                    task_context,
+                    model,
+                    render_model,
+                    core::mem::take(&mut render_hint),
+                );
+                if !effect_chain_exceeded
+                    && is_render_loop(&mut render_loop_detector, observed_intent_count)
+                {
+                    break Some(MessagesConsumed::RenderLoopDetected);
+                }
+                progressing |= observed_intent_count > 0;
+            }
+            if effect_chain_exceeded {
+                log_debug!("Stopping after effect chain exceeded");
+                break Some(MessagesConsumed::EffectChainExceeded);
+            }
+            if progressing && !coalesce_renders {
+                break None;
+            }
+            if yield_scheduler.is_due(processed_count) {
+                log_trace!("Yielding after processing {processed_count} message(s)");
+                yield_now().await;
+            }
+            message = match Transport::try_recv(message_rx) {
+                Ok(next_message) => next_message,
+                Err(TryRecvError::Closed) => {
+                    log_debug!("Stopping after message channel closed");
+                    break Some(MessagesConsumed::ChannelClosed);
+                }
+                Err(TryRecvError::Empty) => {
+                    break None;
+                }
+            };
+        };
+        if coalesce_renders {
+            // Render all changes before stopping or awaiting the next message.
+            let observed_intent_count =
+                render_model_async(task_context, model, render_model, render_hint);
+            if stopped.is_none() && is_render_loop(&mut render_loop_detector, observed_intent_count)
+            {
+                return MessagesConsumed::RenderLoopDetected;
+            }
+            progressing |= observed_intent_count > 0;
+        }
+        if let Some(stopped) = stopped {
+            return stopped;
+        }
+        if !progressing {
+            return stop_after_no_progress(task_context.is_idle());
+        }
+    }
+}
+
+/// Stop after no progress observed and no next message ready
+///
+/// The system has settled if no tracked tasks are pending.
+fn stop_after_no_progress<IntentRejected>(is_idle: bool) -> MessagesConsumed<IntentRejected> {
+    if is_idle {
+        log_debug!(
+            "Stopping after no progress observed, no next message ready, and no pending tasks"
+        );
+        MessagesConsumed::Quiescent
+    } else {
+        log_debug!("Stopping after no progress observed and no next message ready");
+        MessagesConsumed::NoProgress
+    }
+}
+
+#[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+fn is_render_loop(
+    render_loop_detector: &mut RenderLoopDetector,
+    observed_intent_count: usize,
+) -> bool {
+    let Some(consecutive_observed_intents) = render_loop_detector.exceeded(observed_intent_count)
+    else {
+        return false;
+    };
+    log_warn!(
+        "Stopping after {consecutive_observed_intents} consecutive message(s) with observed \
+         intents"
+    );
+    true
+}
+
+/// The outcome of applying a single message
+struct ChainApplied<RenderHint> {
+    render_hint: RenderHint,
+    tasks_spawned: bool,
+    effect_chain_exceeded: bool,
+}
+
+/// Apply a single message and all resulting next effects
+///
+/// Spawns all resulting tasks and accumulates the render hints.
+async fn apply_message_async<M, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    message: Message<M::Intent, M::Effect>,
+    max_effect_chain_len: Option<NonZeroUsize>,
+) -> Result<ChainApplied<M::RenderHint>, M::IntentRejected>
+where
+    M: AsyncModel + MaybeDebug,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let mut effect_applied = match message {
+        Message::Intent(intent) => {
+            log_debug!("Handling intent: {intent:?}");
+            match model.handle_intent(intent).await {
+                IntentHandled::Accepted(effect_applied) => effect_applied,
+                IntentHandled::Rejected(intent_rejected) => {
+                    log_debug!("Intent rejected: {intent_rejected:?}");
+                    return Err(intent_rejected);
+                }
+            }
+        }
+        Message::Effect(effect) => {
+            log_debug!("Applying effect: {effect:?}");
+            model.apply_effect(effect).await
+        }
+    };
+    let mut chain_applied = ChainApplied {
+        render_hint: M::RenderHint::default(),
+        tasks_spawned: false,
+        effect_chain_exceeded: false,
+    };
+    let mut effect_chain_len = 0;
+    let mut pending_effects = Vec::new();
+    loop {
+        let EffectApplied {
+            tasks,
+            render_hint,
+            next_effects,
+        } = effect_applied;
+        for task in tasks {
+            log_debug!("Spawning task: {task:?}");
+            task_context.spawn_task(task);
+            chain_applied.tasks_spawned = true;
+        }
+        chain_applied.render_hint += render_hint;
+        // Depth-first: The next effects precede all remaining, pending effects.
+        pending_effects.extend(next_effects.into_iter().rev());
+        let Some(effect) = pending_effects.pop() else {
+            return Ok(chain_applied);
+        };
+        if max_effect_chain_len.is_some_and(|max| effect_chain_len >= max.get()) {
+            log_error!(
+                "Aborting after applying {effect_chain_len} next effect(s) - discarding \
+                 {discarded_count} effect(s), starting with: {effect:?}",
+                discarded_count = pending_effects.len() + 1,
+            );
+            chain_applied.effect_chain_exceeded = true;
+            return Ok(chain_applied);
+        }
+        effect_chain_len += 1;
+        log_debug!("Applying next effect: {effect:?}");
+        effect_applied = model.apply_effect(effect).await;
+    }
+}
+
+/// Render the model if needed
+///
+/// Returns the number of observed intents that have been submitted.
+fn render_model_async<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &M,
+    render_model: &mut R,
+    render_hint: M::RenderHint,
+) -> usize
+where
+    M: AsyncModel + MaybeDebug,
+    R: AsyncModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    if !render_hint.should_render_model() || !render_model.should_render(model, &render_hint) {
+        return 0;
+    }
+    log_debug!("Rendering model: {model:?}");
+    let mut observed_intent_count = 0;
+    for observed_intent in render_model.render_model(model, render_hint) {
+        log_debug!("Observed intent after rendering model: {observed_intent:?}");
+        if task_context.submit_intent(observed_intent).is_ok() {
+            observed_intent_count += 1;
+        }
+    }
+    observed_intent_count
+}
@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use futures_channel::mpsc;
+
+use crate::{ProcessingObserver, TryModel};
+
+/// Forwards a clone of every applied effect to subscribers
+///
+/// Allows read-only observers like loggers or analytics to follow the
+/// stream of effects without participating in the reduction. Effects
+/// are forwarded in the order they are applied, including next effects.
+///
+/// Used as a [`ProcessingObserver`], e.g. with
+/// [`crate::consume_messages_with_observer`].
+///
+/// Each subscriber receives the effects through an unbounded channel
+/// that keeps growing if it is not consumed. Subscribers are removed
+/// after their receiver has been dropped.
+///
+/// Requires the `broadcast` feature.
+#[derive(Debug)]
+pub struct EffectBroadcast<Effect> {
+    subscribers: Vec<mpsc::UnboundedSender<Effect>>,
+}
+
+impl<Effect> EffectBroadcast<Effect> {
+    /// Create a new instance without subscribers
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Add a new subscriber
+    ///
+    /// The receiver only receives effects that are applied after
+    /// subscribing.
+    #[must_use]
+    pub fn subscribe(&mut self) -> mpsc::UnboundedReceiver<Effect> {
+        let (effect_tx, effect_rx) = mpsc::unbounded();
+        self.subscribers.push(effect_tx);
+        effect_rx
+    }
+
+    /// The number of subscribers
+    ///
+    /// Might include subscribers that have been dropped after the
+    /// last effect has been forwarded.
+    #[must_use]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.len()
+    }
+}
+
+impl<Effect> Default for EffectBroadcast<Effect> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> ProcessingObserver<M> for EffectBroadcast<M::Effect>
+where
+    M: TryModel,
+    M::Effect: Clone,
+{
+    fn on_apply_effect(&mut self, effect: &M::Effect) {
+        self.subscribers
+            .retain(|effect_tx| effect_tx.unbounded_send(effect.clone()).is_ok());
+    }
+}
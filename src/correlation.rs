@@ -0,0 +1,553 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    task::{Context, Poll},
+};
+
+use futures_channel::oneshot;
+
+use crate::{
+    logging::log_debug, ChannelClosed, Message, MessageChannel, MessagePort, MessageReceiver,
+    MessageSender, MessageTransport, MpscTransport, ProcessingObserver, TryModel, TryRecvError,
+    TrySendError,
+};
+
+/// Identifies the effect that responds to an intent
+///
+/// See also: [`Correlatable`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CorrelationId(u64);
+
+impl CorrelationId {
+    /// The numeric value
+    #[must_use]
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+}
+
+/// Intents and effects that carry a [`CorrelationId`]
+///
+/// The model is responsible for echoing the correlation id of an
+/// intent into the effect that responds to it, e.g.
+///
+/// ```no_run
+/// # use infect::{
+/// #     Correlatable, CorrelationId, EffectApplied, IntentHandled, Model, ModelChanged,
+/// # };
+/// # #[derive(Debug)]
+/// # struct Load {
+/// #     correlation_id: Option<CorrelationId>,
+/// # }
+/// # impl Correlatable for Load {
+/// #     fn correlation_id(&self) -> Option<CorrelationId> {
+/// #         self.correlation_id
+/// #     }
+/// #     fn with_correlation_id(self, correlation_id: Option<CorrelationId>) -> Self {
+/// #         Self { correlation_id }
+/// #     }
+/// # }
+/// # #[derive(Debug)]
+/// # struct Loaded {
+/// #     document: String,
+/// #     correlation_id: Option<CorrelationId>,
+/// # }
+/// # impl Loaded {
+/// #     fn new(document: String) -> Self {
+/// #         Self { document, correlation_id: None }
+/// #     }
+/// # }
+/// # impl Correlatable for Loaded {
+/// #     fn correlation_id(&self) -> Option<CorrelationId> {
+/// #         self.correlation_id
+/// #     }
+/// #     fn with_correlation_id(self, correlation_id: Option<CorrelationId>) -> Self {
+/// #         Self { correlation_id, ..self }
+/// #     }
+/// # }
+/// # #[derive(Debug, Default)]
+/// # struct Editor {
+/// #     document: String,
+/// # }
+/// # impl Editor {
+/// #     fn load(&self) -> String {
+/// #         String::new()
+/// #     }
+/// # }
+/// # impl Model for Editor {
+/// #     type Intent = Load;
+/// #     type IntentRejected = ();
+/// #     type Effect = Loaded;
+/// #     type Task = ();
+/// #     type RenderHint = ModelChanged;
+/// fn handle_intent(
+///     &mut self,
+///     intent: Self::Intent,
+/// ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+///     let effect = Loaded::new(self.load()).with_correlation_id(intent.correlation_id());
+///     IntentHandled::Accepted(EffectApplied::unchanged_next(effect))
+/// }
+/// #     fn apply_effect(
+/// #         &mut self,
+/// #         effect: Self::Effect,
+/// #     ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+/// #         self.document = effect.document;
+/// #         EffectApplied::maybe_changed()
+/// #     }
+/// # }
+/// ```
+///
+/// See also: [`crate::MessagePort::submit_and_await_effect()`]
+pub trait Correlatable: Sized {
+    /// The correlation id, if any
+    fn correlation_id(&self) -> Option<CorrelationId>;
+
+    /// Replace the correlation id
+    #[must_use]
+    fn with_correlation_id(self, correlation_id: Option<CorrelationId>) -> Self;
+}
+
+/// The reason why no effect responded to a submitted intent
+///
+/// See also: [`EffectResponse`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoEffectResponse {
+    /// The intent has been rejected
+    IntentRejected,
+
+    /// The intent has been processed without applying an effect
+    /// with the same correlation id
+    Unanswered,
+
+    /// The receiver has been dropped before the intent has been processed
+    ChannelClosed,
+}
+
+/// Resolves with the effect that responds to a submitted intent
+///
+/// Fails if the intent has been rejected, if no effect with the same
+/// correlation id has been applied while processing the intent, or if
+/// the receiver has been dropped before.
+///
+/// See also: [`crate::MessagePort::submit_and_await_effect()`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct EffectResponse<Effect>(oneshot::Receiver<Result<Effect, NoEffectResponse>>);
+
+impl<Effect> Future for EffectResponse<Effect> {
+    type Output = Result<Effect, NoEffectResponse>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.get_mut().0)
+            .poll(cx)
+            .map(|response| response.unwrap_or(Err(NoEffectResponse::ChannelClosed)))
+    }
+}
+
+/// A [`MessageTransport`] that supports sending correlated requests
+pub trait CorrelatedMessageTransport<Intent, Effect>: MessageTransport<Intent, Effect> {
+    /// Try to send an intent with a new correlation id
+    ///
+    /// # Errors
+    ///
+    /// Returns the message if the channel is either full or closed.
+    fn try_send_request(
+        sender: &mut Self::Sender,
+        intent: Intent,
+    ) -> Result<EffectResponse<Effect>, TrySendError<Intent, Effect>>;
+}
+
+/// A [`MessageTransport`] for awaiting the effect that responds to an intent
+///
+/// Each request is stamped with a new [`CorrelationId`]. The receiver
+/// provides [`EffectResponses`] that must be passed as an observer when
+/// processing messages. It forwards a clone of the first applied effect
+/// with a matching correlation id to the pending request, no matter if
+/// the effect has been received from the channel or has been returned
+/// as a next effect. The request fails if the intent is rejected or if
+/// no matching effect has been applied after processing the intent, e.g.
+///
+/// ```no_run
+/// # use infect::{
+/// #     consume_messages_with_observer, correlated_message_channel, message_channel, Correlatable,
+/// #     CorrelatedTransport, MaybeDebug, ModelRender, NoEffectResponse, ProcessingOptions,
+/// #     TaskContext, TaskExecutor, TryModel,
+/// # };
+/// # async fn example<M, R, T>(
+/// #     capacity: usize,
+/// #     task_executor: T,
+/// #     mut model: M,
+/// #     mut render_model: R,
+/// #     load: M::Intent,
+/// # ) -> Result<M::Effect, NoEffectResponse>
+/// # where
+/// #     M: TryModel + MaybeDebug,
+/// #     M::Intent: Correlatable,
+/// #     M::Effect: Correlatable + Clone,
+/// #     R: ModelRender<Model = M>,
+/// #     T: TaskExecutor<
+/// #             T,
+/// #             CorrelatedTransport,
+/// #             Intent = M::Intent,
+/// #             Effect = M::Effect,
+/// #             Task = M::Task,
+/// #         > + Clone,
+/// # {
+/// let (mut message_port, mut message_rx) =
+///     correlated_message_channel(message_channel(capacity)).split();
+/// let mut effect_responses = message_rx.effect_responses();
+/// let effect_response = message_port
+///     .submit_and_await_effect(load)
+///     .map_err(|_| NoEffectResponse::ChannelClosed)?;
+/// # let mut task_context = TaskContext::new(task_executor, message_port);
+/// consume_messages_with_observer(
+///     &mut message_rx,
+///     &mut task_context,
+///     &mut model,
+///     &mut render_model,
+///     ProcessingOptions::default(),
+///     &mut effect_responses,
+/// )
+/// .await;
+/// let effect = effect_response.await?;
+/// # Ok(effect)
+/// # }
+/// ```
+///
+/// Requires the `std` feature.
+///
+/// See also: [`correlated_message_channel()`]
+#[derive(Debug)]
+pub struct CorrelatedTransport<Transport = MpscTransport> {
+    _phantom: PhantomData<fn() -> Transport>,
+}
+
+/// A [`MessagePort`] for submitting correlated requests
+///
+/// See also: [`CorrelatedTransport`]
+pub type CorrelatedMessagePort<Intent, Effect, Transport = MpscTransport> =
+    MessagePort<Intent, Effect, CorrelatedTransport<Transport>>;
+
+/// Wrap a message channel for submitting correlated requests
+///
+/// See also: [`CorrelatedTransport`]
+#[must_use]
+pub fn correlated_message_channel<Intent, Effect, Transport>(
+    channel: MessageChannel<Intent, Effect, Transport>,
+) -> MessageChannel<Intent, Effect, CorrelatedTransport<Transport>>
+where
+    Intent: Correlatable,
+    Transport: MessageTransport<Intent, Effect>,
+{
+    let (sender, receiver) = channel.into_parts();
+    let pending_requests = Arc::new(PendingRequests::default());
+    let message_tx = CorrelatedMessageSender {
+        sender,
+        pending_requests: Arc::clone(&pending_requests),
+    };
+    let message_rx = CorrelatedMessageReceiver {
+        receiver,
+        pending_requests,
+    };
+    MessageChannel::new(message_tx, message_rx)
+}
+
+type ResponseSender<Effect> = oneshot::Sender<Result<Effect, NoEffectResponse>>;
+
+struct PendingRequests<Effect> {
+    next_correlation_id: AtomicU64,
+    response_txs: Mutex<Vec<(CorrelationId, ResponseSender<Effect>)>>,
+}
+
+impl<Effect> Default for PendingRequests<Effect> {
+    fn default() -> Self {
+        Self {
+            next_correlation_id: AtomicU64::new(0),
+            response_txs: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<Effect> PendingRequests<Effect> {
+    fn lock(&self) -> MutexGuard<'_, Vec<(CorrelationId, ResponseSender<Effect>)>> {
+        // The requests remain consistent even if a panic occurred while locked.
+        self.response_txs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn register(&self) -> (CorrelationId, EffectResponse<Effect>) {
+        let correlation_id =
+            CorrelationId(self.next_correlation_id.fetch_add(1, Ordering::Relaxed));
+        let (response_tx, response_rx) = oneshot::channel();
+        let mut response_txs = self.lock();
+        // Discard abandoned requests.
+        response_txs.retain(|(_, response_tx)| !response_tx.is_canceled());
+        response_txs.push((correlation_id, response_tx));
+        (correlation_id, EffectResponse(response_rx))
+    }
+
+    fn unregister(&self, correlation_id: CorrelationId) -> Option<ResponseSender<Effect>> {
+        let mut response_txs = self.lock();
+        let index = response_txs
+            .iter()
+            .position(|(pending_id, _)| *pending_id == correlation_id)?;
+        Some(response_txs.swap_remove(index).1)
+    }
+
+    fn respond(&self, effect: &Effect)
+    where
+        Effect: Correlatable + Clone,
+    {
+        let Some(correlation_id) = effect.correlation_id() else {
+            return;
+        };
+        let Some(response_tx) = self.unregister(correlation_id) else {
+            return;
+        };
+        if response_tx.send(Ok(effect.clone())).is_err() {
+            log_debug!(
+                "Dropping response #{} - request has been abandoned",
+                correlation_id.value()
+            );
+        }
+    }
+
+    /// Fail a request that is still pending
+    fn fail(&self, correlation_id: CorrelationId, no_response: NoEffectResponse) {
+        let Some(response_tx) = self.unregister(correlation_id) else {
+            return;
+        };
+        log_debug!(
+            "Failing request #{}: {no_response:?}",
+            correlation_id.value()
+        );
+        // The request might have been abandoned.
+        let _ = response_tx.send(Err(no_response));
+    }
+}
+
+/// Sender of a [`CorrelatedTransport`]
+pub struct CorrelatedMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    sender: MessageSender<Intent, Effect, Transport>,
+    pending_requests: Arc<PendingRequests<Effect>>,
+}
+
+impl<Intent, Effect, Transport> CorrelatedMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// The number of requests that are still awaiting their response
+    #[must_use]
+    pub fn pending_request_count(&self) -> usize {
+        self.pending_requests.lock().len()
+    }
+}
+
+impl<Intent, Effect, Transport> Clone for CorrelatedMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn clone(&self) -> Self {
+        let Self {
+            sender,
+            pending_requests,
+        } = self;
+        Self {
+            sender: sender.clone(),
+            pending_requests: Arc::clone(pending_requests),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for CorrelatedMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CorrelatedMessageSender")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receiver of a [`CorrelatedTransport`]
+///
+/// All pending requests fail when the receiver is dropped.
+pub struct CorrelatedMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    receiver: MessageReceiver<Intent, Effect, Transport>,
+    pending_requests: Arc<PendingRequests<Effect>>,
+}
+
+impl<Intent, Effect, Transport> CorrelatedMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// The observer for responding to pending requests
+    #[must_use]
+    pub fn effect_responses(&self) -> EffectResponses<Effect> {
+        EffectResponses {
+            pending_requests: Arc::clone(&self.pending_requests),
+            current_request: None,
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> Drop for CorrelatedMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn drop(&mut self) {
+        self.pending_requests.lock().clear();
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for CorrelatedMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CorrelatedMessageReceiver")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Responds to the requests that are submitted through a [`CorrelatedTransport`]
+///
+/// Must be passed as a [`ProcessingObserver`] when processing messages.
+pub struct EffectResponses<Effect> {
+    pending_requests: Arc<PendingRequests<Effect>>,
+    current_request: Option<CorrelationId>,
+}
+
+impl<Effect> Clone for EffectResponses<Effect> {
+    fn clone(&self) -> Self {
+        let Self {
+            pending_requests,
+            current_request,
+        } = self;
+        Self {
+            pending_requests: Arc::clone(pending_requests),
+            current_request: *current_request,
+        }
+    }
+}
+
+impl<Effect> fmt::Debug for EffectResponses<Effect> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EffectResponses").finish_non_exhaustive()
+    }
+}
+
+impl<M> ProcessingObserver<M> for EffectResponses<M::Effect>
+where
+    M: TryModel,
+    M::Intent: Correlatable,
+    M::Effect: Correlatable + Clone,
+{
+    fn on_message(&mut self, message: &Message<M::Intent, M::Effect>) {
+        self.current_request = match message {
+            Message::Intent(intent) => intent.correlation_id(),
+            Message::Effect(_) => None,
+        };
+    }
+
+    fn on_intent_rejected(&mut self, _intent_rejected: &M::IntentRejected) {
+        if let Some(correlation_id) = self.current_request.take() {
+            self.pending_requests
+                .fail(correlation_id, NoEffectResponse::IntentRejected);
+        }
+    }
+
+    fn on_apply_effect(&mut self, effect: &M::Effect) {
+        self.pending_requests.respond(effect);
+    }
+
+    fn on_message_processed(&mut self) {
+        // Fails only if no matching effect has been applied.
+        if let Some(correlation_id) = self.current_request.take() {
+            self.pending_requests
+                .fail(correlation_id, NoEffectResponse::Unanswered);
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> MessageTransport<Intent, Effect> for CorrelatedTransport<Transport>
+where
+    Intent: Correlatable,
+    Transport: MessageTransport<Intent, Effect>,
+{
+    type Sender = CorrelatedMessageSender<Intent, Effect, Transport>;
+    type Receiver = CorrelatedMessageReceiver<Intent, Effect, Transport>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        Transport::try_send(&mut sender.sender, message)
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        Transport::poll_ready(&mut sender.sender, cx)
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        Transport::is_closed(&sender.sender)
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Transport::pending_message_count(&sender.sender)
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        Transport::poll_next(&mut receiver.receiver, cx)
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        Transport::try_recv(&mut receiver.receiver)
+    }
+}
+
+impl<Intent, Effect, Transport> CorrelatedMessageTransport<Intent, Effect>
+    for CorrelatedTransport<Transport>
+where
+    Intent: Correlatable,
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn try_send_request(
+        sender: &mut Self::Sender,
+        intent: Intent,
+    ) -> Result<EffectResponse<Effect>, TrySendError<Intent, Effect>> {
+        let CorrelatedMessageSender {
+            sender,
+            pending_requests,
+        } = sender;
+        let (correlation_id, effect_response) = pending_requests.register();
+        let intent = intent.with_correlation_id(Some(correlation_id));
+        Transport::try_send(sender, Message::Intent(intent))
+            .inspect_err(|_| {
+                pending_requests.unregister(correlation_id);
+            })
+            .map(|()| effect_response)
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll},
+};
+
+use crate::{
+    logging::log_debug, ChannelClosed, Message, MessageChannel, MessagePort, MessageReceiver,
+    MessageSender, MessageTransport, MpscTransport, TryRecvError, TrySendError,
+};
+
+/// A [`MessageTransport`] that drops duplicate intents
+///
+/// An intent is dropped silently if an identical intent has already
+/// been sent but not yet received. Intents are removed from the set
+/// of pending intents when they are received, i.e. before they are
+/// handled by the model. Effects are never dropped.
+///
+/// Intended for chatty producers that submit the same intent many
+/// times in a burst. Pending intents are compared linearly, i.e.
+/// this transport is only suitable for a small number of pending
+/// intents.
+///
+/// Requires the `std` feature.
+///
+/// See also: [`dedup_message_channel()`]
+#[derive(Debug)]
+pub struct DedupTransport<Transport = MpscTransport> {
+    _phantom: PhantomData<fn() -> Transport>,
+}
+
+/// A [`MessagePort`] that drops duplicate intents
+///
+/// See also: [`DedupTransport`]
+pub type DedupMessagePort<Intent, Effect, Transport = MpscTransport> =
+    MessagePort<Intent, Effect, DedupTransport<Transport>>;
+
+/// Wrap a message channel for dropping duplicate intents
+///
+/// ```
+/// # use infect::{dedup_message_channel, message_channel, DedupMessagePort};
+/// # #[derive(Debug, Clone, PartialEq)]
+/// # struct Refresh;
+/// # let capacity = 10;
+/// let (mut message_port, mut message_rx) =
+///     dedup_message_channel(message_channel(capacity)).split();
+/// # let _: &DedupMessagePort<Refresh, ()> = &message_port;
+/// ```
+#[must_use]
+pub fn dedup_message_channel<Intent, Effect, Transport>(
+    channel: MessageChannel<Intent, Effect, Transport>,
+) -> MessageChannel<Intent, Effect, DedupTransport<Transport>>
+where
+    Intent: PartialEq + Clone,
+    Transport: MessageTransport<Intent, Effect>,
+{
+    let (sender, receiver) = channel.into_parts();
+    let pending_intents = Arc::new(PendingIntents::default());
+    let message_tx = DedupMessageSender {
+        sender,
+        pending_intents: Arc::clone(&pending_intents),
+    };
+    let message_rx = DedupMessageReceiver {
+        receiver,
+        pending_intents,
+    };
+    MessageChannel::new(message_tx, message_rx)
+}
+
+#[derive(Debug)]
+struct PendingIntents<Intent> {
+    intents: Mutex<Vec<Intent>>,
+}
+
+impl<Intent> Default for PendingIntents<Intent> {
+    fn default() -> Self {
+        Self {
+            intents: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<Intent> PendingIntents<Intent>
+where
+    Intent: PartialEq,
+{
+    fn lock(&self) -> MutexGuard<'_, Vec<Intent>> {
+        // The intents remain consistent even if a panic occurred while locked.
+        self.intents.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn remove(&self, intent: &Intent) {
+        let mut intents = self.lock();
+        if let Some(index) = intents.iter().position(|pending| pending == intent) {
+            intents.swap_remove(index);
+        }
+    }
+
+    fn remove_received<Effect>(&self, message: &Message<Intent, Effect>) {
+        if let Message::Intent(intent) = message {
+            self.remove(intent);
+        }
+    }
+}
+
+/// Sender of a [`DedupTransport`]
+pub struct DedupMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    sender: MessageSender<Intent, Effect, Transport>,
+    pending_intents: Arc<PendingIntents<Intent>>,
+}
+
+impl<Intent, Effect, Transport> DedupMessageSender<Intent, Effect, Transport>
+where
+    Intent: PartialEq,
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// The number of distinct intents that have not been received yet
+    #[must_use]
+    pub fn pending_intent_count(&self) -> usize {
+        self.pending_intents.lock().len()
+    }
+}
+
+impl<Intent, Effect, Transport> Clone for DedupMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn clone(&self) -> Self {
+        let Self {
+            sender,
+            pending_intents,
+        } = self;
+        Self {
+            sender: sender.clone(),
+            pending_intents: Arc::clone(pending_intents),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for DedupMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupMessageSender").finish_non_exhaustive()
+    }
+}
+
+/// Receiver of a [`DedupTransport`]
+pub struct DedupMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    receiver: MessageReceiver<Intent, Effect, Transport>,
+    pending_intents: Arc<PendingIntents<Intent>>,
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for DedupMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupMessageReceiver")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Intent, Effect, Transport> MessageTransport<Intent, Effect> for DedupTransport<Transport>
+where
+    Intent: PartialEq + Clone,
+    Transport: MessageTransport<Intent, Effect>,
+{
+    type Sender = DedupMessageSender<Intent, Effect, Transport>;
+    type Receiver = DedupMessageReceiver<Intent, Effect, Transport>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        let DedupMessageSender {
+            sender,
+            pending_intents,
+        } = sender;
+        let Message::Intent(intent) = message else {
+            return Transport::try_send(sender, message);
+        };
+        {
+            let mut intents = pending_intents.lock();
+            if intents.contains(&intent) {
+                log_debug!("Dropping duplicate intent");
+                return Ok(());
+            }
+            intents.push(intent.clone());
+        }
+        Transport::try_send(sender, Message::Intent(intent)).inspect_err(|err| {
+            if let Message::Intent(intent) = err.message() {
+                pending_intents.remove(intent);
+            }
+        })
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        Transport::poll_ready(&mut sender.sender, cx)
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        Transport::is_closed(&sender.sender)
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Transport::pending_message_count(&sender.sender)
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        let poll = Transport::poll_next(&mut receiver.receiver, cx);
+        if let Poll::Ready(Some(message)) = &poll {
+            receiver.pending_intents.remove_received(message);
+        }
+        poll
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        let message = Transport::try_recv(&mut receiver.receiver)?;
+        receiver.pending_intents.remove_received(&message);
+        Ok(message)
+    }
+}
@@ -1,24 +1,33 @@
 // SPDX-FileCopyrightText: The infect authors
 // SPDX-License-Identifier: MPL-2.0
 
+use alloc::vec::Vec;
+use core::ops::AddAssign;
+
 use crate::ModelChanged;
 
 /// Outcome of applying an effect to the model
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EffectApplied<Effect, Task, ModelRenderHint> {
     /// A hint for rendering the model
     pub render_hint: ModelRenderHint,
 
-    /// A follow-up task for triggering side-effects
-    pub task: Option<Task>,
+    /// Follow-up tasks for triggering side-effects
+    ///
+    /// Spawned in order.
+    pub tasks: Vec<Task>,
 
-    /// A follow-up effect that will be processed before any queued effects
+    /// Follow-up effects that will be processed in order before any
+    /// queued effects
+    ///
+    /// The follow-up effects of a next effect are processed before its
+    /// remaining siblings, i.e. depth-first.
     ///
     /// Useful for deferring the application of received effects while a
     /// side-effect is pending. When the side-effect finished these deferred
     /// effects could then be recalled one after another before continuing
     /// with the regular message processing.
-    pub next_effect: Option<Effect>,
+    pub next_effects: Vec<Effect>,
 }
 
 impl<Effect, Task, ModelRenderHint> Default for EffectApplied<Effect, Task, ModelRenderHint>
@@ -30,8 +39,8 @@ where
         debug_assert!(!render_hint.should_render_model());
         Self {
             render_hint: Default::default(),
-            task: None,
-            next_effect: None,
+            tasks: Vec::new(),
+            next_effects: Vec::new(),
         }
     }
 }
@@ -53,7 +62,7 @@ where
         T: Into<Task>,
     {
         Self {
-            task: task.into().map(Into::into),
+            tasks: task.into().map(Into::into).into_iter().collect(),
             ..Self::unchanged()
         }
     }
@@ -65,7 +74,20 @@ where
         E: Into<Effect>,
     {
         Self {
-            next_effect: next_effect.into().map(Into::into),
+            next_effects: next_effect.into().map(Into::into).into_iter().collect(),
+            ..Self::unchanged()
+        }
+    }
+
+    /// Render the model with the given hint
+    ///
+    /// Neither dispatches a task nor applies a next effect. Generic
+    /// counterpart of [`Self::maybe_changed()`] and [`Self::render_now()`]
+    /// for custom render hints.
+    #[must_use]
+    pub fn render(render_hint: ModelRenderHint) -> Self {
+        Self {
+            render_hint,
             ..Self::unchanged()
         }
     }
@@ -77,8 +99,8 @@ impl<Effect, Task> EffectApplied<Effect, Task, ModelChanged> {
     pub const fn maybe_changed() -> Self {
         Self {
             render_hint: ModelChanged::MaybeChanged,
-            task: None,
-            next_effect: None,
+            tasks: Vec::new(),
+            next_effects: Vec::new(),
         }
     }
 
@@ -89,7 +111,7 @@ impl<Effect, Task> EffectApplied<Effect, Task, ModelChanged> {
         T: Into<Task>,
     {
         Self {
-            task: task.into().map(Into::into),
+            tasks: task.into().map(Into::into).into_iter().collect(),
             ..Self::maybe_changed()
         }
     }
@@ -101,13 +123,48 @@ impl<Effect, Task> EffectApplied<Effect, Task, ModelChanged> {
         E: Into<Effect>,
     {
         Self {
-            next_effect: next_effect.into().map(Into::into),
+            next_effects: next_effect.into().map(Into::into).into_iter().collect(),
             ..Self::maybe_changed()
         }
     }
+
+    /// Mark the model as unchanged but request rendering
+    ///
+    /// See also: [`ModelChanged::RenderRequested`]
+    #[must_use]
+    pub const fn render_now() -> Self {
+        Self {
+            render_hint: ModelChanged::RenderRequested,
+            tasks: Vec::new(),
+            next_effects: Vec::new(),
+        }
+    }
+
+    /// Request rendering regardless of whether the model has changed
+    ///
+    /// See also: [`ModelChanged::RenderRequested`]
+    #[must_use]
+    pub fn force_render(mut self) -> Self {
+        self.render_hint += ModelChanged::RenderRequested;
+        self
+    }
 }
 
 impl<Effect, Task, ModelRenderHint> EffectApplied<Effect, Task, ModelRenderHint> {
+    /// Dispatch the outcome of a fallible operation
+    ///
+    /// Invokes either `on_ok` or `on_err`, depending on `result`.
+    pub fn from_result<T, E>(
+        result: Result<T, E>,
+        on_ok: impl FnOnce(T) -> Self,
+        on_err: impl FnOnce(E) -> Self,
+    ) -> Self {
+        match result {
+            Ok(ok) => on_ok(ok),
+            Err(err) => on_err(err),
+        }
+    }
+
     /// Map from a differently parameterized type
     pub fn map_from<E, T, M>(from: EffectApplied<E, T, M>) -> Self
     where
@@ -117,16 +174,16 @@ impl<Effect, Task, ModelRenderHint> EffectApplied<Effect, Task, ModelRenderHint>
     {
         let EffectApplied {
             render_hint,
-            task,
-            next_effect,
+            tasks,
+            next_effects,
         } = from;
         let render_hint = render_hint.into();
-        let task = task.map(Into::into);
-        let next_effect = next_effect.map(Into::into);
+        let tasks = tasks.into_iter().map(Into::into).collect();
+        let next_effects = next_effects.into_iter().map(Into::into).collect();
         Self {
             render_hint,
-            task,
-            next_effect,
+            tasks,
+            next_effects,
         }
     }
 
@@ -139,4 +196,300 @@ impl<Effect, Task, ModelRenderHint> EffectApplied<Effect, Task, ModelRenderHint>
     {
         EffectApplied::map_from(self)
     }
+
+    /// Lift into a differently parameterized type by mapping each part
+    ///
+    /// In contrast to [`Self::map_into()`] no conversions between the
+    /// types are required. Useful for composing the outcome of a child
+    /// model into the outcome of its parent, e.g.
+    ///
+    /// ```
+    /// use std::convert::Infallible;
+    ///
+    /// use infect::{
+    ///     EffectApplied, IntentHandled, Model, ModelChanged, ModelRenderHint as _, RenderRegions,
+    /// };
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct Counter(u32);
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum CounterEffect {
+    ///     Increment,
+    ///     IncrementTwice,
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum CounterTask {
+    ///     Persist(u32),
+    /// }
+    ///
+    /// impl Model for Counter {
+    ///     type Intent = CounterEffect;
+    ///     type IntentRejected = Infallible;
+    ///     type Effect = CounterEffect;
+    ///     type Task = CounterTask;
+    ///     type RenderHint = ModelChanged;
+    ///
+    ///     fn handle_intent(
+    ///         &mut self,
+    ///         intent: CounterEffect,
+    ///     ) -> IntentHandled<Infallible, CounterEffect, CounterTask, ModelChanged> {
+    ///         IntentHandled::Accepted(EffectApplied::unchanged_next(intent))
+    ///     }
+    ///
+    ///     fn apply_effect(
+    ///         &mut self,
+    ///         effect: CounterEffect,
+    ///     ) -> EffectApplied<CounterEffect, CounterTask, ModelChanged> {
+    ///         self.0 += 1;
+    ///         match effect {
+    ///             CounterEffect::Increment => {
+    ///                 EffectApplied::maybe_changed_task(CounterTask::Persist(self.0))
+    ///             }
+    ///             CounterEffect::IncrementTwice => {
+    ///                 EffectApplied::maybe_changed_next(CounterEffect::Increment)
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// const TITLE: RenderRegions = RenderRegions::region(0);
+    /// const COUNTER: RenderRegions = RenderRegions::region(1);
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct App {
+    ///     title: String,
+    ///     counter: Counter,
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum AppEffect {
+    ///     Rename(String),
+    ///     Counter(CounterEffect),
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum AppTask {
+    ///     Counter(CounterTask),
+    /// }
+    ///
+    /// impl Model for App {
+    ///     type Intent = AppEffect;
+    ///     type IntentRejected = Infallible;
+    ///     type Effect = AppEffect;
+    ///     type Task = AppTask;
+    ///     type RenderHint = RenderRegions;
+    ///
+    ///     fn handle_intent(
+    ///         &mut self,
+    ///         intent: AppEffect,
+    ///     ) -> IntentHandled<Infallible, AppEffect, AppTask, RenderRegions> {
+    ///         IntentHandled::Accepted(EffectApplied::unchanged_next(intent))
+    ///     }
+    ///
+    ///     fn apply_effect(
+    ///         &mut self,
+    ///         effect: AppEffect,
+    ///     ) -> EffectApplied<AppEffect, AppTask, RenderRegions> {
+    ///         match effect {
+    ///             AppEffect::Rename(title) => {
+    ///                 self.title = title;
+    ///                 EffectApplied::render(TITLE)
+    ///             }
+    ///             // Route the effect to the child model and lift its outcome.
+    ///             AppEffect::Counter(effect) => self.counter.apply_effect(effect).lift(
+    ///                 AppEffect::Counter,
+    ///                 AppTask::Counter,
+    ///                 |render_hint| {
+    ///                     if render_hint.should_render_model() {
+    ///                         COUNTER
+    ///                     } else {
+    ///                         RenderRegions::NONE
+    ///                     }
+    ///                 },
+    ///             ),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut app = App::default();
+    ///
+    /// let effect_applied = app.apply_effect(AppEffect::Counter(CounterEffect::IncrementTwice));
+    /// assert_eq!(app.counter.0, 1);
+    /// assert_eq!(effect_applied.render_hint, COUNTER);
+    /// assert_eq!(
+    ///     effect_applied.next_effects,
+    ///     [AppEffect::Counter(CounterEffect::Increment)],
+    /// );
+    ///
+    /// let effect_applied = app.apply_effect(AppEffect::Counter(CounterEffect::Increment));
+    /// assert_eq!(app.counter.0, 2);
+    /// assert_eq!(
+    ///     effect_applied.tasks,
+    ///     [AppTask::Counter(CounterTask::Persist(2))],
+    /// );
+    /// ```
+    pub fn lift<E, T, M>(
+        self,
+        wrap_effect: impl FnMut(Effect) -> E,
+        wrap_task: impl FnMut(Task) -> T,
+        wrap_render_hint: impl FnOnce(ModelRenderHint) -> M,
+    ) -> EffectApplied<E, T, M> {
+        let Self {
+            render_hint,
+            tasks,
+            next_effects,
+        } = self;
+        EffectApplied {
+            render_hint: wrap_render_hint(render_hint),
+            tasks: tasks.into_iter().map(wrap_task).collect(),
+            next_effects: next_effects.into_iter().map(wrap_effect).collect(),
+        }
+    }
+
+    /// Map only the tasks
+    ///
+    /// See also: [`Self::lift()`]
+    pub fn map_task<T>(
+        self,
+        map_task: impl FnMut(Task) -> T,
+    ) -> EffectApplied<Effect, T, ModelRenderHint> {
+        self.lift(|effect| effect, map_task, |render_hint| render_hint)
+    }
+
+    /// Map only the next effects
+    ///
+    /// See also: [`Self::lift()`]
+    pub fn map_effect<E>(
+        self,
+        map_effect: impl FnMut(Effect) -> E,
+    ) -> EffectApplied<E, Task, ModelRenderHint> {
+        self.lift(map_effect, |task| task, |render_hint| render_hint)
+    }
+
+    /// Map only the render hint
+    ///
+    /// See also: [`Self::lift()`]
+    pub fn map_render_hint<M>(
+        self,
+        map_render_hint: impl FnOnce(ModelRenderHint) -> M,
+    ) -> EffectApplied<Effect, Task, M> {
+        self.lift(|effect| effect, |task| task, map_render_hint)
+    }
+
+    /// Append a task
+    #[must_use]
+    pub fn with_task(mut self, task: impl Into<Task>) -> Self {
+        self.tasks.push(task.into());
+        self
+    }
+
+    /// Append multiple tasks
+    #[must_use]
+    pub fn with_tasks<T>(mut self, tasks: impl IntoIterator<Item = T>) -> Self
+    where
+        T: Into<Task>,
+    {
+        self.tasks.extend(tasks.into_iter().map(Into::into));
+        self
+    }
+
+    /// Append a next effect
+    #[must_use]
+    pub fn then_effect(mut self, next_effect: impl Into<Effect>) -> Self {
+        self.next_effects.push(next_effect.into());
+        self
+    }
+
+    /// Append multiple next effects
+    #[must_use]
+    pub fn then_effects<E>(mut self, next_effects: impl IntoIterator<Item = E>) -> Self
+    where
+        E: Into<Effect>,
+    {
+        self.next_effects
+            .extend(next_effects.into_iter().map(Into::into));
+        self
+    }
+
+    /// Continue building the outcome
+    ///
+    /// Useful for amending the outcome conditionally without breaking
+    /// a chain of builder methods, e.g.
+    ///
+    /// ```no_run
+    /// # use infect::{EffectApplied, ModelChanged};
+    /// # enum Task {
+    /// #     Save,
+    /// # }
+    /// # struct Document {
+    /// #     dirty: bool,
+    /// # }
+    /// # impl Document {
+    /// # fn is_dirty(&self) -> bool {
+    /// #     self.dirty
+    /// # }
+    /// # fn edited(&self) -> EffectApplied<(), Task, ModelChanged> {
+    /// EffectApplied::maybe_changed().and_then(|mut effect_applied| {
+    ///     if self.is_dirty() {
+    ///         effect_applied.push_task(Task::Save);
+    ///     }
+    ///     effect_applied
+    /// })
+    /// # }
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn and_then(self, f: impl FnOnce(Self) -> Self) -> Self {
+        f(self)
+    }
+
+    /// Append a task in place
+    ///
+    /// See also: [`Self::with_task()`]
+    pub fn push_task(&mut self, task: impl Into<Task>) {
+        self.tasks.push(task.into());
+    }
+
+    /// Append a next effect in place
+    ///
+    /// See also: [`Self::then_effect()`]
+    pub fn push_next_effect(&mut self, next_effect: impl Into<Effect>) {
+        self.next_effects.push(next_effect.into());
+    }
+
+    /// Combine the render hint with another render hint in place
+    pub fn bump_render_hint(&mut self, render_hint: impl Into<ModelRenderHint>)
+    where
+        ModelRenderHint: AddAssign,
+    {
+        self.render_hint += render_hint.into();
+    }
+}
+
+/// Outcome of applying an effect as a [`Result`]
+///
+/// Enables the `?` operator in effect handlers: An `Err` contains a
+/// next effect that is applied instead, e.g. for reporting a failure.
+/// The model is then considered as unchanged.
+///
+/// In contrast to [`crate::IntentHandledResult`] the conversion into
+/// [`EffectApplied`] is one-way.
+pub type EffectAppliedResult<Effect, Task, ModelRenderHint> =
+    Result<EffectApplied<Effect, Task, ModelRenderHint>, Effect>;
+
+impl<Effect, Task, ModelRenderHint, E, T, M> From<EffectAppliedResult<E, T, M>>
+    for EffectApplied<Effect, Task, ModelRenderHint>
+where
+    Effect: From<E>,
+    Task: From<T>,
+    ModelRenderHint: crate::ModelRenderHint + From<M>,
+{
+    fn from(res: EffectAppliedResult<E, T, M>) -> Self {
+        match res {
+            Ok(effect_applied) => effect_applied.map_into(),
+            Err(next_effect) => Self::unchanged_next(Effect::from(next_effect)),
+        }
+    }
 }
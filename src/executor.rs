@@ -0,0 +1,451 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{boxed::Box, rc::Rc, sync::Arc};
+use core::{
+    fmt,
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{logging::log_warn, MessageTransport, MpscTransport, TaskContext, TaskExecutor};
+#[cfg(feature = "std")]
+use crate::{task::catch_task_panic, MaybeDebug, TaskError};
+
+type InlineTaskFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+type InlineTaskRunner<Intent, Effect, Task, Transport> = dyn Fn(
+    TaskContext<InlineTaskExecutor<Intent, Effect, Task, Transport>, Intent, Effect, Transport>,
+    Task,
+) -> InlineTaskFuture;
+
+/// Runs tasks synchronously on the current thread
+///
+/// Each task is mapped to a future that is polled once immediately when
+/// spawning the task, using a no-op waker. The future could submit
+/// messages through the provided [`TaskContext`].
+///
+/// Results in a deterministic ordering of messages, e.g. for tests or
+/// simple command line applications that don't need an async runtime.
+///
+/// Tasks must finish without awaiting external events. A future that is
+/// still pending after the first poll will never be woken up. It is dropped
+/// and a warning is logged. Tasks must not wait for capacity in the message
+/// channel, because the channel is not drained while the task is running.
+///
+/// A panicking task unwinds through the caller unless created with
+/// `Self::catching_panics()`, which requires the `std` feature.
+pub struct InlineTaskExecutor<Intent, Effect, Task, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    run_task: Rc<InlineTaskRunner<Intent, Effect, Task, Transport>>,
+}
+
+impl<Intent, Effect, Task, Transport> InlineTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance
+    ///
+    /// The function `run_task` maps each task to a future that is
+    /// executed inline.
+    #[must_use]
+    pub fn new<F>(
+        run_task: impl Fn(TaskContext<Self, Intent, Effect, Transport>, Task) -> F + 'static,
+    ) -> Self
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        Self {
+            run_task: Rc::new(move |context, task| Box::pin(run_task(context, task))),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect, Task, Transport> InlineTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Intent: MaybeDebug + 'static,
+    Effect: MaybeDebug + 'static,
+    Transport: MessageTransport<Intent, Effect> + 'static,
+{
+    /// Create a new instance that reports panicking tasks
+    ///
+    /// Like [`Self::new()`], but panics of tasks are caught and mapped
+    /// into an effect by `on_panic`. The effect is submitted without
+    /// waiting for capacity in the message channel.
+    ///
+    /// Requires the `std` feature.
+    #[must_use]
+    pub fn catching_panics<F>(
+        run_task: impl Fn(TaskContext<Self, Intent, Effect, Transport>, Task) -> F + 'static,
+        on_panic: impl Fn(TaskError) -> Effect + 'static,
+    ) -> Self
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        let on_panic = Rc::new(on_panic);
+        Self::new(move |context, task| {
+            let mut message_port = context.clone_port();
+            let task_future = run_task(context, task);
+            let on_panic = Rc::clone(&on_panic);
+            async move {
+                let Err(task_error) = catch_task_panic(task_future).await else {
+                    return;
+                };
+                log_warn!("Task panicked: {task_error:?}");
+                // Failures are already logged when submitting.
+                let _ = message_port.submit_effect(on_panic(task_error));
+            }
+        })
+    }
+}
+
+impl<Intent, Effect, Task, Transport> Clone for InlineTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn clone(&self) -> Self {
+        let Self { run_task } = self;
+        Self {
+            run_task: Rc::clone(run_task),
+        }
+    }
+}
+
+impl<Intent, Effect, Task, Transport> fmt::Debug
+    for InlineTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InlineTaskExecutor").finish_non_exhaustive()
+    }
+}
+
+impl<Intent, Effect, Task, Transport> TaskExecutor<Self, Transport>
+    for InlineTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    type Intent = Intent;
+    type Effect = Effect;
+    type Task = Task;
+
+    fn spawn_task(&self, context: TaskContext<Self, Intent, Effect, Transport>, task: Task) {
+        let mut task_future = (self.run_task)(context, task);
+        let mut cx = Context::from_waker(Waker::noop());
+        if task_future.as_mut().poll(&mut cx).is_pending() {
+            log_warn!("Dropping pending task - inline tasks must not await external events");
+        }
+    }
+}
+
+/// A boxed future for executing a task on an async runtime
+pub type BoxedTaskFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+type SpawningTaskRunner<Intent, Effect, Task, Transport> = dyn Fn(
+        TaskContext<
+            SpawningTaskExecutor<Intent, Effect, Task, Transport>,
+            Intent,
+            Effect,
+            Transport,
+        >,
+        Task,
+    ) -> BoxedTaskFuture
+    + Send
+    + Sync;
+
+type TaskFutureSpawner = dyn Fn(BoxedTaskFuture) + Send + Sync;
+
+// Both functions are shared behind a single `Arc` to keep cloning cheap.
+struct SpawningTaskExecutorInner<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    run_task: Box<SpawningTaskRunner<Intent, Effect, Task, Transport>>,
+    spawn: Box<TaskFutureSpawner>,
+}
+
+/// Runs tasks concurrently on an async runtime
+///
+/// Each task is mapped to a future that is passed to a runtime-specific
+/// spawn function. The future could submit messages through the provided
+/// [`TaskContext`].
+///
+/// The future of a cancelled tracked task finishes early and drops the
+/// inner future, i.e. the task is aborted at its next suspension point.
+///
+/// Panicking tasks are handled by the runtime, e.g. `tokio` only
+/// logs them. Use `Self::catching_panics()` for reporting them to
+/// the model instead, which requires the `std` feature.
+///
+/// Example for `tokio`:
+///
+/// ```no_run
+/// # use std::future::Future;
+/// # use infect::{MessageTransport, SpawningTaskExecutor};
+/// # mod tokio {
+/// #     pub fn spawn(_: impl std::future::Future<Output = ()> + Send + 'static) {}
+/// # }
+/// # struct Task;
+/// # fn run_task<C>(context: C, _task: Task) -> impl Future<Output = ()> + Send + 'static {
+/// #     drop(context);
+/// #     async {}
+/// # }
+/// # fn example<Transport>() -> SpawningTaskExecutor<(), (), Task, Transport>
+/// # where
+/// #     Transport: MessageTransport<(), ()>,
+/// # {
+/// let task_executor = SpawningTaskExecutor::new(
+///     |context, task| Box::pin(run_task(context, task)),
+///     |task_future| {
+///         tokio::spawn(task_future);
+///     },
+/// );
+/// # task_executor
+/// # }
+/// ```
+pub struct SpawningTaskExecutor<Intent, Effect, Task, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    inner: Arc<SpawningTaskExecutorInner<Intent, Effect, Task, Transport>>,
+}
+
+impl<Intent, Effect, Task, Transport> SpawningTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance
+    ///
+    /// The function `run_task` maps each task to a future that is then
+    /// passed to `spawn`.
+    #[must_use]
+    pub fn new(
+        run_task: impl Fn(TaskContext<Self, Intent, Effect, Transport>, Task) -> BoxedTaskFuture
+            + Send
+            + Sync
+            + 'static,
+        spawn: impl Fn(BoxedTaskFuture) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner: Arc::new(SpawningTaskExecutorInner {
+                run_task: Box::new(run_task),
+                spawn: Box::new(spawn),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect, Task, Transport> SpawningTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Intent: MaybeDebug + Send + 'static,
+    Effect: MaybeDebug + Send + 'static,
+    Transport: MessageTransport<Intent, Effect> + 'static,
+    Transport::Sender: Send + Sync,
+{
+    /// Create a new instance that reports panicking tasks
+    ///
+    /// Like [`Self::new()`], but panics of tasks are caught and mapped
+    /// into an effect by `on_panic`. The effect is then submitted with
+    /// backpressure before the task finishes, see
+    /// [`crate::MessagePort::submit_effect_async()`].
+    ///
+    /// Requires the `std` feature.
+    #[must_use]
+    pub fn catching_panics(
+        run_task: impl Fn(TaskContext<Self, Intent, Effect, Transport>, Task) -> BoxedTaskFuture
+            + Send
+            + Sync
+            + 'static,
+        spawn: impl Fn(BoxedTaskFuture) + Send + Sync + 'static,
+        on_panic: impl Fn(TaskError) -> Effect + Send + Sync + 'static,
+    ) -> Self {
+        let on_panic = Arc::new(on_panic);
+        Self::new(
+            move |context, task| {
+                let mut message_port = context.clone_port();
+                let task_future = run_task(context, task);
+                let on_panic = Arc::clone(&on_panic);
+                Box::pin(async move {
+                    let Err(task_error) = catch_task_panic(task_future).await else {
+                        return;
+                    };
+                    log_warn!("Task panicked: {task_error:?}");
+                    if message_port
+                        .submit_effect_async(on_panic(task_error))
+                        .await
+                        .is_err()
+                    {
+                        log_warn!("Failed to submit panic of task - channel is closed");
+                    }
+                })
+            },
+            spawn,
+        )
+    }
+}
+
+impl<Intent, Effect, Task, Transport> Clone
+    for SpawningTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn clone(&self) -> Self {
+        let Self { inner } = self;
+        Self {
+            inner: Arc::clone(inner),
+        }
+    }
+}
+
+impl<Intent, Effect, Task, Transport> fmt::Debug
+    for SpawningTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpawningTaskExecutor")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Intent, Effect, Task, Transport> TaskExecutor<Self, Transport>
+    for SpawningTaskExecutor<Intent, Effect, Task, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    type Intent = Intent;
+    type Effect = Effect;
+    type Task = Task;
+
+    fn spawn_task(&self, context: TaskContext<Self, Intent, Effect, Transport>, task: Task) {
+        let tracked_task = context.tracked_task();
+        let mut task_future = (self.inner.run_task)(context, task);
+        if let Some(tracked_task) = tracked_task {
+            task_future = Box::pin(poll_fn(move |cx| {
+                if tracked_task.poll_cancelled(cx).is_ready() {
+                    return Poll::Ready(());
+                }
+                task_future.as_mut().poll(cx)
+            }));
+        }
+        (self.inner.spawn)(task_future);
+    }
+}
+
+type BoxedTaskRunner<Intent, Effect, Transport> = dyn FnOnce(
+        TaskContext<BoxedTaskExecutor<Intent, Effect, Transport>, Intent, Effect, Transport>,
+    ) -> BoxedTaskFuture
+    + Send;
+
+/// A type-erased task
+///
+/// Wraps a closure that creates the future for executing the task.
+/// Intended for simple side-effects that don't deserve a dedicated
+/// variant in a typed task enum.
+///
+/// See also: [`BoxedTaskExecutor`]
+pub struct BoxedTask<Intent, Effect, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    run: Box<BoxedTaskRunner<Intent, Effect, Transport>>,
+}
+
+impl<Intent, Effect, Transport> BoxedTask<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new task
+    #[must_use]
+    pub fn new<F>(
+        run: impl FnOnce(
+                TaskContext<BoxedTaskExecutor<Intent, Effect, Transport>, Intent, Effect, Transport>,
+            ) -> F
+            + Send
+            + 'static,
+    ) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            run: Box::new(move |context| Box::pin(run(context))),
+        }
+    }
+
+    fn run(
+        self,
+        context: TaskContext<
+            BoxedTaskExecutor<Intent, Effect, Transport>,
+            Intent,
+            Effect,
+            Transport,
+        >,
+    ) -> BoxedTaskFuture {
+        let Self { run } = self;
+        run(context)
+    }
+}
+
+impl<Intent, Effect, Transport> From<BoxedTaskFuture> for BoxedTask<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn from(task_future: BoxedTaskFuture) -> Self {
+        Self {
+            run: Box::new(move |_context| task_future),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for BoxedTask<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedTask").finish_non_exhaustive()
+    }
+}
+
+/// Runs [`BoxedTask`]s concurrently on an async runtime
+///
+/// Example for `tokio`:
+///
+/// ```no_run
+/// # use infect::{BoxedTaskExecutor, MessageTransport};
+/// # mod tokio {
+/// #     pub fn spawn(_: impl std::future::Future<Output = ()> + Send + 'static) {}
+/// # }
+/// # fn example<Transport>() -> BoxedTaskExecutor<(), (), Transport>
+/// # where
+/// #     Transport: MessageTransport<(), ()>,
+/// # {
+/// let task_executor = BoxedTaskExecutor::for_boxed_tasks(|task_future| {
+///     tokio::spawn(task_future);
+/// });
+/// # task_executor
+/// # }
+/// ```
+///
+/// Typed task enums remain the alternative that could be inspected,
+/// e.g. for testing.
+pub type BoxedTaskExecutor<Intent, Effect, Transport = MpscTransport> =
+    SpawningTaskExecutor<Intent, Effect, BoxedTask<Intent, Effect, Transport>, Transport>;
+
+impl<Intent, Effect, Transport> BoxedTaskExecutor<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance that runs [`BoxedTask`]s
+    ///
+    /// The future of each task is passed to `spawn`.
+    #[must_use]
+    pub fn for_boxed_tasks(spawn: impl Fn(BoxedTaskFuture) + Send + Sync + 'static) -> Self {
+        Self::new(|context, task| task.run(context), spawn)
+    }
+}
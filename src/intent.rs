@@ -1,10 +1,10 @@
 // SPDX-FileCopyrightText: The infect authors
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::EffectApplied;
+use crate::{EffectApplied, ModelChanged};
 
 /// Outcome of handling an intent
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IntentHandled<Rejected, Effect, Task, ModelRenderHint> {
     /// Intent has been rejected
     Rejected(Rejected),
@@ -24,6 +24,17 @@ impl<Rejected, Effect, Task, ModelRenderHint>
         Self::Rejected(rejected.into())
     }
 
+    /// Reject an intent with a reason
+    ///
+    /// Packages both the intent and the reason into a [`RejectedIntent`],
+    /// e.g. for retrying the intent later.
+    pub fn reject_with<I, R>(intent: I, reason: R) -> Self
+    where
+        RejectedIntent<I, R>: Into<Rejected>,
+    {
+        Self::Rejected(RejectedIntent::new(intent, reason).into())
+    }
+
     /// Accept an intent
     pub fn accepted<E, T, M>(effect_applied: EffectApplied<E, T, M>) -> Self
     where
@@ -60,6 +71,66 @@ impl<Rejected, Effect, Task, ModelRenderHint>
     {
         IntentHandled::map_from(self)
     }
+
+    /// Map only the rejection
+    pub fn map_rejected<R>(
+        self,
+        map_rejected: impl FnOnce(Rejected) -> R,
+    ) -> IntentHandled<R, Effect, Task, ModelRenderHint> {
+        match self {
+            Self::Rejected(rejected) => IntentHandled::Rejected(map_rejected(rejected)),
+            Self::Accepted(effect_applied) => IntentHandled::Accepted(effect_applied),
+        }
+    }
+
+    /// Map only the accepted outcome
+    ///
+    /// See also: [`EffectApplied::lift()`]
+    pub fn map_accepted<E, T, M>(
+        self,
+        map_accepted: impl FnOnce(
+            EffectApplied<Effect, Task, ModelRenderHint>,
+        ) -> EffectApplied<E, T, M>,
+    ) -> IntentHandled<Rejected, E, T, M> {
+        match self {
+            Self::Rejected(rejected) => IntentHandled::Rejected(rejected),
+            Self::Accepted(effect_applied) => IntentHandled::Accepted(map_accepted(effect_applied)),
+        }
+    }
+}
+
+impl<Rejected, Effect, Task, ModelRenderHint> IntentHandled<Rejected, Effect, Task, ModelRenderHint>
+where
+    ModelRenderHint: crate::ModelRenderHint,
+{
+    /// Accept an intent without changing the model
+    ///
+    /// See also: [`EffectApplied::unchanged()`]
+    #[must_use]
+    pub fn accepted_unchanged() -> Self {
+        Self::Accepted(EffectApplied::unchanged())
+    }
+
+    /// Accept an intent without changing the model and dispatch a task
+    ///
+    /// See also: [`EffectApplied::unchanged_task()`]
+    #[must_use]
+    pub fn accepted_task<T>(task: impl Into<Option<T>>) -> Self
+    where
+        T: Into<Task>,
+    {
+        Self::Accepted(EffectApplied::unchanged_task(task))
+    }
+}
+
+impl<Rejected, Effect, Task> IntentHandled<Rejected, Effect, Task, ModelChanged> {
+    /// Accept an intent and mark the model as maybe changed
+    ///
+    /// See also: [`EffectApplied::maybe_changed()`]
+    #[must_use]
+    pub const fn accepted_changed() -> Self {
+        Self::Accepted(EffectApplied::maybe_changed())
+    }
 }
 
 impl<Rejected, Effect, Task, ModelRenderHint> From<EffectApplied<Effect, Task, ModelRenderHint>>
@@ -70,6 +141,29 @@ impl<Rejected, Effect, Task, ModelRenderHint> From<EffectApplied<Effect, Task, M
     }
 }
 
+/// A rejected intent together with the reason for the rejection
+///
+/// Could be used as [`crate::Model::IntentRejected`] to return the
+/// original intent to the caller.
+///
+/// See also: [`IntentHandled::reject_with()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RejectedIntent<Intent, Reason> {
+    /// The rejected intent
+    pub intent: Intent,
+
+    /// The reason for the rejection
+    pub reason: Reason,
+}
+
+impl<Intent, Reason> RejectedIntent<Intent, Reason> {
+    /// Create a new instance
+    #[must_use]
+    pub const fn new(intent: Intent, reason: Reason) -> Self {
+        Self { intent, reason }
+    }
+}
+
 /// Isomorphic representation of [`IntentHandled`] as a  [`Result`].
 ///
 /// [`IntentHandled`] can be converted seamlessly from and into this result type.
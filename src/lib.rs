@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: The infect authors
 // SPDX-License-Identifier: MPL-2.0
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(rustdoc::invalid_rust_codeblocks)]
 #![doc = include_str!("../README.md")]
 #![warn(rust_2018_idioms)]
@@ -20,25 +21,135 @@
 // is not needed since the context is obvious.
 #![allow(clippy::default_trait_access)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod ack;
+#[cfg(feature = "std")]
+pub use self::ack::{
+    ack_message_channel, Ack, AckMessagePort, AckMessageReceiver, AckMessageTransport, AckSender,
+    AckTransport, Acked, IntentAck, IntentAcks,
+};
+
+mod async_model;
+pub use self::async_model::{
+    consume_messages_async, consume_messages_async_with_options, AsyncModel, AsyncModelRender,
+};
+
+#[cfg(feature = "broadcast")]
+mod broadcast;
+#[cfg(feature = "broadcast")]
+pub use self::broadcast::EffectBroadcast;
+
+#[cfg(feature = "std")]
+mod correlation;
+#[cfg(feature = "std")]
+pub use self::correlation::{
+    correlated_message_channel, Correlatable, CorrelatedMessagePort, CorrelatedMessageReceiver,
+    CorrelatedMessageSender, CorrelatedMessageTransport, CorrelatedTransport, CorrelationId,
+    EffectResponse, EffectResponses, NoEffectResponse,
+};
+
+#[cfg(feature = "std")]
+mod dedup;
+#[cfg(feature = "std")]
+pub use self::dedup::{
+    dedup_message_channel, DedupMessagePort, DedupMessageReceiver, DedupMessageSender,
+    DedupTransport,
+};
+
 mod effect;
-pub use self::effect::EffectApplied;
+pub use self::effect::{EffectApplied, EffectAppliedResult};
+
+mod executor;
+pub use self::executor::{
+    BoxedTask, BoxedTaskExecutor, BoxedTaskFuture, InlineTaskExecutor, SpawningTaskExecutor,
+};
 
 mod intent;
-pub use self::intent::{IntentHandled, IntentHandledResult};
+pub use self::intent::{IntentHandled, IntentHandledResult, RejectedIntent};
+
+mod logging;
+pub use self::logging::MaybeDebug;
 
 mod message;
 pub use self::message::Message;
 
 mod messaging;
 pub use self::messaging::{
-    message_channel, MessageChannel, MessagePort, MessageReceiver, MessageSender,
+    forward_stream_as_effects, ChannelClosed, DropHandler, DropReason, EffectStream,
+    MessageChannel, MessagePort, MessageReceiver, MessageSender, MessageTransport, MpscTransport,
+    OverflowPolicy, SubmitError, SubmitMessagesError, TryRecvError, TrySendError, WeakMessagePort,
+};
+#[cfg(feature = "std")]
+pub use self::messaging::{
+    message_channel, unbounded_message_channel, MpscReceiver, MpscSender, UnboundedMessageReceiver,
+    UnboundedMessageSender, UnboundedMpscTransport,
 };
 
+mod middleware;
+pub use self::middleware::{process_message_with_middleware, Middleware, MiddlewareOutcome};
+
 mod model;
-pub use self::model::{Model, ModelChanged, ModelRender, ModelRenderHint};
+pub use self::model::{
+    Model, ModelChanged, ModelRender, ModelRenderHint, NoRender, RenderRegions, TryModel,
+};
+
+mod observer;
+pub use self::observer::{ProcessingObserver, ProcessingStats};
+
+mod priority;
+pub use self::priority::{
+    priority_level_channel, Priority, PriorityLevelReceiver, PriorityLevelSender,
+    PriorityLevelTransport, PriorityMessageReceiver, PriorityMessageTransport, PriorityTransport,
+};
 
 mod processing;
-pub use self::processing::{consume_messages, process_message, MessageProcessed, MessagesConsumed};
+#[cfg(feature = "std")]
+pub use self::processing::run_model;
+pub use self::processing::{
+    consume_messages, consume_messages_bounded, consume_messages_coalesced,
+    consume_messages_continue_on_reject, consume_messages_reporting_rejections,
+    consume_messages_until, consume_messages_with_observer, consume_messages_with_options,
+    consume_messages_with_render_hint, consume_messages_with_stats, process_message,
+    process_message_no_render, process_message_with_observer, process_message_with_options,
+    process_message_with_rejected_intent, run_model_with_context, try_consume_messages,
+    try_consume_messages_with_options, MessageProcessed, MessagesConsumed, ProcessingOptions,
+};
+
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "std")]
+pub use self::render::{
+    render_due_scheduler, Clock, DebouncedRender, RenderScheduler, SystemClock,
+};
+
+mod render_chain;
+pub use self::render_chain::{render_chain, RenderAll, RenderChain};
+
+#[cfg(feature = "std")]
+mod runtime;
+#[cfg(feature = "std")]
+pub use self::runtime::{Runtime, RuntimeBuilder};
+
+mod sequenced;
+pub use self::sequenced::{
+    sequenced_message_channel, Sequenced, SequencedMessagePort, SequencedMessageReceiver,
+    SequencedMessageSender, SequencedTransport,
+};
+
+mod snapshot;
+pub use self::snapshot::{process_message_with_snapshot, Snapshot, SnapshotHistory};
 
 mod task;
-pub use self::task::{TaskContext, TaskExecutor};
+pub use self::task::{
+    Backoff, RetryPolicy, TaskContext, TaskError, TaskExecutor, TaskId, TaskTrackingDisabled,
+};
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use self::watch::{ModelWatch, WatchRender};
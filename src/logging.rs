@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+#[cfg(feature = "logging")]
+use core::fmt;
+
+#[cfg(feature = "logging")]
+use crate::TryModel;
+
+/// Types that could be logged
+///
+/// Requires [`fmt::Debug`](core::fmt::Debug) if the `logging` feature is enabled
+/// and is implemented for all types otherwise.
+#[cfg(feature = "logging")]
+pub trait MaybeDebug: fmt::Debug {}
+
+#[cfg(feature = "logging")]
+impl<T> MaybeDebug for T where T: fmt::Debug + ?Sized {}
+
+/// Types that could be logged
+///
+/// Requires [`fmt::Debug`](core::fmt::Debug) if the `logging` feature is enabled
+/// and is implemented for all types otherwise.
+#[cfg(not(feature = "logging"))]
+pub trait MaybeDebug {}
+
+#[cfg(not(feature = "logging"))]
+impl<T> MaybeDebug for T where T: ?Sized {}
+
+/// Prefixes log messages with the label of the model, if any
+///
+/// See also: [`TryModel::label()`]
+#[cfg(feature = "logging")]
+pub(crate) struct LogLabel<'a, M>(pub(crate) &'a M);
+
+#[cfg(feature = "logging")]
+impl<M> fmt::Display for LogLabel<'_, M>
+where
+    M: TryModel,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = self.0.label();
+        if label.is_empty() {
+            return Ok(());
+        }
+        write!(f, "[{label}] ")
+    }
+}
+
+// The following macros expand to nothing if the `logging` feature is disabled.
+
+macro_rules! log_trace {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "logging")]
+        log::trace!($($arg)+);
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "logging")]
+        log::debug!($($arg)+);
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "logging")]
+        log::warn!($($arg)+);
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "logging")]
+        log::error!($($arg)+);
+    };
+}
+
+pub(crate) use {log_debug, log_error, log_trace, log_warn};
@@ -30,3 +30,85 @@ pub enum Message<Intent, Effect> {
     /// dropped somehow.
     Effect(Effect),
 }
+
+impl<Intent, Effect> Message<Intent, Effect> {
+    /// Map both the intent and the effect type
+    ///
+    /// Lifts the message of a child component into the message of its
+    /// parent.
+    #[must_use]
+    pub fn map<I, E>(
+        self,
+        map_intent: impl FnOnce(Intent) -> I,
+        map_effect: impl FnOnce(Effect) -> E,
+    ) -> Message<I, E> {
+        match self {
+            Self::Intent(intent) => Message::Intent(map_intent(intent)),
+            Self::Effect(effect) => Message::Effect(map_effect(effect)),
+        }
+    }
+
+    /// Map the intent type
+    ///
+    /// See also: [`Self::map()`]
+    #[must_use]
+    pub fn map_intent<I>(self, map_intent: impl FnOnce(Intent) -> I) -> Message<I, Effect> {
+        self.map(map_intent, |effect| effect)
+    }
+
+    /// Map the effect type
+    ///
+    /// See also: [`Self::map()`]
+    #[must_use]
+    pub fn map_effect<E>(self, map_effect: impl FnOnce(Effect) -> E) -> Message<Intent, E> {
+        self.map(|intent| intent, map_effect)
+    }
+
+    /// Check if the message is an intent
+    #[must_use]
+    pub const fn is_intent(&self) -> bool {
+        matches!(self, Self::Intent(_))
+    }
+
+    /// Check if the message is an effect
+    #[must_use]
+    pub const fn is_effect(&self) -> bool {
+        matches!(self, Self::Effect(_))
+    }
+
+    /// Borrow the intent
+    #[must_use]
+    pub const fn as_intent(&self) -> Option<&Intent> {
+        match self {
+            Self::Intent(intent) => Some(intent),
+            Self::Effect(_) => None,
+        }
+    }
+
+    /// Borrow the effect
+    #[must_use]
+    pub const fn as_effect(&self) -> Option<&Effect> {
+        match self {
+            Self::Intent(_) => None,
+            Self::Effect(effect) => Some(effect),
+        }
+    }
+
+    /// Convert into the intent
+    #[must_use]
+    pub fn into_intent(self) -> Option<Intent> {
+        match self {
+            Self::Intent(intent) => Some(intent),
+            Self::Effect(_) => None,
+        }
+    }
+
+    /// Convert into the effect
+    #[must_use]
+    pub fn into_effect(self) -> Option<Effect> {
+        match self {
+            Self::Intent(_) => None,
+            Self::Effect(effect) => Some(effect),
+        }
+    }
+}
@@ -1,113 +1,1346 @@
 // SPDX-FileCopyrightText: The infect authors
 // SPDX-License-Identifier: MPL-2.0
 
-use std::fmt;
+use alloc::sync::{Arc, Weak};
+use core::{
+    fmt,
+    future::poll_fn,
+    pin::{pin, Pin},
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
 
+#[cfg(feature = "std")]
+use std::task::ready;
+
+#[cfg(feature = "std")]
 use futures_channel::mpsc;
+use futures_util::{task::AtomicWaker, Stream};
+
+use crate::{
+    logging::{log_debug, log_warn},
+    MaybeDebug, Message, Priority, PriorityMessageTransport,
+};
+#[cfg(feature = "std")]
+use crate::{AckMessageTransport, CorrelatedMessageTransport, EffectResponse, IntentAck};
+
+/// Pluggable channel backend for transporting messages
+///
+/// Multiple [`Self::Sender`]s feed messages into a single
+/// [`Self::Receiver`].
+///
+/// The default implementation is [`MpscTransport`].
+pub trait MessageTransport<Intent, Effect> {
+    /// Sending side of the channel
+    type Sender: Clone;
+
+    /// Receiving side of the channel
+    type Receiver;
+
+    /// Try to send a message without blocking
+    ///
+    /// # Errors
+    ///
+    /// Returns the message if the channel is either full or closed.
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>>;
+
+    /// Poll the sender until it is ready to accept a message
+    ///
+    /// # Errors
+    ///
+    /// Fails if the channel is closed.
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>>;
+
+    /// Check if the channel is closed
+    ///
+    /// Messages sent into a closed channel will be dropped.
+    fn is_closed(sender: &Self::Sender) -> bool;
+
+    /// The number of messages in the channel that have not been received yet
+    ///
+    /// Returns `None` if unknown or not supported by the channel.
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        let _ = sender;
+        None
+    }
+
+    /// Poll the next message
+    ///
+    /// Returns `Poll::Ready(None)` after the channel has been closed
+    /// and all pending messages have been received.
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>>;
+
+    /// Try to receive the next message without blocking
+    ///
+    /// # Errors
+    ///
+    /// Fails if the channel is either empty or closed.
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError>;
+}
+
+/// Failure when trying to send a message
+#[derive(Debug)]
+pub enum TrySendError<Intent, Effect> {
+    /// The channel is full
+    Full(Message<Intent, Effect>),
+
+    /// The channel is closed, i.e. the receiver has been dropped
+    Disconnected(Message<Intent, Effect>),
+}
+
+impl<Intent, Effect> TrySendError<Intent, Effect> {
+    /// The message that could not be sent
+    #[must_use]
+    pub const fn message(&self) -> &Message<Intent, Effect> {
+        match self {
+            Self::Full(message) | Self::Disconnected(message) => message,
+        }
+    }
+
+    /// Recover the message that could not be sent
+    #[must_use]
+    pub fn into_message(self) -> Message<Intent, Effect> {
+        match self {
+            Self::Full(message) | Self::Disconnected(message) => message,
+        }
+    }
+}
+
+/// Failure when submitting a message
+///
+/// Returns the message that could not be delivered to the caller.
+pub type SubmitError<Intent, Effect> = TrySendError<Intent, Effect>;
+
+/// The channel is closed, i.e. the receiver has been dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelClosed;
+
+/// Failure when submitting multiple messages
+///
+/// All messages following the failed message have not been taken from
+/// the iterator.
+#[derive(Debug)]
+pub struct SubmitMessagesError<Intent, Effect> {
+    /// The number of messages that have been submitted before the failure
+    pub submitted_count: usize,
+
+    /// The error of the first message that could not be sent
+    pub error: SubmitError<Intent, Effect>,
+}
+
+/// Failure when trying to receive a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The channel is empty but not closed
+    Empty,
+
+    /// The channel is empty and closed
+    Closed,
+}
+
+/// Bounded `futures_channel::mpsc` channel
+///
+/// The default [`MessageTransport`].
+///
+/// The number of pending messages is tracked by counting sent and
+/// received messages, because the bounded channel doesn't provide it.
+///
+/// Only implements [`MessageTransport`] if the `std` feature is enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MpscTransport;
+
+/// Sender of a [`MpscTransport`]
+#[cfg(feature = "std")]
+pub struct MpscSender<Intent, Effect> {
+    message_tx: mpsc::Sender<Message<Intent, Effect>>,
+    pending_count: Arc<AtomicUsize>,
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect> Clone for MpscSender<Intent, Effect> {
+    fn clone(&self) -> Self {
+        let Self {
+            message_tx,
+            pending_count,
+        } = self;
+        Self {
+            message_tx: message_tx.clone(),
+            pending_count: Arc::clone(pending_count),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect> fmt::Debug for MpscSender<Intent, Effect> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpscSender").finish_non_exhaustive()
+    }
+}
+
+/// Receiver of a [`MpscTransport`]
+#[cfg(feature = "std")]
+pub struct MpscReceiver<Intent, Effect> {
+    message_rx: mpsc::Receiver<Message<Intent, Effect>>,
+    pending_count: Arc<AtomicUsize>,
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect> MpscReceiver<Intent, Effect> {
+    fn received(&self) {
+        // Incremented before sending, i.e. never decremented below zero.
+        self.pending_count.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect> fmt::Debug for MpscReceiver<Intent, Effect> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpscReceiver").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect> MessageTransport<Intent, Effect> for MpscTransport {
+    type Sender = MpscSender<Intent, Effect>;
+    type Receiver = MpscReceiver<Intent, Effect>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        let MpscSender {
+            message_tx,
+            pending_count,
+        } = sender;
+        // Counted upfront, because the message might be received immediately.
+        pending_count.fetch_add(1, Ordering::Relaxed);
+        message_tx.try_send(message).map_err(|err| {
+            pending_count.fetch_sub(1, Ordering::Relaxed);
+            if err.is_full() {
+                TrySendError::Full(err.into_inner())
+            } else {
+                debug_assert!(err.is_disconnected());
+                TrySendError::Disconnected(err.into_inner())
+            }
+        })
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        sender.message_tx.poll_ready(cx).map_err(|err| {
+            debug_assert!(err.is_disconnected());
+            ChannelClosed
+        })
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        sender.message_tx.is_closed()
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Some(sender.pending_count.load(Ordering::Relaxed))
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        let next_message = ready!(Pin::new(&mut receiver.message_rx).poll_next(cx));
+        if next_message.is_some() {
+            receiver.received();
+        }
+        Poll::Ready(next_message)
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        let message = receiver.message_rx.try_recv().map_err(|err| match err {
+            mpsc::TryRecvError::Empty => TryRecvError::Empty,
+            mpsc::TryRecvError::Closed => TryRecvError::Closed,
+        })?;
+        receiver.received();
+        Ok(message)
+    }
+}
+
+/// Unbounded [`futures_channel::mpsc`] channel
+///
+/// Messages are never dropped because the channel is full. The price is
+/// the loss of backpressure: Memory consumption grows without limits if
+/// messages are submitted faster than they are consumed.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnboundedMpscTransport;
+
+#[cfg(feature = "std")]
+impl<Intent, Effect> MessageTransport<Intent, Effect> for UnboundedMpscTransport {
+    type Sender = mpsc::UnboundedSender<Message<Intent, Effect>>;
+    type Receiver = mpsc::UnboundedReceiver<Message<Intent, Effect>>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        sender.unbounded_send(message).map_err(|err| {
+            // An unbounded channel is never full
+            debug_assert!(err.is_disconnected());
+            TrySendError::Disconnected(err.into_inner())
+        })
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        sender.poll_ready(cx).map_err(|err| {
+            debug_assert!(err.is_disconnected());
+            ChannelClosed
+        })
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        sender.is_closed()
+    }
 
-use crate::Message;
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Some(sender.len())
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        Pin::new(receiver).poll_next(cx)
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        receiver.try_recv().map_err(|err| match err {
+            mpsc::TryRecvError::Empty => TryRecvError::Empty,
+            mpsc::TryRecvError::Closed => TryRecvError::Closed,
+        })
+    }
+}
 
 /// Message sender for submitting messages
-pub type MessageSender<Intent, Effect> = mpsc::Sender<Message<Intent, Effect>>;
+pub type MessageSender<Intent, Effect, Transport = MpscTransport> =
+    <Transport as MessageTransport<Intent, Effect>>::Sender;
 
 /// Message receiver for consuming messages
-pub type MessageReceiver<Intent, Effect> = mpsc::Receiver<Message<Intent, Effect>>;
+pub type MessageReceiver<Intent, Effect, Transport = MpscTransport> =
+    <Transport as MessageTransport<Intent, Effect>>::Receiver;
 
 /// Buffered, MPSC message channel
-pub type MessageChannel<Intent, Effect> = (
-    MessageSender<Intent, Effect>,
-    MessageReceiver<Intent, Effect>,
-);
+///
+/// Bundles the [`MessageSender`] with the corresponding [`MessageReceiver`].
+pub struct MessageChannel<Intent, Effect, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    message_tx: MessageSender<Intent, Effect, Transport>,
+    message_rx: MessageReceiver<Intent, Effect, Transport>,
+}
+
+impl<Intent, Effect, Transport> MessageChannel<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance from both ends of a channel
+    #[must_use]
+    pub const fn new(
+        message_tx: MessageSender<Intent, Effect, Transport>,
+        message_rx: MessageReceiver<Intent, Effect, Transport>,
+    ) -> Self {
+        Self {
+            message_tx,
+            message_rx,
+        }
+    }
+
+    /// The sending side
+    #[must_use]
+    pub const fn sender(&self) -> &MessageSender<Intent, Effect, Transport> {
+        &self.message_tx
+    }
+
+    /// The receiving side
+    #[must_use]
+    pub const fn receiver(&self) -> &MessageReceiver<Intent, Effect, Transport> {
+        &self.message_rx
+    }
+
+    /// The receiving side for consuming messages
+    #[must_use]
+    pub fn receiver_mut(&mut self) -> &mut MessageReceiver<Intent, Effect, Transport> {
+        &mut self.message_rx
+    }
+
+    /// Split into sender and receiver
+    ///
+    /// See also: [`Self::split`]
+    #[must_use]
+    pub fn into_parts(
+        self,
+    ) -> (
+        MessageSender<Intent, Effect, Transport>,
+        MessageReceiver<Intent, Effect, Transport>,
+    ) {
+        let Self {
+            message_tx,
+            message_rx,
+        } = self;
+        (message_tx, message_rx)
+    }
+
+    /// Split into a [`MessagePort`] and the receiver
+    ///
+    /// See also: [`Self::into_parts`]
+    #[must_use]
+    pub fn split(
+        self,
+    ) -> (
+        MessagePort<Intent, Effect, Transport>,
+        MessageReceiver<Intent, Effect, Transport>,
+    ) {
+        let (message_tx, message_rx) = self.into_parts();
+        (MessagePort::new_with_transport(message_tx), message_rx)
+    }
+}
+
+impl<Intent, Effect, Transport> From<MessageChannel<Intent, Effect, Transport>>
+    for (
+        MessageSender<Intent, Effect, Transport>,
+        MessageReceiver<Intent, Effect, Transport>,
+    )
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn from(from: MessageChannel<Intent, Effect, Transport>) -> Self {
+        from.into_parts()
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for MessageChannel<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+    MessageSender<Intent, Effect, Transport>: fmt::Debug,
+    MessageReceiver<Intent, Effect, Transport>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            message_tx,
+            message_rx,
+        } = self;
+        f.debug_struct("MessageChannel")
+            .field("message_tx", message_tx)
+            .field("message_rx", message_rx)
+            .finish()
+    }
+}
+
+/// Unbounded message sender for submitting messages
+#[cfg(feature = "std")]
+pub type UnboundedMessageSender<Intent, Effect> =
+    MessageSender<Intent, Effect, UnboundedMpscTransport>;
+
+/// Unbounded message receiver for consuming messages
+#[cfg(feature = "std")]
+pub type UnboundedMessageReceiver<Intent, Effect> =
+    MessageReceiver<Intent, Effect, UnboundedMpscTransport>;
 
 /// Create a buffered, MPSC message channel with limited capacity
 ///
 /// FIFO queue of sent messages that are consumed by a single
 /// [`MessageReceiver`].
+#[cfg(feature = "std")]
 #[must_use]
-pub fn message_channel<Intent, Effect>(
-    capacity: usize,
-) -> (
-    MessageSender<Intent, Effect>,
-    MessageReceiver<Intent, Effect>,
-) {
-    mpsc::channel(capacity)
+pub fn message_channel<Intent, Effect>(capacity: usize) -> MessageChannel<Intent, Effect> {
+    let (message_tx, message_rx) = mpsc::channel(capacity);
+    let pending_count = Arc::new(AtomicUsize::new(0));
+    let message_tx = MpscSender {
+        message_tx,
+        pending_count: Arc::clone(&pending_count),
+    };
+    let message_rx = MpscReceiver {
+        message_rx,
+        pending_count,
+    };
+    MessageChannel::new(message_tx, message_rx)
+}
+
+/// Create a buffered, MPSC message channel with unlimited capacity
+///
+/// FIFO queue of sent messages that are consumed by a single
+/// [`UnboundedMessageReceiver`].
+///
+/// Submitted messages are never dropped because the channel is full.
+/// On the other hand there is no backpressure, i.e. the queue keeps
+/// growing if messages are submitted faster than they are consumed.
+#[cfg(feature = "std")]
+#[must_use]
+pub fn unbounded_message_channel<Intent, Effect>(
+) -> MessageChannel<Intent, Effect, UnboundedMpscTransport> {
+    let (message_tx, message_rx) = mpsc::unbounded();
+    MessageChannel::new(message_tx, message_rx)
+}
+
+/// Behavior when submitting a message into a full channel
+///
+/// Dropping the oldest message is not supported, because only the
+/// receiver is able to remove messages from the channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the submitted message
+    ///
+    /// The message is passed to the [`DropHandler`] before it is
+    /// returned to the caller.
+    #[default]
+    DropNewest,
+
+    /// Reject the submitted message
+    ///
+    /// The message is returned to the caller without invoking the
+    /// [`DropHandler`].
+    Error,
+}
+
+/// The reason why a message has been dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// The channel is full
+    Full,
+
+    /// The channel is closed, i.e. the receiver has been dropped
+    Disconnected,
+}
+
+/// Callback for observing dropped messages
+///
+/// Could be used for collecting metrics or for persisting messages that
+/// would otherwise get lost.
+pub type DropHandler<Intent, Effect> =
+    Arc<dyn Fn(&Message<Intent, Effect>, DropReason) + Send + Sync>;
+
+/// State that is shared by all clones of a [`MessagePort`]
+struct SharedPort<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    // Only used for creating new senders when upgrading a `WeakMessagePort`.
+    message_tx: MessageSender<Intent, Effect, Transport>,
+    port_count: AtomicUsize,
+    // Woken when the last but one port has been dropped.
+    last_port_waker: AtomicWaker,
+}
+
+/// Counted reference to the [`SharedPort`]
+///
+/// Dropped together with the owning [`MessagePort`].
+struct SharedPortRef<Intent, Effect, Transport>(Arc<SharedPort<Intent, Effect, Transport>>)
+where
+    Transport: MessageTransport<Intent, Effect>;
+
+impl<Intent, Effect, Transport> SharedPortRef<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn new(shared: Arc<SharedPort<Intent, Effect, Transport>>) -> Self {
+        shared.port_count.fetch_add(1, Ordering::AcqRel);
+        Self(shared)
+    }
+}
+
+impl<Intent, Effect, Transport> Clone for SharedPortRef<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn clone(&self) -> Self {
+        Self::new(Arc::clone(&self.0))
+    }
+}
+
+impl<Intent, Effect, Transport> Drop for SharedPortRef<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn drop(&mut self) {
+        if self.0.port_count.fetch_sub(1, Ordering::AcqRel) == 2 {
+            self.0.last_port_waker.wake();
+        }
+    }
 }
 
 /// Domain-specific wrapper around a [`MessageSender`]
-#[derive(Debug)]
-pub struct MessagePort<Intent, Effect> {
-    message_tx: MessageSender<Intent, Effect>,
+pub struct MessagePort<Intent, Effect, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    message_tx: MessageSender<Intent, Effect, Transport>,
+    overflow_policy: OverflowPolicy,
+    drop_handler: Option<DropHandler<Intent, Effect>>,
+    shared: SharedPortRef<Intent, Effect, Transport>,
 }
 
+#[cfg(feature = "std")]
 impl<Intent, Effect> MessagePort<Intent, Effect> {
     /// Create a new instance
     #[must_use]
     pub fn new(message_tx: MessageSender<Intent, Effect>) -> Self {
-        Self { message_tx }
+        Self::new_with_transport(message_tx)
+    }
+
+    /// Create a new instance with a custom [`OverflowPolicy`]
+    #[must_use]
+    pub fn new_with_policy(
+        message_tx: MessageSender<Intent, Effect>,
+        overflow_policy: OverflowPolicy,
+    ) -> Self {
+        let mut message_port = Self::new(message_tx);
+        message_port.set_overflow_policy(overflow_policy);
+        message_port
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect> MessagePort<Intent, Effect, UnboundedMpscTransport> {
+    /// Create a new instance for an unbounded channel
+    #[must_use]
+    pub fn new_unbounded(message_tx: UnboundedMessageSender<Intent, Effect>) -> Self {
+        Self::new_with_transport(message_tx)
+    }
+}
+
+impl<Intent, Effect, Transport> MessagePort<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance for a custom [`MessageTransport`]
+    #[must_use]
+    pub fn new_with_transport(message_tx: MessageSender<Intent, Effect, Transport>) -> Self {
+        let shared = SharedPort {
+            message_tx: message_tx.clone(),
+            port_count: AtomicUsize::new(0),
+            last_port_waker: AtomicWaker::new(),
+        };
+        Self {
+            message_tx,
+            overflow_policy: Default::default(),
+            drop_handler: None,
+            shared: SharedPortRef::new(Arc::new(shared)),
+        }
+    }
+
+    /// Install a [`DropHandler`]
+    ///
+    /// The handler is invoked for every message that is dropped by
+    /// [`Self::submit_message()`], i.e. before the message is returned
+    /// to the caller. Messages rejected by [`OverflowPolicy::Error`] are
+    /// not considered as dropped.
+    #[must_use]
+    pub fn with_drop_handler(
+        mut self,
+        drop_handler: impl Fn(&Message<Intent, Effect>, DropReason) + Send + Sync + 'static,
+    ) -> Self {
+        self.drop_handler = Some(Arc::new(drop_handler));
+        self
+    }
+
+    /// The current [`OverflowPolicy`]
+    #[must_use]
+    pub const fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// Replace the [`OverflowPolicy`]
+    pub fn set_overflow_policy(&mut self, overflow_policy: OverflowPolicy) {
+        self.overflow_policy = overflow_policy;
+    }
+
+    /// Check if the channel is closed
+    ///
+    /// Submitting messages into a closed channel is wasted effort,
+    /// because all messages will be dropped.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        Transport::is_closed(&self.message_tx)
+    }
+
+    /// The number of submitted messages that have not been received yet
+    ///
+    /// Returns `None` if not supported by the [`MessageTransport`].
+    #[must_use]
+    pub fn pending_message_count(&self) -> Option<usize> {
+        Transport::pending_message_count(&self.message_tx)
+    }
+
+    /// Create a [`WeakMessagePort`] that doesn't keep the channel open
+    #[must_use]
+    pub fn downgrade(&self) -> WeakMessagePort<Intent, Effect, Transport> {
+        let Self {
+            message_tx: _,
+            overflow_policy,
+            drop_handler,
+            shared,
+        } = self;
+        WeakMessagePort {
+            overflow_policy: *overflow_policy,
+            drop_handler: drop_handler.clone(),
+            shared: Arc::downgrade(&shared.0),
+        }
     }
 
     /// Obtain the inner [`MessageSender`] for the channel
     #[must_use]
-    pub fn into_inner(self) -> MessageSender<Intent, Effect> {
-        let Self { message_tx } = self;
+    pub fn into_inner(self) -> MessageSender<Intent, Effect, Transport> {
+        let Self {
+            message_tx,
+            overflow_policy: _,
+            drop_handler: _,
+            shared: _,
+        } = self;
         message_tx
     }
 }
 
-impl<Intent, Effect> MessagePort<Intent, Effect>
+impl<Intent, Effect, Transport> MessagePort<Intent, Effect, Transport>
 where
-    Intent: fmt::Debug,
-    Effect: fmt::Debug,
+    Intent: MaybeDebug,
+    Effect: MaybeDebug,
+    Transport: MessageTransport<Intent, Effect>,
 {
     /// Enqueue a message into the channel
     ///
-    /// A utility function that detects and logs unexpected send failures
-    /// that the submitter should not be bothered with.
+    /// A utility function that detects and logs unexpected send failures.
     ///
-    /// Submitting a message is a fire-and-forget operation that must
-    /// always succeed. The framework is responsible for dealing with
-    /// unexpected failures.
-    pub fn submit_message(&mut self, message: impl Into<Message<Intent, Effect>>) {
+    /// Submitting a message is meant to be a fire-and-forget operation.
+    /// Callers that don't care about undeliverable messages could simply
+    /// ignore the result. The [`OverflowPolicy`] controls if a full channel
+    /// is considered as an expected condition that drops the message or as
+    /// an error that needs to be handled by the caller.
+    ///
+    /// Dropped messages are passed to the [`DropHandler`] if installed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the message that could not be sent, either because the
+    /// channel is full or because it is closed.
+    pub fn submit_message(
+        &mut self,
+        message: impl Into<Message<Intent, Effect>>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
         let message = message.into();
-        log::debug!("Sending message: {message:?}");
-        if let Err(err) = self.message_tx.try_send(message) {
-            if err.is_disconnected() {
+        log_debug!("Sending message: {message:?}");
+        let Err(err) = Transport::try_send(&mut self.message_tx, message) else {
+            return Ok(());
+        };
+        Err(self.send_failed(err))
+    }
+
+    /// Handle a message that could not be sent
+    fn send_failed(&self, err: TrySendError<Intent, Effect>) -> SubmitError<Intent, Effect> {
+        let drop_reason = match err {
+            TrySendError::Disconnected(_) => {
                 // No receiver
-                log::debug!(
-                    "Dropping message - channel is closed: {message:?}",
-                    message = err.into_inner()
-                );
-            } else if err.is_full() {
-                log::warn!(
-                    "Dropping message - channel is full: {message:?}",
-                    message = err.into_inner()
-                );
-            } else {
-                // This code should be unreachable
-                log::error!("Failed to send message: {err}");
+                log_debug!("Dropping message - channel is closed: {:?}", err.message());
+                Some(DropReason::Disconnected)
             }
+            TrySendError::Full(_) => match self.overflow_policy {
+                OverflowPolicy::DropNewest => {
+                    log_warn!("Dropping message - channel is full: {:?}", err.message());
+                    Some(DropReason::Full)
+                }
+                OverflowPolicy::Error => {
+                    log_warn!(
+                        "Failed to send message - channel is full: {:?}",
+                        err.message()
+                    );
+                    None
+                }
+            },
+        };
+        if let (Some(drop_reason), Some(drop_handler)) = (drop_reason, &self.drop_handler) {
+            drop_handler(err.message(), drop_reason);
         }
+        err
     }
 
     /// Submit an intent
     ///
     /// See also: [`Self::submit_message`]
-    pub fn submit_intent(&mut self, intent: impl Into<Intent>) {
-        self.submit_message(Message::Intent(intent.into()));
+    ///
+    /// # Errors
+    ///
+    /// Returns the message with the intent if it could not be sent.
+    pub fn submit_intent(
+        &mut self,
+        intent: impl Into<Intent>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        self.submit_message(Message::Intent(intent.into()))
     }
 
     /// Submit an effect
     ///
     /// See also: [`Self::submit_message`]
-    pub fn submit_effect(&mut self, effect: impl Into<Effect>) {
-        self.submit_message(Message::Effect(effect.into()));
+    ///
+    /// # Errors
+    ///
+    /// Returns the message with the effect if it could not be sent.
+    pub fn submit_effect(
+        &mut self,
+        effect: impl Into<Effect>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        self.submit_message(Message::Effect(effect.into()))
+    }
+
+    /// Enqueue multiple messages into the channel
+    ///
+    /// Messages are submitted in order until the first failure. On success
+    /// the number of submitted messages is returned.
+    ///
+    /// In contrast to [`Self::submit_message`] undeliverable messages are
+    /// never passed to the [`DropHandler`], independent of the
+    /// [`OverflowPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the number of submitted messages together with the first
+    /// message that could not be sent. The remaining messages are dropped
+    /// together with the iterator, unless the caller passes the iterator
+    /// by reference, e.g. `messages.by_ref()`, for resubmitting them later.
+    pub fn submit_messages<M>(
+        &mut self,
+        messages: impl IntoIterator<Item = M>,
+    ) -> Result<usize, SubmitMessagesError<Intent, Effect>>
+    where
+        M: Into<Message<Intent, Effect>>,
+    {
+        let mut submitted_count = 0;
+        for message in messages {
+            if let Err(error) = Transport::try_send(&mut self.message_tx, message.into()) {
+                log_debug!(
+                    "Stopped sending messages after {submitted_count} message(s): {error:?}"
+                );
+                return Err(SubmitMessagesError {
+                    submitted_count,
+                    error,
+                });
+            }
+            submitted_count += 1;
+        }
+        log_debug!("Sent {submitted_count} message(s)");
+        Ok(submitted_count)
+    }
+
+    /// Submit multiple intents
+    ///
+    /// See also: [`Self::submit_messages`]
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::submit_messages`].
+    pub fn submit_intents<I>(
+        &mut self,
+        intents: impl IntoIterator<Item = I>,
+    ) -> Result<usize, SubmitMessagesError<Intent, Effect>>
+    where
+        I: Into<Intent>,
+    {
+        self.submit_messages(
+            intents
+                .into_iter()
+                .map(|intent| Message::Intent(intent.into())),
+        )
+    }
+
+    /// Submit multiple effects
+    ///
+    /// See also: [`Self::submit_messages`]
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::submit_messages`].
+    pub fn submit_effects<E>(
+        &mut self,
+        effects: impl IntoIterator<Item = E>,
+    ) -> Result<usize, SubmitMessagesError<Intent, Effect>>
+    where
+        E: Into<Effect>,
+    {
+        self.submit_messages(
+            effects
+                .into_iter()
+                .map(|effect| Message::Effect(effect.into())),
+        )
+    }
+
+    /// Enqueue a message into the channel and wait for capacity if needed
+    ///
+    /// In contrast to [`Self::submit_message`] the message is not dropped
+    /// if the channel is full. Instead, the caller is suspended until the
+    /// channel is able to accept the message. Only suitable for producers
+    /// that could afford to be blocked by backpressure.
+    ///
+    /// # Errors
+    ///
+    /// Returns the message as [`TrySendError::Disconnected`] if the
+    /// channel is closed.
+    pub async fn submit_message_async(
+        &mut self,
+        message: impl Into<Message<Intent, Effect>>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        let mut message = message.into();
+        log_debug!("Sending message: {message:?}");
+        while poll_fn(|cx| Transport::poll_ready(&mut self.message_tx, cx))
+            .await
+            .is_ok()
+        {
+            match Transport::try_send(&mut self.message_tx, message) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Full(full)) => {
+                    // Capacity has been claimed by another sender in the meantime
+                    message = full;
+                }
+                Err(TrySendError::Disconnected(disconnected)) => {
+                    message = disconnected;
+                    break;
+                }
+            }
+        }
+        log_debug!("Failed to send message - channel is closed: {message:?}");
+        Err(TrySendError::Disconnected(message))
+    }
+
+    /// Submit an intent and wait for capacity if needed
+    ///
+    /// See also: [`Self::submit_message_async`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the message with the intent if the channel is closed.
+    pub async fn submit_intent_async(
+        &mut self,
+        intent: impl Into<Intent>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        self.submit_message_async(Message::Intent(intent.into()))
+            .await
+    }
+
+    /// Submit an effect and wait for capacity if needed
+    ///
+    /// See also: [`Self::submit_message_async`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the message with the effect if the channel is closed.
+    pub async fn submit_effect_async(
+        &mut self,
+        effect: impl Into<Effect>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        self.submit_message_async(Message::Effect(effect.into()))
+            .await
     }
 }
 
-impl<Intent, Effect> Clone for MessagePort<Intent, Effect> {
+impl<Intent, Effect, Transport> MessagePort<Intent, Effect, Transport>
+where
+    Intent: MaybeDebug,
+    Effect: MaybeDebug,
+    Transport: PriorityMessageTransport<Intent, Effect>,
+{
+    /// Enqueue a message with a [`Priority`] into the channel
+    ///
+    /// Messages with a higher priority are delivered before all pending
+    /// messages with a lower priority, e.g. for preempting a backlog of
+    /// progress updates with a cancellation.
+    ///
+    /// See also: [`Self::submit_message`], [`crate::priority_level_channel()`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the message that could not be sent, either because the
+    /// channel is full or because it is closed.
+    pub fn submit_message_with_priority(
+        &mut self,
+        message: impl Into<Message<Intent, Effect>>,
+        priority: Priority,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        let message = message.into();
+        log_debug!("Sending message with priority {priority:?}: {message:?}");
+        let Err(err) = Transport::try_send_with_priority(&mut self.message_tx, message, priority)
+        else {
+            return Ok(());
+        };
+        Err(self.send_failed(err))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect, Transport> MessagePort<Intent, Effect, Transport>
+where
+    Intent: MaybeDebug,
+    Effect: MaybeDebug,
+    Transport: AckMessageTransport<Intent, Effect>,
+{
+    /// Enqueue an intent and get notified after it has been handled
+    ///
+    /// The returned future resolves with [`crate::Ack::Accepted`],
+    /// [`crate::Ack::Rejected`], or [`crate::Ack::Failed`] after the whole
+    /// message has been processed, or with [`crate::Ack::Dropped`] if it
+    /// has been discarded.
+    ///
+    /// See also: [`crate::AckTransport`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the message that could not be sent, either because the
+    /// channel is full or because it is closed.
+    pub fn submit_intent_ack(
+        &mut self,
+        intent: impl Into<Intent>,
+    ) -> Result<IntentAck, SubmitError<Intent, Effect>> {
+        let intent = intent.into();
+        log_debug!("Sending intent with acknowledgement: {intent:?}");
+        let (ack_tx, intent_ack) = IntentAck::channel();
+        let Err(err) = Transport::try_send_with_ack(&mut self.message_tx, intent, ack_tx) else {
+            return Ok(intent_ack);
+        };
+        Err(self.send_failed(err))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Intent, Effect, Transport> MessagePort<Intent, Effect, Transport>
+where
+    Intent: MaybeDebug,
+    Effect: MaybeDebug,
+    Transport: CorrelatedMessageTransport<Intent, Effect>,
+{
+    /// Enqueue an intent and await the effect that responds to it
+    ///
+    /// The intent is stamped with a new [`crate::CorrelationId`] before
+    /// it is sent. The returned future resolves with the first applied
+    /// effect that carries the same correlation id.
+    ///
+    /// See also: [`crate::CorrelatedTransport`], [`crate::Correlatable`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the message that could not be sent, either because the
+    /// channel is full or because it is closed.
+    pub fn submit_and_await_effect(
+        &mut self,
+        intent: impl Into<Intent>,
+    ) -> Result<EffectResponse<Effect>, SubmitError<Intent, Effect>> {
+        let intent = intent.into();
+        log_debug!("Sending request: {intent:?}");
+        Transport::try_send_request(&mut self.message_tx, intent)
+            .map_err(|err| self.send_failed(err))
+    }
+}
+
+impl<Intent, Effect, Transport> Clone for MessagePort<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn clone(&self) -> Self {
+        let Self {
+            message_tx,
+            overflow_policy,
+            drop_handler,
+            shared,
+        } = self;
+        Self {
+            message_tx: message_tx.clone(),
+            overflow_policy: *overflow_policy,
+            drop_handler: drop_handler.clone(),
+            shared: shared.clone(),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for MessagePort<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+    MessageSender<Intent, Effect, Transport>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            message_tx,
+            overflow_policy,
+            drop_handler,
+            shared: _,
+        } = self;
+        f.debug_struct("MessagePort")
+            .field("message_tx", message_tx)
+            .field("overflow_policy", overflow_policy)
+            .field(
+                "drop_handler",
+                &drop_handler.as_ref().map(|_| "DropHandler"),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Stream`] of only the effects that are received from a channel
+///
+/// Enables read-side projections that are only interested in effects,
+/// e.g. for journaling all effects. Received intents are passed to a
+/// handler that could either drop them or forward them into another
+/// channel, e.g.
+///
+/// ```no_run
+/// # use futures_util::StreamExt as _;
+/// # use infect::{EffectStream, MaybeDebug, MessagePort, MessageReceiver, MessageTransport};
+/// # struct Journal;
+/// # impl Journal {
+/// #     async fn append<Effect>(&self, _effect: Effect) {}
+/// # }
+/// # async fn example<Intent, Effect, Transport>(
+/// #     message_rx: MessageReceiver<Intent, Effect, Transport>,
+/// #     mut intent_port: MessagePort<Intent, Effect, Transport>,
+/// #     journal: Journal,
+/// # ) where
+/// #     Intent: MaybeDebug,
+/// #     Effect: MaybeDebug,
+/// #     Transport: MessageTransport<Intent, Effect>,
+/// #     MessagePort<Intent, Effect, Transport>: Unpin,
+/// #     MessageReceiver<Intent, Effect, Transport>: Unpin,
+/// # {
+/// let effects: EffectStream<_, _, Transport, _> =
+///     EffectStream::with_intent_handler(message_rx, move |intent| {
+///         let _ = intent_port.submit_intent(intent);
+///     });
+/// effects.for_each(|effect| journal.append(effect)).await;
+/// # }
+/// ```
+pub struct EffectStream<Intent, Effect, Transport = MpscTransport, F = fn(Intent)>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    message_rx: MessageReceiver<Intent, Effect, Transport>,
+    on_intent: F,
+}
+
+impl<Intent, Effect, Transport> EffectStream<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance that drops all intents
+    #[must_use]
+    pub fn new(message_rx: MessageReceiver<Intent, Effect, Transport>) -> Self {
+        Self::with_intent_handler(message_rx, drop)
+    }
+}
+
+impl<Intent, Effect, Transport, F> EffectStream<Intent, Effect, Transport, F>
+where
+    Transport: MessageTransport<Intent, Effect>,
+    F: FnMut(Intent),
+{
+    /// Create a new instance that passes all intents to a handler
+    #[must_use]
+    pub const fn with_intent_handler(
+        message_rx: MessageReceiver<Intent, Effect, Transport>,
+        on_intent: F,
+    ) -> Self {
+        Self {
+            message_rx,
+            on_intent,
+        }
+    }
+
+    /// Recover the receiver
+    #[must_use]
+    pub fn into_inner(self) -> MessageReceiver<Intent, Effect, Transport> {
+        let Self {
+            message_rx,
+            on_intent: _,
+        } = self;
+        message_rx
+    }
+}
+
+impl<Intent, Effect, Transport, F> Stream for EffectStream<Intent, Effect, Transport, F>
+where
+    Transport: MessageTransport<Intent, Effect>,
+    MessageReceiver<Intent, Effect, Transport>: Unpin,
+    F: FnMut(Intent) + Unpin,
+{
+    type Item = Effect;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Self {
+            message_rx,
+            on_intent,
+        } = self.get_mut();
+        loop {
+            match Transport::poll_next(message_rx, cx) {
+                Poll::Ready(Some(Message::Effect(effect))) => return Poll::Ready(Some(effect)),
+                Poll::Ready(Some(Message::Intent(intent))) => on_intent(intent),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<Intent, Effect, Transport, F> fmt::Debug for EffectStream<Intent, Effect, Transport, F>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EffectStream").finish_non_exhaustive()
+    }
+}
+
+/// Forward all items of a [`Stream`] as effects
+///
+/// Feeds an external source of events into the message loop. Each item
+/// is converted into an effect and submitted with backpressure, see
+/// [`MessagePort::submit_effect_async`].
+///
+/// The returned future could be spawned as a task. It is _cancellation safe_:
+/// The next item is only pulled from the stream after the channel is ready
+/// to accept it.
+///
+/// # Errors
+///
+/// Returns [`ChannelClosed`] if forwarding stopped before the stream has
+/// ended, because the channel has been closed.
+pub async fn forward_stream_as_effects<Intent, Effect, Transport, S>(
+    stream: S,
+    mut message_port: MessagePort<Intent, Effect, Transport>,
+) -> Result<(), ChannelClosed>
+where
+    Intent: MaybeDebug,
+    Effect: MaybeDebug,
+    Transport: MessageTransport<Intent, Effect>,
+    S: Stream,
+    S::Item: Into<Effect>,
+{
+    let mut stream = pin!(stream);
+    loop {
+        if poll_fn(|cx| Transport::poll_ready(&mut message_port.message_tx, cx))
+            .await
+            .is_err()
+        {
+            log_debug!("Stopped forwarding stream - channel is closed");
+            return Err(ChannelClosed);
+        }
+        let Some(item) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await else {
+            log_debug!("Stopped forwarding stream - stream has ended");
+            return Ok(());
+        };
+        // Capacity has already been reserved and this should not suspend.
+        if message_port.submit_effect_async(item).await.is_err() {
+            return Err(ChannelClosed);
+        }
+    }
+}
+
+/// A [`MessagePort`] that doesn't keep the channel open
+///
+/// Obtained by [`MessagePort::downgrade()`]. Could be stored in long-lived
+/// background tasks that should not prevent the channel from closing.
+pub struct WeakMessagePort<Intent, Effect, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    overflow_policy: OverflowPolicy,
+    drop_handler: Option<DropHandler<Intent, Effect>>,
+    shared: Weak<SharedPort<Intent, Effect, Transport>>,
+}
+
+impl<Intent, Effect, Transport> WeakMessagePort<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Try to obtain a [`MessagePort`]
+    ///
+    /// Returns `None` if either all [`MessagePort`]s have been dropped
+    /// or if the channel has been closed.
+    #[must_use]
+    pub fn upgrade(&self) -> Option<MessagePort<Intent, Effect, Transport>> {
+        let Self {
+            overflow_policy,
+            drop_handler,
+            shared,
+        } = self;
+        let shared = shared.upgrade()?;
+        if Transport::is_closed(&shared.message_tx) {
+            return None;
+        }
+        Some(MessagePort {
+            message_tx: shared.message_tx.clone(),
+            overflow_policy: *overflow_policy,
+            drop_handler: drop_handler.clone(),
+            shared: SharedPortRef::new(shared),
+        })
+    }
+
+    /// Poll until no more messages could be received from the channel
+    /// unless submitted through the last remaining [`MessagePort`]
+    ///
+    /// Ready when at most a single [`MessagePort`] remains and the channel
+    /// is empty. Never ready if the [`MessageTransport`] doesn't report
+    /// the number of pending messages.
+    pub(crate) fn poll_last_port_idle(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let Some(shared) = self.shared.upgrade() else {
+            return Poll::Ready(());
+        };
+        shared.last_port_waker.register(cx.waker());
+        if shared.port_count.load(Ordering::Acquire) > 1
+            || Transport::pending_message_count(&shared.message_tx) != Some(0)
+        {
+            return Poll::Pending;
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<Intent, Effect, Transport> Clone for WeakMessagePort<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
     fn clone(&self) -> Self {
-        let Self { message_tx } = self;
-        let message_tx = message_tx.clone();
-        Self { message_tx }
+        let Self {
+            overflow_policy,
+            drop_handler,
+            shared,
+        } = self;
+        Self {
+            overflow_policy: *overflow_policy,
+            drop_handler: drop_handler.clone(),
+            shared: Weak::clone(shared),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for WeakMessagePort<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            overflow_policy,
+            drop_handler,
+            shared: _,
+        } = self;
+        f.debug_struct("WeakMessagePort")
+            .field("overflow_policy", overflow_policy)
+            .field(
+                "drop_handler",
+                &drop_handler.as_ref().map(|_| "DropHandler"),
+            )
+            .finish_non_exhaustive()
     }
 }
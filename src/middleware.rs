@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::boxed::Box;
+
+use crate::{
+    logging::log_debug, process_message, MaybeDebug, Message, MessageProcessed, MessageTransport,
+    ModelRender, TaskContext, TaskExecutor, TryModel,
+};
+
+/// Outcome of [`Middleware::before()`]
+#[derive(Debug, Clone)]
+pub enum MiddlewareOutcome<Intent, Effect, IntentRejected> {
+    /// Continue with either the original or a replacement message
+    Continue(Message<Intent, Effect>),
+
+    /// Discard the message silently
+    Drop,
+
+    /// Reject the message without passing it to the model
+    Reject(IntentRejected),
+}
+
+/// Intercepts messages before they are passed to the model
+///
+/// Intended for cross-cutting concerns like logging, access checks, or
+/// rewriting intents.
+///
+/// Multiple middleware are invoked in order when combined into a tuple
+/// `(first, second)` or a slice. Each middleware receives the outcome of
+/// its predecessor. The first middleware that doesn't continue stops
+/// the chain.
+///
+/// The unit type `()` passes all messages unchanged.
+///
+#[cfg_attr(
+    feature = "std",
+    doc = "See also: [`process_message_with_middleware`], [`crate::RuntimeBuilder::middleware()`]"
+)]
+#[cfg_attr(
+    not(feature = "std"),
+    doc = "See also: [`process_message_with_middleware`]"
+)]
+pub trait Middleware<M: TryModel> {
+    /// Inspect, replace, drop, or reject a message
+    fn before(
+        &mut self,
+        message: Message<M::Intent, M::Effect>,
+    ) -> MiddlewareOutcome<M::Intent, M::Effect, M::IntentRejected>;
+}
+
+impl<M: TryModel> Middleware<M> for () {
+    fn before(
+        &mut self,
+        message: Message<M::Intent, M::Effect>,
+    ) -> MiddlewareOutcome<M::Intent, M::Effect, M::IntentRejected> {
+        MiddlewareOutcome::Continue(message)
+    }
+}
+
+impl<M, T> Middleware<M> for Box<T>
+where
+    M: TryModel,
+    T: Middleware<M> + ?Sized,
+{
+    fn before(
+        &mut self,
+        message: Message<M::Intent, M::Effect>,
+    ) -> MiddlewareOutcome<M::Intent, M::Effect, M::IntentRejected> {
+        (**self).before(message)
+    }
+}
+
+impl<M, T> Middleware<M> for [T]
+where
+    M: TryModel,
+    T: Middleware<M>,
+{
+    fn before(
+        &mut self,
+        mut message: Message<M::Intent, M::Effect>,
+    ) -> MiddlewareOutcome<M::Intent, M::Effect, M::IntentRejected> {
+        for middleware in self {
+            match middleware.before(message) {
+                MiddlewareOutcome::Continue(next_message) => message = next_message,
+                outcome @ (MiddlewareOutcome::Drop | MiddlewareOutcome::Reject(_)) => {
+                    return outcome;
+                }
+            }
+        }
+        MiddlewareOutcome::Continue(message)
+    }
+}
+
+impl<M, A, B> Middleware<M> for (A, B)
+where
+    M: TryModel,
+    A: Middleware<M>,
+    B: Middleware<M>,
+{
+    fn before(
+        &mut self,
+        message: Message<M::Intent, M::Effect>,
+    ) -> MiddlewareOutcome<M::Intent, M::Effect, M::IntentRejected> {
+        let (first, second) = self;
+        match first.before(message) {
+            MiddlewareOutcome::Continue(message) => second.before(message),
+            outcome @ (MiddlewareOutcome::Drop | MiddlewareOutcome::Reject(_)) => outcome,
+        }
+    }
+}
+
+/// Process a single message after passing it through middleware
+///
+/// Dropped messages result in [`MessageProcessed::NoProgress`] without
+/// rendering the model. Rejected messages result in
+/// [`MessageProcessed::IntentRejected`].
+///
+/// See also: [`process_message`]
+#[must_use]
+pub fn process_message_with_middleware<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    message: Message<M::Intent, M::Effect>,
+    middleware: &mut (impl Middleware<M> + ?Sized),
+) -> MessageProcessed<M::IntentRejected, M::RenderHint, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    match middleware.before(message) {
+        MiddlewareOutcome::Continue(message) => {
+            process_message(task_context, model, render_model, message)
+        }
+        MiddlewareOutcome::Drop => {
+            log_debug!("Message dropped by middleware");
+            MessageProcessed::NoProgress {
+                rendered: false,
+                render_hint: Default::default(),
+                effect_discarded: false,
+            }
+        }
+        MiddlewareOutcome::Reject(intent_rejected) => {
+            log_debug!("Message rejected by middleware: {intent_rejected:?}");
+            MessageProcessed::IntentRejected(intent_rejected)
+        }
+    }
+}
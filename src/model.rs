@@ -1,12 +1,15 @@
 // SPDX-FileCopyrightText: The infect authors
 // SPDX-License-Identifier: MPL-2.0
 
-use std::{
+use core::{
+    cmp::Ordering,
+    convert::Infallible,
     fmt,
+    marker::PhantomData,
     ops::{Add, AddAssign},
 };
 
-use crate::{EffectApplied, IntentHandled};
+use crate::{EffectApplied, IntentHandled, MaybeDebug};
 
 /// A stateful model
 ///
@@ -18,23 +21,26 @@ use crate::{EffectApplied, IntentHandled};
 /// outer system state.
 ///
 /// All associated types are supposed to be a simple value types that
-/// implement [`std::fmt::Debug`] for logging purposes.
+/// implement [`core::fmt::Debug`] for logging purposes. This requirement
+/// is relaxed if the `logging` feature is disabled, see [`MaybeDebug`].
+///
+/// Implement [`TryModel`] instead if applying effects could fail.
 pub trait Model {
     /// An intent type that this model handles
-    type Intent: fmt::Debug;
+    type Intent: MaybeDebug;
 
     /// The result of rejecting an intent
     ///
     /// Rejecting an intent by returning the same type would
     /// be one option. In addition the model may also provide
     /// the reason for the rejection.
-    type IntentRejected: fmt::Debug;
+    type IntentRejected: MaybeDebug;
 
     /// An effect type that could be applied to this model
-    type Effect: fmt::Debug;
+    type Effect: MaybeDebug;
 
     /// A task type for inducing side-effects
-    type Task: fmt::Debug;
+    type Task: MaybeDebug;
 
     /// A hint for rendering
     ///
@@ -66,6 +72,157 @@ pub trait Model {
         &mut self,
         effect: Self::Effect,
     ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint>;
+
+    /// Start the model
+    ///
+    /// Invoked once by [`crate::run_model_with_context()`] before
+    /// processing the first message. The result is processed like the
+    /// result of any other effect, e.g. for spawning an initial task.
+    ///
+    /// Does nothing by default.
+    #[must_use]
+    fn on_start(&mut self) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        EffectApplied::unchanged()
+    }
+
+    /// Stop the model
+    ///
+    /// Invoked once by [`crate::run_model_with_context()`] after consuming
+    /// messages has terminated, e.g. for releasing resources.
+    ///
+    /// Does nothing by default.
+    fn on_stop(&mut self) {}
+
+    /// A label for identifying the model in log messages
+    ///
+    /// Log messages about processing messages are prefixed with the
+    /// label, e.g. for distinguishing multiple message loops that run
+    /// in the same process. Only used if the `logging` feature is
+    /// enabled.
+    ///
+    /// Returns an empty string by default, i.e. no prefix.
+    #[must_use]
+    // Implementations may borrow the label from the model.
+    #[allow(clippy::unnecessary_literal_bound)]
+    fn label(&self) -> &str {
+        ""
+    }
+}
+
+/// A stateful model with fallible effects
+///
+/// Like [`Model`], but applying an effect could fail, e.g. if an
+/// invariant would be violated. Failures are reported as
+/// [`crate::MessageProcessed::EffectFailed`] instead of panicking.
+///
+/// Messages are processed through this trait. It is implemented for
+/// all types that implement [`Model`], i.e. with effects that never
+/// fail.
+pub trait TryModel {
+    /// See also: [`Model::Intent`]
+    type Intent: MaybeDebug;
+
+    /// See also: [`Model::IntentRejected`]
+    type IntentRejected: MaybeDebug;
+
+    /// See also: [`Model::Effect`]
+    type Effect: MaybeDebug;
+
+    /// The error of failing to apply an effect
+    ///
+    /// The model must remain consistent, i.e. effects are applied
+    /// atomically.
+    type ApplyError: MaybeDebug;
+
+    /// See also: [`Model::Task`]
+    type Task: MaybeDebug;
+
+    /// See also: [`Model::RenderHint`]
+    type RenderHint: ModelRenderHint;
+
+    /// Handle an intent
+    ///
+    /// See also: [`Model::handle_intent()`]
+    #[must_use]
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint>;
+
+    /// Try to apply an effect to the model
+    ///
+    /// See also: [`Model::apply_effect()`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the effect could not be applied. The model
+    /// must remain unchanged.
+    #[allow(clippy::type_complexity)]
+    fn try_apply_effect(
+        &mut self,
+        effect: Self::Effect,
+    ) -> Result<EffectApplied<Self::Effect, Self::Task, Self::RenderHint>, Self::ApplyError>;
+
+    /// Start the model
+    ///
+    /// See also: [`Model::on_start()`]
+    #[must_use]
+    fn on_start(&mut self) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        EffectApplied::unchanged()
+    }
+
+    /// Stop the model
+    ///
+    /// See also: [`Model::on_stop()`]
+    fn on_stop(&mut self) {}
+
+    /// A label for identifying the model in log messages
+    ///
+    /// See also: [`Model::label()`]
+    #[must_use]
+    // Implementations may borrow the label from the model.
+    #[allow(clippy::unnecessary_literal_bound)]
+    fn label(&self) -> &str {
+        ""
+    }
+}
+
+impl<M> TryModel for M
+where
+    M: Model,
+{
+    type Intent = M::Intent;
+    type IntentRejected = M::IntentRejected;
+    type Effect = M::Effect;
+    type ApplyError = Infallible;
+    type Task = M::Task;
+    type RenderHint = M::RenderHint;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        Model::handle_intent(self, intent)
+    }
+
+    fn try_apply_effect(
+        &mut self,
+        effect: Self::Effect,
+    ) -> Result<EffectApplied<Self::Effect, Self::Task, Self::RenderHint>, Self::ApplyError> {
+        Ok(Model::apply_effect(self, effect))
+    }
+
+    fn on_start(&mut self) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        Model::on_start(self)
+    }
+
+    fn on_stop(&mut self) {
+        Model::on_stop(self);
+    }
+
+    fn label(&self) -> &str {
+        Model::label(self)
+    }
 }
 
 /// Render the model after changed
@@ -76,24 +233,134 @@ pub trait Model {
 /// Both approaches could be combined in flexible ways.
 pub trait ModelRender {
     /// The model
-    type Model: Model;
+    type Model: TryModel;
+
+    /// Decide if the model should be rendered
+    ///
+    /// Consulted before invoking [`Self::render_model()`] if the render
+    /// hint requests rendering. Allows to veto rendering independent of
+    /// the model, e.g. if the target surface is currently not visible.
+    ///
+    /// Returns `true` by default.
+    #[must_use]
+    fn should_render(
+        &self,
+        model: &Self::Model,
+        hint: &<Self::Model as TryModel>::RenderHint,
+    ) -> bool {
+        let _ = (model, hint);
+        true
+    }
 
     /// Render the model after changed
     ///
-    /// Might return an observed intent that is enqueued as a message
-    /// and handled in turn later.
+    /// Might return observed intents that are enqueued as messages in
+    /// order and handled in turn later. Any collection of intents could be
+    /// returned, e.g. an [`Option`] if at most a single intent is observed.
     #[must_use]
     fn render_model(
         &mut self,
         model: &Self::Model,
-        hint: <Self::Model as Model>::RenderHint,
-    ) -> Option<<Self::Model as Model>::Intent>;
+        hint: <Self::Model as TryModel>::RenderHint,
+    ) -> impl IntoIterator<Item = <Self::Model as TryModel>::Intent>;
+
+    /// Decide if an observed intent should be enqueued
+    ///
+    /// Consulted for each intent returned by [`Self::render_model()`].
+    /// Vetoed intents are dropped, e.g. for breaking feedback cycles when
+    /// handling an observed intent would cause the same intent to be
+    /// observed again.
+    ///
+    /// Returns `true` by default.
+    #[must_use]
+    fn accept_observed_intent(&self, intent: &<Self::Model as TryModel>::Intent) -> bool {
+        let _ = intent;
+        true
+    }
+}
+
+/// The canonical renderer for headless operation
+///
+/// Never observes any intents, e.g. for running the model in tests or
+/// for server-side simulations without any view.
+///
+/// ```no_run
+/// # use infect::{
+/// #     consume_messages, MaybeDebug, MessageReceiver, MessageTransport, NoRender, TaskContext,
+/// #     TaskExecutor, TryModel,
+/// # };
+/// # async fn example<M, T, Transport>(
+/// #     mut message_rx: MessageReceiver<M::Intent, M::Effect, Transport>,
+/// #     mut task_context: TaskContext<T, M::Intent, M::Effect, Transport>,
+/// #     mut model: M,
+/// # ) where
+/// #     M: TryModel + MaybeDebug,
+/// #     T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task>
+/// #         + Clone,
+/// #     Transport: MessageTransport<M::Intent, M::Effect>,
+/// # {
+/// consume_messages(
+///     &mut message_rx,
+///     &mut task_context,
+///     &mut model,
+///     &mut NoRender::new(),
+/// )
+/// .await;
+/// # }
+/// ```
+pub struct NoRender<M> {
+    _phantom: PhantomData<fn(&M)>,
+}
+
+impl<M> NoRender<M> {
+    /// Create a new instance
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<M> Default for NoRender<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Clone for NoRender<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for NoRender<M> {}
+
+impl<M> fmt::Debug for NoRender<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NoRender").finish()
+    }
+}
+
+impl<M> ModelRender for NoRender<M>
+where
+    M: TryModel,
+{
+    type Model = M;
+
+    fn render_model(
+        &mut self,
+        _model: &Self::Model,
+        _hint: <Self::Model as TryModel>::RenderHint,
+    ) -> impl IntoIterator<Item = <Self::Model as TryModel>::Intent> {
+        None
+    }
 }
 
 /// Control rendering after applying effects
 ///
 /// Rendering hints are additive, e.g. like a bloom filter.
-pub trait ModelRenderHint: Sized + Add + AddAssign + Default {
+pub trait ModelRenderHint: Sized + Clone + Add + AddAssign + Default {
     /// Decide if the model needs to be rendered after applying an effect
     ///
     /// Must return `false` for the default value!
@@ -104,7 +371,24 @@ pub trait ModelRenderHint: Sized + Add + AddAssign + Default {
 ///
 /// The most basic implementation of [`ModelRenderHint`] that might be
 /// sufficient for many cases and could be used as a default.
+///
+/// New variants might be added in the future without a breaking change.
+/// Matching on the indicator requires a wildcard arm:
+///
+/// ```
+/// use infect::ModelChanged;
+///
+/// fn is_unchanged(model_changed: ModelChanged) -> bool {
+///     match model_changed {
+///         ModelChanged::Unchanged => true,
+///         _ => false,
+///     }
+/// }
+///
+/// assert!(!is_unchanged(ModelChanged::RenderRequested));
+/// ```
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ModelChanged {
     /// The model has not changed
     ///
@@ -121,6 +405,73 @@ pub enum ModelChanged {
     /// if the model has actually changed is either costly or impossible
     /// then default to this variant.
     MaybeChanged,
+
+    /// The model has not changed but needs to be re-rendered anyway
+    ///
+    /// For rendering that is not driven by changes of the model, e.g.
+    /// after changing the theme of the view.
+    RenderRequested,
+}
+
+impl ModelChanged {
+    /// Mark the model as changed
+    ///
+    /// Readable alias for [`Self::MaybeChanged`].
+    #[must_use]
+    pub const fn changed() -> Self {
+        Self::MaybeChanged
+    }
+
+    /// Rank in the order of [`Add`], i.e. the sum is the maximum
+    const fn rank(self) -> u8 {
+        match self {
+            Self::Unchanged => 0,
+            Self::RenderRequested => 1,
+            Self::MaybeChanged => 2,
+        }
+    }
+}
+
+/// Ordered consistently with [`Add`]
+///
+/// `Unchanged < RenderRequested < MaybeChanged`, i.e. `lhs + rhs` equals
+/// `lhs.max(rhs)`:
+///
+/// ```
+/// use infect::ModelChanged;
+///
+/// let variants = [
+///     ModelChanged::Unchanged,
+///     ModelChanged::RenderRequested,
+///     ModelChanged::MaybeChanged,
+/// ];
+/// for lhs in variants {
+///     for rhs in variants {
+///         assert_eq!(lhs + rhs, lhs.max(rhs));
+///     }
+/// }
+/// ```
+impl Ord for ModelChanged {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for ModelChanged {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<bool> for ModelChanged {
+    /// Convert a flag that indicates if the model has changed
+    fn from(changed: bool) -> Self {
+        if changed {
+            Self::MaybeChanged
+        } else {
+            Self::Unchanged
+        }
+    }
 }
 
 impl Add<ModelChanged> for ModelChanged {
@@ -130,6 +481,7 @@ impl Add<ModelChanged> for ModelChanged {
         match (self, rhs) {
             (Self::Unchanged, Self::Unchanged) => Self::Unchanged,
             (Self::MaybeChanged, _) | (_, Self::MaybeChanged) => Self::MaybeChanged,
+            (Self::RenderRequested, _) | (_, Self::RenderRequested) => Self::RenderRequested,
         }
     }
 }
@@ -144,7 +496,158 @@ impl ModelRenderHint for ModelChanged {
     fn should_render_model(&self) -> bool {
         match self {
             Self::Unchanged => false,
-            Self::MaybeChanged => true,
+            Self::MaybeChanged | Self::RenderRequested => true,
         }
     }
 }
+
+/// Dirty regions of the rendered view
+///
+/// A bitset of up to [`Self::MAX_REGIONS`] independent regions that
+/// could be re-rendered separately, e.g.
+///
+/// ```
+/// use infect::RenderRegions;
+///
+/// const HEADER: RenderRegions = RenderRegions::region(0);
+/// const LIST: RenderRegions = RenderRegions::region(1);
+///
+/// let mut render_hint = RenderRegions::NONE;
+/// render_hint += HEADER;
+/// assert!(render_hint.contains(HEADER));
+/// assert!(!render_hint.contains(LIST));
+/// ```
+///
+/// Adding render hints results in the union of their regions. The model
+/// needs to be rendered if any region is dirty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RenderRegions(u64);
+
+impl RenderRegions {
+    /// The maximum number of regions
+    pub const MAX_REGIONS: u32 = u64::BITS;
+
+    /// No dirty regions
+    pub const NONE: Self = Self(0);
+
+    /// All regions are dirty
+    pub const ALL: Self = Self(u64::MAX);
+
+    /// A single region
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than [`Self::MAX_REGIONS`].
+    #[must_use]
+    pub const fn region(index: u32) -> Self {
+        assert!(index < Self::MAX_REGIONS);
+        Self(1 << index)
+    }
+
+    /// Create from the raw bits
+    #[must_use]
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// The raw bits
+    #[must_use]
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Check if no region is dirty
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Check if all given regions are dirty
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Check if any of the given regions is dirty
+    #[must_use]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// The union of both regions
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl Add<RenderRegions> for RenderRegions {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl AddAssign for RenderRegions {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl ModelRenderHint for RenderRegions {
+    fn should_render_model(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+/// Implement [`ModelRenderHint`] for a totally ordered type
+///
+/// Adding render hints results in the maximum of both values. The model
+/// needs to be rendered for any value that differs from the default
+/// value, i.e. the contract of [`ModelRenderHint::should_render_model()`]
+/// is fulfilled implicitly.
+///
+/// The type must implement [`Ord`], [`Clone`], and [`Default`]. The
+/// default value should be the minimum.
+///
+/// ```
+/// #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+/// enum RenderHint {
+///     #[default]
+///     None,
+///     Status,
+///     Everything,
+/// }
+///
+/// infect::impl_model_render_hint!(RenderHint);
+///
+/// use infect::ModelRenderHint as _;
+///
+/// assert!(!RenderHint::None.should_render_model());
+/// assert_eq!(RenderHint::Everything, RenderHint::Status + RenderHint::Everything);
+/// ```
+#[macro_export]
+macro_rules! impl_model_render_hint {
+    ($ty:ty) => {
+        impl ::core::ops::Add for $ty {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                ::core::cmp::Ord::max(self, rhs)
+            }
+        }
+
+        impl ::core::ops::AddAssign for $ty {
+            fn add_assign(&mut self, other: Self) {
+                *self = ::core::clone::Clone::clone(self) + other;
+            }
+        }
+
+        impl $crate::ModelRenderHint for $ty {
+            fn should_render_model(&self) -> bool {
+                *self != <Self as ::core::default::Default>::default()
+            }
+        }
+    };
+}
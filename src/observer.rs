@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{Message, TryModel};
+
+/// Callbacks for observing the processing of messages
+///
+/// Intended for collecting metrics or tracing without modifying the
+/// model or the processing loop. All callbacks are invoked synchronously
+/// while processing a message and do nothing by default.
+///
+/// The unit type `()` is the no-op implementation.
+///
+/// See also: [`crate::process_message_with_observer`],
+/// [`crate::consume_messages_with_observer`]
+pub trait ProcessingObserver<M: TryModel> {
+    /// A message is about to be processed
+    fn on_message(&mut self, message: &Message<M::Intent, M::Effect>) {
+        let _ = message;
+    }
+
+    /// An intent has been accepted
+    fn on_intent_handled(&mut self) {}
+
+    /// An intent has been rejected
+    fn on_intent_rejected(&mut self, intent_rejected: &M::IntentRejected) {
+        let _ = intent_rejected;
+    }
+
+    /// An effect is about to be applied, either from a message or as a next effect
+    fn on_apply_effect(&mut self, effect: &M::Effect) {
+        let _ = effect;
+    }
+
+    /// An effect has been applied, either from a message or as a next effect
+    fn on_effect_applied(&mut self, render_hint: &M::RenderHint) {
+        let _ = render_hint;
+    }
+
+    /// Applying an effect failed
+    fn on_effect_failed(&mut self, apply_error: &M::ApplyError) {
+        let _ = apply_error;
+    }
+
+    /// The chain of next effects exceeded the configured maximum length
+    fn on_effect_chain_exceeded(&mut self) {}
+
+    /// A task is about to be spawned
+    fn on_task_spawned(&mut self, task: &M::Task) {
+        let _ = task;
+    }
+
+    /// The model has been rendered
+    fn on_model_rendered(&mut self, model: &M, render_hint: &M::RenderHint) {
+        let _ = (model, render_hint);
+    }
+
+    /// A message has been processed
+    ///
+    /// Invoked after all effects have been applied and the model has been
+    /// rendered, independent of the outcome. When coalescing renders the
+    /// model is rendered only once after all ready messages have been
+    /// processed.
+    fn on_message_processed(&mut self) {}
+}
+
+impl<M: TryModel> ProcessingObserver<M> for () {}
+
+/// Counters for the processing of messages
+///
+/// Collects statistics when used as an observer. The statistics of a
+/// single pass are returned by [`crate::consume_messages_with_stats`]:
+///
+/// ```no_run
+/// # use infect::{
+/// #     consume_messages_with_stats, MaybeDebug, MessageReceiver, MessageTransport, ModelRender,
+/// #     ProcessingOptions, TaskContext, TaskExecutor, TryModel,
+/// # };
+/// # fn report_rejections(_: usize) {}
+/// # async fn example<M, R, T, Transport>(
+/// #     mut message_rx: MessageReceiver<M::Intent, M::Effect, Transport>,
+/// #     mut task_context: TaskContext<T, M::Intent, M::Effect, Transport>,
+/// #     mut model: M,
+/// #     mut render_model: R,
+/// # ) where
+/// #     M: TryModel + MaybeDebug,
+/// #     R: ModelRender<Model = M>,
+/// #     T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task>
+/// #         + Clone,
+/// #     Transport: MessageTransport<M::Intent, M::Effect>,
+/// # {
+/// let (messages_consumed, stats) = consume_messages_with_stats(
+///     &mut message_rx,
+///     &mut task_context,
+///     &mut model,
+///     &mut render_model,
+///     ProcessingOptions::default(),
+/// )
+/// .await;
+/// if stats.rejections > 0 {
+///     report_rejections(stats.rejections);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessingStats {
+    /// The number of processed messages
+    pub messages: usize,
+
+    /// The number of processed messages with an intent
+    pub intents: usize,
+
+    /// The number of rejected intents
+    pub rejections: usize,
+
+    /// The number of applied effects, including next effects
+    pub effects: usize,
+
+    /// The number of spawned tasks
+    pub tasks_spawned: usize,
+
+    /// The number of renders
+    pub renders: usize,
+}
+
+impl<M: TryModel> ProcessingObserver<M> for ProcessingStats {
+    fn on_message(&mut self, message: &Message<M::Intent, M::Effect>) {
+        self.messages += 1;
+        if message.is_intent() {
+            self.intents += 1;
+        }
+    }
+
+    fn on_intent_rejected(&mut self, _intent_rejected: &M::IntentRejected) {
+        self.rejections += 1;
+    }
+
+    fn on_effect_applied(&mut self, _render_hint: &M::RenderHint) {
+        self.effects += 1;
+    }
+
+    fn on_task_spawned(&mut self, _task: &M::Task) {
+        self.tasks_spawned += 1;
+    }
+
+    fn on_model_rendered(&mut self, _model: &M, _render_hint: &M::RenderHint) {
+        self.renders += 1;
+    }
+}
@@ -0,0 +1,474 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::VecDeque;
+use core::{
+    fmt,
+    marker::PhantomData,
+    num::NonZeroUsize,
+    task::{Context, Poll},
+};
+
+use crate::{
+    ChannelClosed, Message, MessageChannel, MessageReceiver, MessageSender, MessageTransport,
+    TryRecvError, TrySendError,
+};
+
+/// A [`MessageTransport`] that delivers effects before intents
+///
+/// Wraps the receiver of an inner transport into a
+/// [`PriorityMessageReceiver`]. Messages that are ready are received
+/// from the inner transport and sorted into two queues until an effect
+/// has been found. Effects are then delivered before intents, i.e. the
+/// model is consistent before acting on new proposals. The order of
+/// messages within each class is preserved.
+///
+/// The number of buffered messages is limited to preserve the
+/// backpressure of a bounded inner transport. Only effects within this
+/// window are delivered before intents.
+///
+/// The sender is shared with the inner transport:
+///
+#[cfg_attr(feature = "std", doc = "```no_run")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// # use std::num::NonZeroUsize;
+/// # use infect::{
+/// #     consume_messages, message_channel, MaybeDebug, MessagePort, ModelRender, MpscTransport,
+/// #     PriorityMessageReceiver, PriorityTransport, TaskContext, TaskExecutor, TryModel,
+/// # };
+/// # async fn example<M, R, T>(mut model: M, mut render_model: R, task_executor: T)
+/// # where
+/// #     M: TryModel + MaybeDebug,
+/// #     R: ModelRender<Model = M>,
+/// #     T: TaskExecutor<
+/// #             T,
+/// #             PriorityTransport<MpscTransport>,
+/// #             Intent = M::Intent,
+/// #             Effect = M::Effect,
+/// #             Task = M::Task,
+/// #         > + Clone,
+/// # {
+/// # let capacity = 10;
+/// let (message_tx, message_rx) = message_channel(capacity).into_parts();
+/// let max_buffered_messages = NonZeroUsize::new(capacity).unwrap();
+/// let mut message_rx = PriorityMessageReceiver::new(message_rx, max_buffered_messages);
+/// let message_port =
+///     MessagePort::<_, _, PriorityTransport<MpscTransport>>::new_with_transport(message_tx);
+/// let mut task_context = TaskContext::new(task_executor, message_port);
+/// consume_messages(&mut message_rx, &mut task_context, &mut model, &mut render_model).await;
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PriorityTransport<Transport> {
+    _phantom: PhantomData<fn() -> Transport>,
+}
+
+/// Receiver of a [`PriorityTransport`]
+///
+/// Messages that have been received from the inner transport but not
+/// yet delivered are buffered, i.e. receiving messages remains
+/// cancellation safe. Buffered messages are not considered by
+/// [`MessageTransport::pending_message_count()`].
+pub struct PriorityMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    receiver: MessageReceiver<Intent, Effect, Transport>,
+    max_buffered_messages: NonZeroUsize,
+    intents: VecDeque<Intent>,
+    effects: VecDeque<Effect>,
+    closed: bool,
+}
+
+impl<Intent, Effect, Transport> PriorityMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance
+    ///
+    /// At most `max_buffered_messages` are received from the inner
+    /// transport ahead of delivering them, e.g. the capacity of the
+    /// inner channel.
+    #[must_use]
+    pub const fn new(
+        receiver: MessageReceiver<Intent, Effect, Transport>,
+        max_buffered_messages: NonZeroUsize,
+    ) -> Self {
+        Self {
+            receiver,
+            max_buffered_messages,
+            intents: VecDeque::new(),
+            effects: VecDeque::new(),
+            closed: false,
+        }
+    }
+
+    /// The number of buffered messages that have not been delivered yet
+    #[must_use]
+    pub fn buffered_message_count(&self) -> usize {
+        self.intents.len() + self.effects.len()
+    }
+
+    /// Receive more messages from the inner transport until an effect
+    /// has been buffered or the buffer is full
+    fn should_receive(&self) -> bool {
+        !self.closed
+            && self.effects.is_empty()
+            && self.buffered_message_count() < self.max_buffered_messages.get()
+    }
+
+    fn buffer_message(&mut self, message: Message<Intent, Effect>) {
+        match message {
+            Message::Intent(intent) => self.intents.push_back(intent),
+            Message::Effect(effect) => self.effects.push_back(effect),
+        }
+    }
+
+    fn pop_message(&mut self) -> Option<Message<Intent, Effect>> {
+        self.effects
+            .pop_front()
+            .map(Message::Effect)
+            .or_else(|| self.intents.pop_front().map(Message::Intent))
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for PriorityMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            receiver: _,
+            max_buffered_messages,
+            intents,
+            effects,
+            closed,
+        } = self;
+        f.debug_struct("PriorityMessageReceiver")
+            .field("max_buffered_messages", max_buffered_messages)
+            .field("intents", &intents.len())
+            .field("effects", &effects.len())
+            .field("closed", closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Intent, Effect, Transport> MessageTransport<Intent, Effect> for PriorityTransport<Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    type Sender = MessageSender<Intent, Effect, Transport>;
+    type Receiver = PriorityMessageReceiver<Intent, Effect, Transport>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        Transport::try_send(sender, message)
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        Transport::poll_ready(sender, cx)
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        Transport::is_closed(sender)
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Transport::pending_message_count(sender)
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        while receiver.should_receive() {
+            match Transport::poll_next(&mut receiver.receiver, cx) {
+                Poll::Ready(Some(message)) => receiver.buffer_message(message),
+                Poll::Ready(None) => receiver.closed = true,
+                Poll::Pending => break,
+            }
+        }
+        if let Some(message) = receiver.pop_message() {
+            return Poll::Ready(Some(message));
+        }
+        if receiver.closed {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        while receiver.should_receive() {
+            match Transport::try_recv(&mut receiver.receiver) {
+                Ok(message) => receiver.buffer_message(message),
+                Err(TryRecvError::Closed) => receiver.closed = true,
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        if let Some(message) = receiver.pop_message() {
+            return Ok(message);
+        }
+        Err(if receiver.closed {
+            TryRecvError::Closed
+        } else {
+            TryRecvError::Empty
+        })
+    }
+}
+
+/// Priority of a message
+///
+/// See also: [`crate::MessagePort::submit_message_with_priority()`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Delivered after all other messages
+    Low,
+
+    /// The priority of all messages that are submitted without a priority
+    #[default]
+    Normal,
+
+    /// Delivered before all other messages, e.g. for cancellation
+    High,
+}
+
+impl Priority {
+    /// All priorities in the order of delivery
+    const DESCENDING: [Self; 3] = [Self::High, Self::Normal, Self::Low];
+
+    const fn level(self) -> usize {
+        match self {
+            Self::High => 0,
+            Self::Normal => 1,
+            Self::Low => 2,
+        }
+    }
+}
+
+/// A [`MessageTransport`] that supports sending messages with a [`Priority`]
+pub trait PriorityMessageTransport<Intent, Effect>: MessageTransport<Intent, Effect> {
+    /// Try to send a message with a priority without blocking
+    ///
+    /// [`MessageTransport::try_send()`] sends messages with
+    /// [`Priority::Normal`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the message if the channel is either full or closed.
+    fn try_send_with_priority(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+        priority: Priority,
+    ) -> Result<(), TrySendError<Intent, Effect>>;
+}
+
+/// A [`MessageTransport`] with a separate inner channel for each [`Priority`]
+///
+/// Messages with a higher priority are delivered before messages with
+/// a lower priority. The order of messages with the same priority is
+/// preserved.
+///
+/// See also: [`priority_level_channel()`]
+#[derive(Debug)]
+pub struct PriorityLevelTransport<Transport> {
+    _phantom: PhantomData<fn() -> Transport>,
+}
+
+/// Create a message channel with a separate inner channel for each [`Priority`]
+///
+/// The inner channels are created by invoking `new_channel` once per
+/// priority, e.g.
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// # use infect::{message_channel, priority_level_channel, Message, Priority, SubmitError};
+/// # #[derive(Debug)]
+/// # enum Intent {
+/// #     Cancel,
+/// # }
+/// # let capacity = 10;
+/// # (|| -> Result<(), SubmitError<Intent, ()>> {
+/// let (mut message_port, mut message_rx) =
+///     priority_level_channel(|| message_channel(capacity)).split();
+/// message_port.submit_message_with_priority(Message::Intent(Intent::Cancel), Priority::High)?;
+/// # Ok(())
+/// # })().unwrap();
+/// ```
+///
+/// The capacity applies to each priority individually.
+#[must_use]
+pub fn priority_level_channel<Intent, Effect, Transport>(
+    mut new_channel: impl FnMut() -> MessageChannel<Intent, Effect, Transport>,
+) -> MessageChannel<Intent, Effect, PriorityLevelTransport<Transport>>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    let [high, normal, low] = Priority::DESCENDING.map(|_| new_channel().into_parts());
+    let message_tx = PriorityLevelSender {
+        senders: [high.0, normal.0, low.0],
+    };
+    let message_rx = PriorityLevelReceiver {
+        receivers: [high.1, normal.1, low.1],
+        closed: [false; 3],
+    };
+    MessageChannel::new(message_tx, message_rx)
+}
+
+/// Sender of a [`PriorityLevelTransport`]
+pub struct PriorityLevelSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    senders: [MessageSender<Intent, Effect, Transport>; 3],
+}
+
+impl<Intent, Effect, Transport> PriorityLevelSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn sender_mut(&mut self, priority: Priority) -> &mut MessageSender<Intent, Effect, Transport> {
+        &mut self.senders[priority.level()]
+    }
+}
+
+impl<Intent, Effect, Transport> Clone for PriorityLevelSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn clone(&self) -> Self {
+        let Self { senders } = self;
+        Self {
+            senders: senders.clone(),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for PriorityLevelSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityLevelSender")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receiver of a [`PriorityLevelTransport`]
+pub struct PriorityLevelReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    receivers: [MessageReceiver<Intent, Effect, Transport>; 3],
+    closed: [bool; 3],
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for PriorityLevelReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            receivers: _,
+            closed,
+        } = self;
+        f.debug_struct("PriorityLevelReceiver")
+            .field("closed", closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Intent, Effect, Transport> MessageTransport<Intent, Effect>
+    for PriorityLevelTransport<Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    type Sender = PriorityLevelSender<Intent, Effect, Transport>;
+    type Receiver = PriorityLevelReceiver<Intent, Effect, Transport>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        Self::try_send_with_priority(sender, message, Priority::Normal)
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        Transport::poll_ready(sender.sender_mut(Priority::Normal), cx)
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        sender.senders.iter().all(Transport::is_closed)
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        sender
+            .senders
+            .iter()
+            .map(Transport::pending_message_count)
+            .sum()
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        let PriorityLevelReceiver { receivers, closed } = receiver;
+        for (receiver, closed) in receivers.iter_mut().zip(closed.iter_mut()) {
+            if *closed {
+                continue;
+            }
+            match Transport::poll_next(receiver, cx) {
+                Poll::Ready(Some(message)) => return Poll::Ready(Some(message)),
+                Poll::Ready(None) => *closed = true,
+                Poll::Pending => (),
+            }
+        }
+        if closed.iter().all(|closed| *closed) {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        let PriorityLevelReceiver { receivers, closed } = receiver;
+        for (receiver, closed) in receivers.iter_mut().zip(closed.iter_mut()) {
+            if *closed {
+                continue;
+            }
+            match Transport::try_recv(receiver) {
+                Ok(message) => return Ok(message),
+                Err(TryRecvError::Closed) => *closed = true,
+                Err(TryRecvError::Empty) => (),
+            }
+        }
+        if closed.iter().all(|closed| *closed) {
+            Err(TryRecvError::Closed)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> PriorityMessageTransport<Intent, Effect>
+    for PriorityLevelTransport<Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    fn try_send_with_priority(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+        priority: Priority,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        Transport::try_send(sender.sender_mut(priority), message)
+    }
+}
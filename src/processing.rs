@@ -1,18 +1,52 @@
 // SPDX-FileCopyrightText: The infect authors
 // SPDX-License-Identifier: MPL-2.0
 
-use std::fmt;
-
-use futures_util::StreamExt as _;
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{
+    convert::Infallible,
+    future::{pending, poll_fn, Future},
+    num::NonZeroUsize,
+    ops::ControlFlow,
+    pin::{pin, Pin},
+    task::Poll,
+};
 
+#[cfg(feature = "logging")]
+use crate::logging::LogLabel;
 use crate::{
-    task::TaskContext, EffectApplied, IntentHandled, Message, MessageReceiver, Model, ModelRender,
-    ModelRenderHint, TaskExecutor,
+    logging::{log_debug, log_error, log_trace, log_warn},
+    task::TaskContext,
+    EffectApplied, IntentHandled, MaybeDebug, Message, MessageReceiver, MessageTransport,
+    Middleware, MiddlewareOutcome, ModelRender, ModelRenderHint, ProcessingObserver,
+    ProcessingStats, RejectedIntent, TaskExecutor, TryModel, TryRecvError,
 };
+#[cfg(feature = "std")]
+use crate::{message_channel, MessagePort};
 
 /// Outcome of processing a single message
+///
+/// New variants might be added in the future without a breaking change.
+/// Matching on the outcome requires a wildcard arm:
+///
+/// ```no_run
+/// # use infect::MessageProcessed;
+/// # fn reject<T>(_: T) {}
+/// # fn example<IntentRejected, RenderHint>(
+/// #     mut process_next_message: impl FnMut() -> MessageProcessed<IntentRejected, RenderHint>,
+/// # ) {
+/// # loop {
+/// # let message_processed = process_next_message();
+/// match message_processed {
+///     MessageProcessed::IntentRejected(intent_rejected) => reject(intent_rejected),
+///     MessageProcessed::Progressing { .. } => continue,
+///     _ => break,
+/// }
+/// # }
+/// # }
+/// ```
 #[derive(Debug, Clone)]
-pub enum MessageProcessed<IntentRejected> {
+#[non_exhaustive]
+pub enum MessageProcessed<IntentRejected, RenderHint, ApplyError = Infallible> {
     /// A message with an intent has been rejected
     IntentRejected(IntentRejected),
 
@@ -26,93 +60,721 @@ pub enum MessageProcessed<IntentRejected> {
     /// message has been submitted and the message channel won't be empty. If a
     /// task has been spawned then this task is expected to submit a message
     /// eventually.
-    Progressing,
+    Progressing {
+        /// The number of tasks that have been spawned
+        tasks_spawned: usize,
+
+        /// The model has been rendered
+        rendered: bool,
+
+        /// The accumulated render hint of all applied effects
+        render_hint: RenderHint,
+    },
 
     /// Not [`Self::Progressing`]
-    NoProgress,
+    ///
+    /// Applying an effect that neither requests rendering nor results in
+    /// any tasks or next effects is reported as discarded, e.g. for finding
+    /// effects that are dead on arrival:
+    ///
+    #[cfg_attr(feature = "std", doc = "```")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// use std::convert::Infallible;
+    ///
+    /// use infect::{
+    ///     message_channel, process_message_no_render, EffectApplied, InlineTaskExecutor,
+    ///     IntentHandled, Message, MessageProcessed, Model, ModelChanged, TaskContext,
+    /// };
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct Counter(u32);
+    ///
+    /// impl Model for Counter {
+    ///     type Intent = Infallible;
+    ///     type IntentRejected = Infallible;
+    ///     type Effect = u32;
+    ///     type Task = Infallible;
+    ///     type RenderHint = ModelChanged;
+    ///
+    ///     fn handle_intent(
+    ///         &mut self,
+    ///         intent: Infallible,
+    ///     ) -> IntentHandled<Infallible, u32, Infallible, ModelChanged> {
+    ///         match intent {}
+    ///     }
+    ///
+    ///     fn apply_effect(&mut self, increment: u32) -> EffectApplied<u32, Infallible, ModelChanged> {
+    ///         if increment == 0 {
+    ///             return EffectApplied::unchanged();
+    ///         }
+    ///         self.0 += increment;
+    ///         EffectApplied::render(ModelChanged::MaybeChanged)
+    ///     }
+    /// }
+    ///
+    /// let task_executor = InlineTaskExecutor::new(|_, task| async move { match task {} });
+    /// let (message_port, _message_rx) = message_channel(1).split();
+    /// let mut task_context = TaskContext::new(task_executor, message_port);
+    /// let mut model = Counter::default();
+    ///
+    /// let message_processed =
+    ///     process_message_no_render(&mut task_context, &mut model, Message::Effect(0));
+    /// assert!(matches!(
+    ///     message_processed,
+    ///     MessageProcessed::NoProgress {
+    ///         effect_discarded: true,
+    ///         ..
+    ///     }
+    /// ));
+    ///
+    /// let message_processed =
+    ///     process_message_no_render(&mut task_context, &mut model, Message::Effect(1));
+    /// assert!(matches!(
+    ///     message_processed,
+    ///     MessageProcessed::NoProgress {
+    ///         effect_discarded: false,
+    ///         ..
+    ///     }
+    /// ));
+    /// ```
+    NoProgress {
+        /// The model has been rendered
+        rendered: bool,
+
+        /// The accumulated render hint of all applied effects
+        render_hint: RenderHint,
+
+        /// An effect has been applied without any outcome
+        effect_discarded: bool,
+    },
+
+    /// Aborted after exceeding [`ProcessingOptions::max_effect_chain_len`]
+    ///
+    /// All pending next effects have been discarded.
+    EffectChainExceeded,
+
+    /// Applying an effect failed
+    ///
+    /// All pending next effects have been discarded. The model has been
+    /// rendered if any of the preceding effects requested it.
+    ///
+    /// See also: [`TryModel::try_apply_effect()`]
+    EffectFailed(ApplyError),
+}
+
+impl<IntentRejected, RenderHint, ApplyError>
+    MessageProcessed<IntentRejected, RenderHint, ApplyError>
+{
+    /// Map only the rejection
+    pub fn map_intent_rejected<R>(
+        self,
+        map_intent_rejected: impl FnOnce(IntentRejected) -> R,
+    ) -> MessageProcessed<R, RenderHint, ApplyError> {
+        match self {
+            Self::IntentRejected(intent_rejected) => {
+                MessageProcessed::IntentRejected(map_intent_rejected(intent_rejected))
+            }
+            Self::Progressing {
+                tasks_spawned,
+                rendered,
+                render_hint,
+            } => MessageProcessed::Progressing {
+                tasks_spawned,
+                rendered,
+                render_hint,
+            },
+            Self::NoProgress {
+                rendered,
+                render_hint,
+                effect_discarded,
+            } => MessageProcessed::NoProgress {
+                rendered,
+                render_hint,
+                effect_discarded,
+            },
+            Self::EffectChainExceeded => MessageProcessed::EffectChainExceeded,
+            Self::EffectFailed(apply_error) => MessageProcessed::EffectFailed(apply_error),
+        }
+    }
+}
+
+/// Options for processing messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessingOptions {
+    /// Maximum number of next effects that are applied while processing
+    /// a single message
+    ///
+    /// Guards against runaway chains of [`EffectApplied::next_effects`]
+    /// that would otherwise never return. Unlimited if `None`.
+    pub max_effect_chain_len: Option<NonZeroUsize>,
+
+    /// Maximum number of consecutive messages that result in an observed
+    /// intent after rendering the model
+    ///
+    /// Guards against render loops, i.e. rendering the model repeatedly
+    /// produces an intent that causes the model to be rendered again.
+    /// Only considered when consuming messages. Unlimited if `None`.
+    pub max_consecutive_observed_intents: Option<NonZeroUsize>,
+
+    /// Render the model only once after processing all messages that
+    /// are ready
+    ///
+    /// The render hints of all processed messages are accumulated and
+    /// the model is rendered before awaiting the next message. Intermediate
+    /// states are never rendered. Only considered when consuming messages.
+    ///
+    /// See also: [`consume_messages_coalesced`]
+    pub coalesce_renders: bool,
+
+    /// Yield to the executor after processing this number of messages
+    ///
+    /// Consuming messages only yields when awaiting the next message.
+    /// A fast producer that keeps the channel non-empty could starve other
+    /// tasks on the same executor, e.g. on a single-threaded runtime.
+    /// Forces a yield periodically between two messages without losing
+    /// any messages. Only considered when consuming messages asynchronously.
+    /// Never forced if `None`.
+    pub yield_interval: Option<NonZeroUsize>,
 }
 
 /// Process a single message
+///
+/// Applies the resulting chain of effects and renders the model at most
+/// once afterwards, using the accumulated render hints of all effects.
+///
+/// Uses the default [`ProcessingOptions`].
+///
+/// See also: [`process_message_with_options`]
 #[must_use]
-pub fn process_message<M, R, T>(
-    task_context: &mut TaskContext<T, M::Intent, M::Effect>,
+pub fn process_message<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
     model: &mut M,
     render_model: &mut R,
-    mut message: Message<M::Intent, M::Effect>,
-) -> MessageProcessed<M::IntentRejected>
+    message: Message<M::Intent, M::Effect>,
+) -> MessageProcessed<M::IntentRejected, M::RenderHint, M::ApplyError>
 where
-    M: Model + fmt::Debug,
-    M::Intent: fmt::Debug,
-    M::IntentRejected: fmt::Debug,
-    M::Effect: fmt::Debug,
-    M::Task: fmt::Debug,
+    M: TryModel + MaybeDebug,
     R: ModelRender<Model = M>,
-    T: TaskExecutor<T, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
 {
-    let mut progressing = false;
+    process_message_with_options(
+        task_context,
+        model,
+        render_model,
+        message,
+        ProcessingOptions::default(),
+    )
+}
 
-    loop {
-        let effect_applied = match message {
-            Message::Intent(intent) => {
-                log::debug!("Handling intent: {intent:?}");
-                match model.handle_intent(intent) {
-                    IntentHandled::Accepted(effect_applied) => effect_applied,
-                    IntentHandled::Rejected(intent_rejected) => {
-                        log::debug!("Intent rejected: {intent_rejected:?}");
-                        return MessageProcessed::IntentRejected(intent_rejected);
-                    }
+/// Process a single message with custom [`ProcessingOptions`]
+#[must_use]
+pub fn process_message_with_options<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    message: Message<M::Intent, M::Effect>,
+    options: ProcessingOptions,
+) -> MessageProcessed<M::IntentRejected, M::RenderHint, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    process_message_with_observer(task_context, model, render_model, message, options, &mut ())
+}
+
+/// Process a single message and notify an observer
+///
+/// See also: [`process_message_with_options`]
+#[must_use]
+pub fn process_message_with_observer<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    message: Message<M::Intent, M::Effect>,
+    options: ProcessingOptions,
+    observer: &mut impl ProcessingObserver<M>,
+) -> MessageProcessed<M::IntentRejected, M::RenderHint, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let (message_processed, _) = process_message_observing_intents(
+        task_context,
+        model,
+        render_model,
+        message,
+        options,
+        &mut (),
+        observer,
+        None,
+    );
+    message_processed
+}
+
+/// Process a single message and return a rejected intent to the caller
+///
+/// The rejection is bundled with a clone of the original intent, e.g.
+/// for re-submitting the intent after the model has changed.
+///
+/// See also: [`process_message`]
+#[must_use]
+// The closure never panics, because only intents could be rejected.
+#[allow(clippy::missing_panics_doc, clippy::type_complexity)]
+pub fn process_message_with_rejected_intent<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    message: Message<M::Intent, M::Effect>,
+) -> MessageProcessed<RejectedIntent<M::Intent, M::IntentRejected>, M::RenderHint, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    M::Intent: Clone,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let intent = match &message {
+        Message::Intent(intent) => Some(intent.clone()),
+        Message::Effect(_) => None,
+    };
+    process_message(task_context, model, render_model, message).map_intent_rejected(|reason| {
+        let intent = intent.expect("rejected message with an intent");
+        RejectedIntent::new(intent, reason)
+    })
+}
+
+/// Process a single message without rendering the model
+///
+/// Intended for use cases that update the view through other means, e.g.
+/// with fine-grained reactivity. The accumulated render hint is returned
+/// to the caller instead.
+///
+/// Uses the default [`ProcessingOptions`].
+#[must_use]
+pub fn process_message_no_render<M, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    message: Message<M::Intent, M::Effect>,
+) -> MessageProcessed<M::IntentRejected, M::RenderHint, M::ApplyError>
+where
+    M: TryModel,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let ProcessingOptions {
+        max_effect_chain_len,
+        max_consecutive_observed_intents: _,
+        coalesce_renders: _,
+        yield_interval: _,
+    } = ProcessingOptions::default();
+    let chain_applied =
+        match apply_message(task_context, model, message, max_effect_chain_len, &mut ()) {
+            Ok(chain_applied) => chain_applied,
+            Err(intent_rejected) => return MessageProcessed::IntentRejected(intent_rejected),
+        };
+    let EffectChainApplied {
+        render_hint,
+        tasks_spawned,
+        effect_discarded,
+        effect_chain_exceeded,
+        effect_failed,
+    } = chain_applied;
+    if let Some(apply_error) = effect_failed {
+        MessageProcessed::EffectFailed(apply_error)
+    } else if effect_chain_exceeded {
+        MessageProcessed::EffectChainExceeded
+    } else if tasks_spawned > 0 {
+        MessageProcessed::Progressing {
+            tasks_spawned,
+            rendered: false,
+            render_hint,
+        }
+    } else {
+        MessageProcessed::NoProgress {
+            rendered: false,
+            render_hint,
+            effect_discarded,
+        }
+    }
+}
+
+type MessageProcessedOf<M> = MessageProcessed<
+    <M as TryModel>::IntentRejected,
+    <M as TryModel>::RenderHint,
+    <M as TryModel>::ApplyError,
+>;
+
+/// The outcome of [`apply_message`]
+struct EffectChainApplied<RenderHint, ApplyError> {
+    render_hint: RenderHint,
+    tasks_spawned: usize,
+    effect_discarded: bool,
+    effect_chain_exceeded: bool,
+    effect_failed: Option<ApplyError>,
+}
+
+impl<RenderHint, ApplyError> EffectChainApplied<RenderHint, ApplyError>
+where
+    RenderHint: Default,
+{
+    fn new() -> Self {
+        Self {
+            render_hint: Default::default(),
+            tasks_spawned: 0,
+            effect_discarded: false,
+            effect_chain_exceeded: false,
+            effect_failed: None,
+        }
+    }
+}
+
+/// Handle the intent or apply the effect followed by all next effects
+fn apply_message<M, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    message: Message<M::Intent, M::Effect>,
+    max_effect_chain_len: Option<NonZeroUsize>,
+    observer: &mut impl ProcessingObserver<M>,
+) -> Result<EffectChainApplied<M::RenderHint, M::ApplyError>, M::IntentRejected>
+where
+    M: TryModel,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    observer.on_message(&message);
+    let effect_applied = match message {
+        Message::Intent(intent) => {
+            log_debug!("{}Handling intent: {intent:?}", LogLabel(model));
+            match model.handle_intent(intent) {
+                IntentHandled::Accepted(effect_applied) => {
+                    observer.on_intent_handled();
+                    effect_applied
+                }
+                IntentHandled::Rejected(intent_rejected) => {
+                    log_debug!("{}Intent rejected: {intent_rejected:?}", LogLabel(model));
+                    observer.on_intent_rejected(&intent_rejected);
+                    return Err(intent_rejected);
                 }
             }
-            Message::Effect(effect) => {
-                log::debug!("Applying effect: {effect:?}");
-                model.apply_effect(effect)
+        }
+        Message::Effect(effect) => {
+            log_debug!("{}Applying effect: {effect:?}", LogLabel(model));
+            observer.on_apply_effect(&effect);
+            match model.try_apply_effect(effect) {
+                Ok(effect_applied) => {
+                    observer.on_effect_applied(&effect_applied.render_hint);
+                    if is_effect_discarded(&effect_applied) {
+                        log_debug!("{}Effect applied without any outcome", LogLabel(model));
+                        let mut chain_applied = EffectChainApplied::new();
+                        chain_applied.effect_discarded = true;
+                        return Ok(chain_applied);
+                    }
+                    effect_applied
+                }
+                Err(apply_error) => {
+                    log_warn!("{}Applying effect failed: {apply_error:?}", LogLabel(model));
+                    observer.on_effect_failed(&apply_error);
+                    let mut chain_applied = EffectChainApplied::new();
+                    chain_applied.effect_failed = Some(apply_error);
+                    return Ok(chain_applied);
+                }
             }
-        };
+        }
+    };
+    Ok(apply_effect_chain(
+        task_context,
+        model,
+        effect_applied,
+        max_effect_chain_len,
+        observer,
+    ))
+}
+
+/// Pass the message through middleware before applying it
+///
+/// Dropped messages are treated like messages that have been applied
+/// without any outcome.
+fn apply_message_with_middleware<M, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    message: Message<M::Intent, M::Effect>,
+    max_effect_chain_len: Option<NonZeroUsize>,
+    middleware: &mut impl Middleware<M>,
+    observer: &mut impl ProcessingObserver<M>,
+) -> Result<EffectChainApplied<M::RenderHint, M::ApplyError>, M::IntentRejected>
+where
+    M: TryModel,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    match middleware.before(message) {
+        MiddlewareOutcome::Continue(message) => {
+            apply_message(task_context, model, message, max_effect_chain_len, observer)
+        }
+        MiddlewareOutcome::Drop => {
+            log_debug!("{}Message dropped by middleware", LogLabel(model));
+            Ok(EffectChainApplied::new())
+        }
+        MiddlewareOutcome::Reject(intent_rejected) => {
+            log_debug!(
+                "{}Message rejected by middleware: {intent_rejected:?}",
+                LogLabel(model)
+            );
+            observer.on_intent_rejected(&intent_rejected);
+            Err(intent_rejected)
+        }
+    }
+}
+
+fn is_effect_discarded<Effect, Task, RenderHint>(
+    effect_applied: &EffectApplied<Effect, Task, RenderHint>,
+) -> bool
+where
+    RenderHint: ModelRenderHint,
+{
+    let EffectApplied {
+        render_hint,
+        tasks,
+        next_effects,
+    } = effect_applied;
+    !render_hint.should_render_model() && tasks.is_empty() && next_effects.is_empty()
+}
+
+/// Apply all next effects
+///
+/// Spawns all resulting tasks and accumulates the render hints.
+fn apply_effect_chain<M, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    mut effect_applied: EffectApplied<M::Effect, M::Task, M::RenderHint>,
+    max_effect_chain_len: Option<NonZeroUsize>,
+    observer: &mut impl ProcessingObserver<M>,
+) -> EffectChainApplied<M::RenderHint, M::ApplyError>
+where
+    M: TryModel,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let mut effect_chain_len = 0;
+    let mut chain_applied = EffectChainApplied::new();
+    let mut pending_effects = VecDeque::new();
+    loop {
         let EffectApplied {
-            task,
+            tasks,
             render_hint,
-            next_effect,
+            next_effects,
         } = effect_applied;
-        if let Some(task) = task {
-            log::debug!("Spawning task: {task:?}");
+        for task in tasks {
+            log_debug!("{}Spawning task: {task:?}", LogLabel(model));
+            observer.on_task_spawned(&task);
             task_context.spawn_task(task);
-            progressing = true;
-        }
-
-        // Verify that the trait implements the contract as documented.
-        debug_assert!(!M::RenderHint::default().should_render_model());
-        if render_hint.should_render_model() {
-            log::debug!("Rendering model: {model:?}");
-            if let Some(observed_intent) = render_model.render_model(model, render_hint) {
-                log::debug!("Observed intent after rendering model: {observed_intent:?}");
-                // The corresponding message is enqueued like any other message,
-                // i.e. not processed immediately during this turn!
-                task_context.submit_intent(observed_intent);
-                progressing = true;
+            chain_applied.tasks_spawned += 1;
+        }
+        chain_applied.render_hint += render_hint;
+        // Depth-first: The next effects precede all remaining, pending effects.
+        for next_effect in next_effects.into_iter().rev() {
+            pending_effects.push_front(next_effect);
+        }
+        let Some(effect) = pending_effects.pop_front() else {
+            return chain_applied;
+        };
+        if max_effect_chain_len.is_some_and(|max| effect_chain_len >= max.get()) {
+            log_error!(
+                "{label}Aborting after applying {effect_chain_len} next effect(s) - discarding \
+                 {discarded_count} effect(s), starting with: {effect:?}",
+                discarded_count = pending_effects.len() + 1,
+                label = LogLabel(model),
+            );
+            observer.on_effect_chain_exceeded();
+            chain_applied.effect_chain_exceeded = true;
+            return chain_applied;
+        }
+        effect_chain_len += 1;
+        // Immediately continue processing the next effect before any
+        // other, enqueued messages.
+        log_debug!("{}Applying next effect: {effect:?}", LogLabel(model));
+        observer.on_apply_effect(&effect);
+        effect_applied = match model.try_apply_effect(effect) {
+            Ok(effect_applied) => effect_applied,
+            Err(apply_error) => {
+                log_warn!(
+                    "{label}Applying next effect failed: {apply_error:?} - discarding \
+                     {discarded_count} effect(s)",
+                    discarded_count = pending_effects.len(),
+                    label = LogLabel(model),
+                );
+                observer.on_effect_failed(&apply_error);
+                chain_applied.effect_failed = Some(apply_error);
+                return chain_applied;
+            }
+        };
+        observer.on_effect_applied(&effect_applied.render_hint);
+    }
+}
+
+/// Returns the outcome together with the number of submitted,
+/// observed intents.
+#[allow(clippy::too_many_arguments)]
+fn process_message_observing_intents<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    message: Message<M::Intent, M::Effect>,
+    options: ProcessingOptions,
+    middleware: &mut impl Middleware<M>,
+    observer: &mut impl ProcessingObserver<M>,
+    render_hint_sum: Option<&mut M::RenderHint>,
+) -> (MessageProcessedOf<M>, usize)
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let ProcessingOptions {
+        max_effect_chain_len,
+        max_consecutive_observed_intents: _,
+        coalesce_renders: _,
+        yield_interval: _,
+    } = options;
+    let message_applied = apply_message_with_middleware(
+        task_context,
+        model,
+        message,
+        max_effect_chain_len,
+        middleware,
+        observer,
+    );
+    let processed = match message_applied {
+        Ok(chain_applied) => {
+            if let Some(render_hint_sum) = render_hint_sum {
+                *render_hint_sum += chain_applied.render_hint.clone();
             }
+            render_effect_chain(task_context, model, render_model, chain_applied, observer)
         }
-        if let Some(effect) = next_effect {
-            message = Message::Effect(effect);
-            // Immediately continue processing the message with the next effect
-            // before any other, enqueued messages.
-        } else {
-            break;
+        Err(intent_rejected) => (MessageProcessed::IntentRejected(intent_rejected), 0),
+    };
+    observer.on_message_processed();
+    processed
+}
+
+/// Render the model after applying a chain of effects
+///
+/// Returns the outcome together with the number of submitted,
+/// observed intents.
+fn render_effect_chain<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &M,
+    render_model: &mut R,
+    chain_applied: EffectChainApplied<M::RenderHint, M::ApplyError>,
+    observer: &mut impl ProcessingObserver<M>,
+) -> (MessageProcessedOf<M>, usize)
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let EffectChainApplied {
+        render_hint,
+        tasks_spawned,
+        effect_discarded,
+        effect_chain_exceeded,
+        effect_failed,
+    } = chain_applied;
+    let mut progressing = tasks_spawned > 0;
+    let mut observed_intent_count = 0;
+    let mut rendered = false;
+
+    // The model is only rendered once after the whole chain of effects
+    // has been applied.
+    // Verify that the trait implements the contract as documented.
+    debug_assert!(!M::RenderHint::default().should_render_model());
+    if render_hint.should_render_model() && render_model.should_render(model, &render_hint) {
+        log_debug!("{}Rendering model: {model:?}", LogLabel(model));
+        rendered = true;
+        // Collected upfront for consulting the renderer about each intent.
+        let observed_intents: Vec<_> = render_model
+            .render_model(model, render_hint.clone())
+            .into_iter()
+            .collect();
+        observer.on_model_rendered(model, &render_hint);
+        for observed_intent in observed_intents {
+            if !render_model.accept_observed_intent(&observed_intent) {
+                log_debug!(
+                    "{}Dropping vetoed intent after rendering model: {observed_intent:?}",
+                    LogLabel(model)
+                );
+                continue;
+            }
+            log_debug!(
+                "{}Observed intent after rendering model: {observed_intent:?}",
+                LogLabel(model)
+            );
+            // The corresponding message is enqueued like any other message,
+            // i.e. not processed immediately during this turn!
+            if task_context.submit_intent(observed_intent).is_ok() {
+                observed_intent_count += 1;
+                progressing = true;
+            }
         }
     }
 
-    if progressing {
-        MessageProcessed::Progressing
+    let message_processed = if let Some(apply_error) = effect_failed {
+        MessageProcessed::EffectFailed(apply_error)
+    } else if effect_chain_exceeded {
+        MessageProcessed::EffectChainExceeded
+    } else if progressing {
+        MessageProcessed::Progressing {
+            tasks_spawned,
+            rendered,
+            render_hint,
+        }
     } else {
-        MessageProcessed::NoProgress
-    }
+        debug_assert_eq!(tasks_spawned, 0);
+        MessageProcessed::NoProgress {
+            rendered,
+            render_hint,
+            effect_discarded,
+        }
+    };
+    (message_processed, observed_intent_count)
 }
 
 /// Outcome of consuming multiple messages
 ///
 /// The condition with associated data that stopped consuming messages.
+///
+/// New variants might be added in the future without a breaking change.
+/// Matching on the outcome requires a wildcard arm:
+///
+/// ```no_run
+/// # use infect::MessagesConsumed;
+/// # fn reject<T>(_: T) {}
+/// # fn example<IntentRejected>(
+/// #     mut consume_next_messages: impl FnMut() -> MessagesConsumed<IntentRejected>,
+/// # ) {
+/// # loop {
+/// # let messages_consumed = consume_next_messages();
+/// match messages_consumed {
+///     MessagesConsumed::ChannelClosed => break,
+///     MessagesConsumed::IntentRejected(intent_rejected) => reject(intent_rejected),
+///     _ => continue,
+/// }
+/// # }
+/// # }
+/// ```
 #[derive(Debug, Clone)]
-pub enum MessagesConsumed<IntentRejected> {
+#[non_exhaustive]
+pub enum MessagesConsumed<IntentRejected, ApplyError = Infallible> {
     /// The last message with an intent has been rejected
+    ///
+    /// See also: [`consume_messages_reporting_rejections`]
     IntentRejected(IntentRejected),
 
     /// The message channel is closed.
@@ -121,72 +783,1218 @@ pub enum MessagesConsumed<IntentRejected> {
     /// No progress observed after processing the last message from the channel
     ///
     /// This happens when the channel is empty and no task has been spawned
-    /// after processing the last message.
+    /// after processing the last message. Tasks that have been spawned
+    /// before might still be pending.
     NoProgress,
+
+    /// The system has settled
+    ///
+    /// Like [`Self::NoProgress`], but no tracked tasks are pending. No
+    /// more messages are expected to arrive unless submitted from the
+    /// outside, e.g. it is safe to persist the model before exiting.
+    ///
+    /// Requires task tracking, see `TaskContext::with_task_tracking()`.
+    /// Otherwise [`Self::NoProgress`] is returned.
+    ///
+    /// See also: [`TaskContext::is_quiescent()`]
+    Quiescent,
+
+    /// Processing the last message exceeded the maximum length of the
+    /// effect chain
+    ///
+    /// See also: [`MessageProcessed::EffectChainExceeded`]
+    EffectChainExceeded,
+
+    /// Applying an effect failed while processing the last message
+    ///
+    /// See also: [`MessageProcessed::EffectFailed`]
+    EffectFailed(ApplyError),
+
+    /// Stopped after exceeding
+    /// [`ProcessingOptions::max_consecutive_observed_intents`]
+    ///
+    /// The last observed intent has already been submitted and is
+    /// still pending in the channel.
+    RenderLoopDetected,
+
+    /// The maximum number of messages has been processed and more
+    /// messages might be pending in the channel
+    ///
+    /// See also: [`consume_messages_bounded`]
+    BudgetExhausted,
+
+    /// The stop future has completed
+    ///
+    /// See also: [`consume_messages_until`]
+    Stopped,
+
+    /// No message is ready, but more messages are expected to arrive
+    ///
+    /// Either no message has been processed or the last message has
+    /// made progress. Only returned by the synchronous
+    /// [`try_consume_messages`] instead of awaiting the next message
+    /// and by [`consume_messages_bounded`] after the maximum number of
+    /// messages has been processed.
+    Pending,
 }
 
 /// Receive and process messages until one of the stop conditions are
 /// encountered
 ///
+/// Stops after an intent has been rejected, handing control back to
+/// the caller. Use [`consume_messages_continue_on_reject`] or
+/// [`consume_messages_reporting_rejections`] for continuing instead.
+///
 /// This `async fn` is _cancellation safe_. The only yield point occurs
-/// when receiving the next message from the channel.
-#[allow(clippy::manual_let_else)] // false positive?
-pub async fn consume_messages<M, R, T>(
-    message_rx: &mut MessageReceiver<M::Intent, M::Effect>,
-    task_context: &mut TaskContext<T, M::Intent, M::Effect>,
+/// when receiving the next message from the channel. Messages that have
+/// been received are processed before yielding again, i.e. no message
+/// is lost if the returned future is dropped.
+///
+/// Uses the default [`ProcessingOptions`].
+///
+/// See also: [`consume_messages_with_options`]
+pub async fn consume_messages<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
     model: &mut M,
     render_model: &mut R,
-) -> MessagesConsumed<M::IntentRejected>
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
 where
-    M: Model + fmt::Debug,
-    M::Intent: fmt::Debug,
-    M::IntentRejected: fmt::Debug,
-    M::Effect: fmt::Debug,
-    M::Task: fmt::Debug,
+    M: TryModel + MaybeDebug,
     R: ModelRender<Model = M>,
-    T: TaskExecutor<T, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_with_options(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions::default(),
+    )
+    .await
+}
+
+/// Receive and process messages with custom [`ProcessingOptions`]
+///
+/// See also: [`consume_messages`]
+pub async fn consume_messages_with_options<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
 {
-    let mut next_message: Option<Message<M::Intent, M::Effect>> = None;
+    consume_messages_with_observer(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        options,
+        &mut (),
+    )
+    .await
+}
+
+/// Receive and process messages and notify an observer
+///
+/// See also: [`consume_messages_with_options`]
+pub async fn consume_messages_with_observer<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+    observer: &mut impl ProcessingObserver<M>,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_internal(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        options,
+        None,
+        pin!(pending::<()>()),
+        &mut (),
+        observer,
+        None,
+        &mut stop_on_intent_rejected,
+    )
+    .await
+}
+
+/// Receive and process messages and collect statistics
+///
+/// Returns the outcome together with the [`ProcessingStats`] of all
+/// messages that have been processed during this pass.
+///
+/// See also: [`consume_messages_with_observer`]
+pub async fn consume_messages_with_stats<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+) -> (
+    MessagesConsumed<M::IntentRejected, M::ApplyError>,
+    ProcessingStats,
+)
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let mut stats = ProcessingStats::default();
+    let messages_consumed = consume_messages_with_observer(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        options,
+        &mut stats,
+    )
+    .await;
+    (messages_consumed, stats)
+}
+
+/// Receive and process messages while accumulating the render hint
+///
+/// The render hints of all messages that have been processed during
+/// this pass are added to `render_hint`, which is not reset beforehand.
+/// Afterwards [`ModelRenderHint::should_render_model()`] tells if the
+/// model might have changed during this pass, e.g. for deciding if a
+/// new frame needs to be presented.
+///
+/// See also: [`consume_messages_with_options`]
+pub async fn consume_messages_with_render_hint<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+    render_hint: &mut M::RenderHint,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_internal(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        options,
+        None,
+        pin!(pending::<()>()),
+        &mut (),
+        &mut (),
+        Some(render_hint),
+        &mut stop_on_intent_rejected,
+    )
+    .await
+}
+
+/// Receive and process messages and render the model once per burst
+///
+/// All messages that are ready are processed before rendering the model
+/// once with the accumulated render hint, see
+/// [`ProcessingOptions::coalesce_renders`]. Intended for frame-based
+/// user interfaces that never display intermediate states.
+///
+/// See also: [`consume_messages`]
+pub async fn consume_messages_coalesced<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_with_options(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions {
+            coalesce_renders: true,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Receive and process at most `max_messages` messages
+///
+/// Stops with [`MessagesConsumed::BudgetExhausted`] after `max_messages`
+/// have been processed and the channel is not empty. Pending messages
+/// remain in the channel and are processed by the next invocation.
+///
+/// If the channel is known to be empty, i.e. if
+/// [`MessageTransport::pending_message_count()`] returns `Some(0)`, the
+/// outcome is the same as if no next message was ready:
+/// [`MessagesConsumed::Pending`] if the last message has made progress or
+/// [`MessagesConsumed::NoProgress`] and [`MessagesConsumed::Quiescent`]
+/// otherwise. The bounded and unbounded mpsc transports report the number
+/// of pending messages, other transports that don't always stop with
+/// [`MessagesConsumed::BudgetExhausted`].
+///
+/// Useful for interleaving message processing with other work, e.g. in
+/// a fixed-timestep loop.
+///
+/// Uses the default [`ProcessingOptions`].
+///
+/// See also: [`consume_messages`]
+pub async fn consume_messages_bounded<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    max_messages: usize,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_internal(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions::default(),
+        Some(max_messages),
+        pin!(pending::<()>()),
+        &mut (),
+        &mut (),
+        None,
+        &mut stop_on_intent_rejected,
+    )
+    .await
+}
+
+/// Receive and process messages until either a stop condition is
+/// encountered or the `stop` future completes
+///
+/// The `stop` future is only polled at the single yield point when
+/// awaiting the next message. It takes precedence over receiving the
+/// next message. Stops with [`MessagesConsumed::Stopped`] without losing
+/// any messages, which remain in the channel.
+///
+/// Prefer this function over racing [`consume_messages`] against a
+/// shutdown signal, e.g. by using `select!`.
+///
+/// Uses the default [`ProcessingOptions`].
+pub async fn consume_messages_until<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    stop: impl Future,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_internal(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions::default(),
+        None,
+        pin!(stop),
+        &mut (),
+        &mut (),
+        None,
+        &mut stop_on_intent_rejected,
+    )
+    .await
+}
+
+/// Receive and process messages and report rejected intents instead of
+/// stopping
+///
+/// Rejected intents are passed to `on_intent_rejected`, e.g. for
+/// forwarding them into a separate channel that displays notifications.
+/// Processing then continues as if the message made no progress, i.e.
+/// [`MessagesConsumed::IntentRejected`] is never returned.
+///
+/// See also: [`consume_messages_with_options`]
+pub async fn consume_messages_reporting_rejections<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+    mut on_intent_rejected: impl FnMut(M::IntentRejected),
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_internal(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        options,
+        None,
+        pin!(pending::<()>()),
+        &mut (),
+        &mut (),
+        None,
+        &mut |_model, intent_rejected| {
+            on_intent_rejected(intent_rejected);
+            ControlFlow::Continue(())
+        },
+    )
+    .await
+}
+
+/// Receive and process messages and skip rejected intents
+///
+/// Like [`consume_messages`], but rejected intents are only logged and
+/// don't stop consuming messages, i.e.
+/// [`MessagesConsumed::IntentRejected`] is never returned.
+///
+/// Uses the default [`ProcessingOptions`].
+///
+/// See also: [`consume_messages_reporting_rejections`]
+pub async fn consume_messages_continue_on_reject<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    consume_messages_internal(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions::default(),
+        None,
+        pin!(pending::<()>()),
+        &mut (),
+        &mut (),
+        None,
+        &mut skip_intent_rejected,
+    )
+    .await
+}
+
+/// Stop consuming messages after an intent has been rejected
+fn stop_on_intent_rejected<M>(
+    _model: &M,
+    intent_rejected: M::IntentRejected,
+) -> ControlFlow<M::IntentRejected>
+where
+    M: TryModel,
+{
+    ControlFlow::Break(intent_rejected)
+}
+
+/// Log and skip a rejected intent
+#[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+fn skip_intent_rejected<M>(
+    model: &M,
+    intent_rejected: M::IntentRejected,
+) -> ControlFlow<M::IntentRejected>
+where
+    M: TryModel,
+{
+    log_warn!(
+        "{}Skipping rejected intent: {intent_rejected:?}",
+        LogLabel(model)
+    );
+    drop(intent_rejected);
+    ControlFlow::Continue(())
+}
+
+/// Process all messages that are ready without awaiting
+///
+/// Synchronous variant of [`consume_messages`] for integration into an
+/// event loop, e.g. once per frame. Returns [`MessagesConsumed::Pending`]
+/// instead of awaiting the next message when the channel is empty after
+/// the system made progress. Stops with [`MessagesConsumed::NoProgress`]
+/// or [`MessagesConsumed::Quiescent`] like [`consume_messages`].
+///
+/// Uses the default [`ProcessingOptions`].
+///
+/// See also: [`try_consume_messages_with_options`]
+pub fn try_consume_messages<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    try_consume_messages_with_options(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions::default(),
+    )
+}
+
+/// Process all messages that are ready without awaiting with custom
+/// [`ProcessingOptions`]
+///
+/// Setting [`ProcessingOptions::max_consecutive_observed_intents`] is
+/// recommended to return control to the caller even if rendering the
+/// model keeps producing new intents.
+///
+/// See also: [`try_consume_messages`]
+pub fn try_consume_messages_with_options<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let mut render_loop_detector =
+        RenderLoopDetector::new(options.max_consecutive_observed_intents);
+    let mut processed_count = 0;
+    let mut no_progress = false;
     loop {
-        let message = if let Some(next_message) = next_message.take() {
-            next_message
-        } else {
-            log::trace!("Awaiting next message");
-            let Some(next_message) = message_rx.next().await else {
-                log::debug!("Stopping after message channel closed");
+        let message = match Transport::try_recv(message_rx) {
+            Ok(message) => message,
+            Err(TryRecvError::Closed) => {
+                log_debug!("{}Stopping after message channel closed", LogLabel(model));
                 return MessagesConsumed::ChannelClosed;
-            };
-            next_message
+            }
+            Err(TryRecvError::Empty) => {
+                // Pending tasks are required for making progress.
+                let is_idle = task_context.is_idle();
+                if no_progress || is_idle {
+                    return stop_after_no_progress(model, is_idle);
+                }
+                log_trace!("{}No next message ready", LogLabel(model));
+                return MessagesConsumed::Pending;
+            }
         };
-        debug_assert!(next_message.is_none());
-        log::debug!("Processing message: {message:?}");
-        match process_message(task_context, model, render_model, message) {
-            MessageProcessed::IntentRejected(intent_rejected) => {
-                log::debug!("Stopping after intent rejected: {intent_rejected:?}");
-                return MessagesConsumed::IntentRejected(intent_rejected);
-            }
-            MessageProcessed::Progressing => {
-                // Continue by awaiting the next message that is expected
-                // to arrive eventually
-            }
-            MessageProcessed::NoProgress => {
-                next_message = match message_rx.try_next() {
-                    Ok(Some(next_message)) => Some(next_message),
-                    Ok(None) => {
-                        log::debug!(
-                            "Stopping after no progress observed and message channel closed"
-                        );
-                        return MessagesConsumed::ChannelClosed;
-                    }
-                    Err(_) => {
-                        // The message channel is empty but not closed
-                        log::debug!(
-                            "Stopping after no progress observed and no next message ready"
-                        );
-                        return MessagesConsumed::NoProgress;
+        if options.coalesce_renders {
+            if let Some(stopped) = process_ready_messages_coalesced(
+                message_rx,
+                task_context,
+                model,
+                render_model,
+                message,
+                options,
+                &mut processed_count,
+                None,
+                &mut render_loop_detector,
+                &mut (),
+                &mut (),
+                None,
+                &mut stop_on_intent_rejected,
+            ) {
+                return stopped;
+            }
+            continue;
+        }
+        log_debug!("{}Processing message: {message:?}", LogLabel(model));
+        let (message_processed, observed_intent_count) = process_message_observing_intents(
+            task_context,
+            model,
+            render_model,
+            message,
+            options,
+            &mut (),
+            &mut (),
+            None,
+        );
+        if render_loop_detector.is_render_loop(model, observed_intent_count) {
+            return MessagesConsumed::RenderLoopDetected;
+        }
+        match continue_after_message_processed(
+            model,
+            message_processed,
+            &mut stop_on_intent_rejected,
+        ) {
+            ControlFlow::Continue(progressing) => no_progress = !progressing,
+            ControlFlow::Break(stopped) => return stopped,
+        }
+    }
+}
+
+/// Decide if consuming messages continues after processing a message
+///
+/// Continues with the progress indicator or breaks with the stop
+/// condition.
+fn continue_after_message_processed<M>(
+    model: &M,
+    message_processed: MessageProcessed<M::IntentRejected, M::RenderHint, M::ApplyError>,
+    on_intent_rejected: &mut impl FnMut(&M, M::IntentRejected) -> ControlFlow<M::IntentRejected>,
+) -> ControlFlow<MessagesConsumed<M::IntentRejected, M::ApplyError>, bool>
+where
+    M: TryModel,
+{
+    match message_processed {
+        MessageProcessed::IntentRejected(intent_rejected) => {
+            continue_after_intent_rejected(model, intent_rejected, on_intent_rejected)
+                .map_break(MessagesConsumed::IntentRejected)
+                .map_continue(|()| false)
+        }
+        MessageProcessed::EffectChainExceeded => {
+            log_debug!("{}Stopping after effect chain exceeded", LogLabel(model));
+            ControlFlow::Break(MessagesConsumed::EffectChainExceeded)
+        }
+        MessageProcessed::EffectFailed(apply_error) => {
+            log_debug!(
+                "{}Stopping after effect failed: {apply_error:?}",
+                LogLabel(model)
+            );
+            ControlFlow::Break(MessagesConsumed::EffectFailed(apply_error))
+        }
+        MessageProcessed::Progressing { .. } => ControlFlow::Continue(true),
+        MessageProcessed::NoProgress { .. } => ControlFlow::Continue(false),
+    }
+}
+
+/// Decide if consuming messages continues after an intent has been rejected
+///
+/// Rejected intents that are consumed by the handler don't stop consuming
+/// messages. The message is then considered as not making progress.
+#[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+fn continue_after_intent_rejected<M>(
+    model: &M,
+    intent_rejected: M::IntentRejected,
+    on_intent_rejected: &mut impl FnMut(&M, M::IntentRejected) -> ControlFlow<M::IntentRejected>,
+) -> ControlFlow<M::IntentRejected>
+where
+    M: TryModel,
+{
+    match on_intent_rejected(model, intent_rejected) {
+        ControlFlow::Break(intent_rejected) => {
+            log_debug!(
+                "{}Stopping after intent rejected: {intent_rejected:?}",
+                LogLabel(model)
+            );
+            ControlFlow::Break(intent_rejected)
+        }
+        ControlFlow::Continue(()) => {
+            log_debug!("{}Continuing after intent rejected", LogLabel(model));
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+async fn consume_messages_internal<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+    max_messages: Option<usize>,
+    mut stop: Pin<&mut impl Future>,
+    middleware: &mut impl Middleware<M>,
+    observer: &mut impl ProcessingObserver<M>,
+    mut render_hint_sum: Option<&mut M::RenderHint>,
+    on_intent_rejected: &mut impl FnMut(&M, M::IntentRejected) -> ControlFlow<M::IntentRejected>,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let ProcessingOptions {
+        max_effect_chain_len: _,
+        max_consecutive_observed_intents,
+        coalesce_renders,
+        yield_interval,
+    } = options;
+    let mut render_loop_detector = RenderLoopDetector::new(max_consecutive_observed_intents);
+    let mut yield_scheduler = YieldScheduler::new(yield_interval);
+    let mut processed_count = 0;
+    loop {
+        match MessageBudget::check(model, max_messages, processed_count, task_context) {
+            MessageBudget::Available => (),
+            MessageBudget::Exhausted => return MessagesConsumed::BudgetExhausted,
+            MessageBudget::ExhaustedChannelEmpty => return MessagesConsumed::Pending,
+        }
+        yield_scheduler.maybe_yield(model, processed_count).await;
+        log_trace!("{}Awaiting next message", LogLabel(model));
+        let next_message = poll_fn(|cx| {
+            if stop.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+            Transport::poll_next(message_rx, cx).map(Some)
+        })
+        .await;
+        let Some(next_message) = next_message else {
+            log_debug!("{}Stopping after stop future completed", LogLabel(model));
+            return MessagesConsumed::Stopped;
+        };
+        let Some(mut message) = next_message else {
+            log_debug!("{}Stopping after message channel closed", LogLabel(model));
+            return MessagesConsumed::ChannelClosed;
+        };
+        if coalesce_renders {
+            if let Some(stopped) = process_ready_messages_coalesced(
+                message_rx,
+                task_context,
+                model,
+                render_model,
+                message,
+                options,
+                &mut processed_count,
+                max_messages,
+                &mut render_loop_detector,
+                middleware,
+                observer,
+                render_hint_sum.as_deref_mut(),
+                on_intent_rejected,
+            ) {
+                return stopped;
+            }
+            continue;
+        }
+        // Process all messages that are ready without yielding.
+        loop {
+            log_debug!("{}Processing message: {message:?}", LogLabel(model));
+            processed_count += 1;
+            let (message_processed, observed_intent_count) = process_message_observing_intents(
+                task_context,
+                model,
+                render_model,
+                message,
+                options,
+                middleware,
+                observer,
+                render_hint_sum.as_deref_mut(),
+            );
+            if render_loop_detector.is_render_loop(model, observed_intent_count) {
+                return MessagesConsumed::RenderLoopDetected;
+            }
+            match continue_after_message_processed(model, message_processed, on_intent_rejected) {
+                ControlFlow::Break(stopped) => return stopped,
+                ControlFlow::Continue(true) => {
+                    // Continue by awaiting the next message that is expected
+                    // to arrive eventually
+                    break;
+                }
+                ControlFlow::Continue(false) => {
+                    match MessageBudget::check(model, max_messages, processed_count, task_context) {
+                        MessageBudget::Available => (),
+                        MessageBudget::Exhausted => return MessagesConsumed::BudgetExhausted,
+                        MessageBudget::ExhaustedChannelEmpty => {
+                            return stop_after_no_progress(model, task_context.is_idle());
+                        }
                     }
-                };
+                    yield_scheduler.maybe_yield(model, processed_count).await;
+                    message = match Transport::try_recv(message_rx) {
+                        Ok(next_message) => next_message,
+                        Err(TryRecvError::Closed) => {
+                            log_debug!(
+                                "{}Stopping after no progress observed and message channel closed",
+                                LogLabel(model)
+                            );
+                            return MessagesConsumed::ChannelClosed;
+                        }
+                        Err(TryRecvError::Empty) => {
+                            // The message channel is empty but not closed
+                            return stop_after_no_progress(model, task_context.is_idle());
+                        }
+                    };
+                }
             }
         }
     }
 }
+
+/// Stop after no progress observed and no next message ready
+///
+/// The system has settled if no tracked tasks are pending.
+#[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+fn stop_after_no_progress<M>(
+    model: &M,
+    is_idle: bool,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel,
+{
+    if is_idle {
+        log_debug!(
+            "{}Stopping after no progress observed, no next message ready, and no pending tasks",
+            LogLabel(model)
+        );
+        MessagesConsumed::Quiescent
+    } else {
+        log_debug!(
+            "{}Stopping after no progress observed and no next message ready",
+            LogLabel(model)
+        );
+        MessagesConsumed::NoProgress
+    }
+}
+
+/// The remaining budget of [`consume_messages_bounded`]
+///
+/// The budget is checked before receiving the next message that
+/// would otherwise get lost.
+enum MessageBudget {
+    /// More messages could be processed
+    Available,
+
+    /// No more messages could be processed and the channel is either
+    /// non-empty or its state is unknown
+    Exhausted,
+
+    /// No more messages could be processed and the channel is empty
+    ExhaustedChannelEmpty,
+}
+
+impl MessageBudget {
+    #[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+    fn check<M, T, Transport>(
+        model: &M,
+        max_messages: Option<usize>,
+        processed_count: usize,
+        task_context: &TaskContext<T, M::Intent, M::Effect, Transport>,
+    ) -> Self
+    where
+        M: TryModel,
+        Transport: MessageTransport<M::Intent, M::Effect>,
+    {
+        match max_messages {
+            Some(max) if processed_count >= max => (),
+            _ => return Self::Available,
+        }
+        if task_context.message_port().pending_message_count() == Some(0) {
+            log_trace!(
+                "{}Budget exhausted after processing {processed_count} message(s) and no next \
+                 message ready",
+                LogLabel(model)
+            );
+            Self::ExhaustedChannelEmpty
+        } else {
+            log_debug!(
+                "{}Stopping after processing {processed_count} message(s)",
+                LogLabel(model)
+            );
+            Self::Exhausted
+        }
+    }
+}
+
+/// Yields to the executor periodically while consuming messages
+pub(crate) struct YieldScheduler {
+    yield_interval: Option<NonZeroUsize>,
+    last_yield_processed_count: usize,
+}
+
+impl YieldScheduler {
+    pub(crate) const fn new(yield_interval: Option<NonZeroUsize>) -> Self {
+        Self {
+            yield_interval,
+            last_yield_processed_count: 0,
+        }
+    }
+
+    /// Check if the interval has elapsed since the last yield
+    ///
+    /// The caller is expected to yield if `true` is returned.
+    pub(crate) fn is_due(&mut self, processed_count: usize) -> bool {
+        let Some(yield_interval) = self.yield_interval else {
+            return false;
+        };
+        if processed_count - self.last_yield_processed_count < yield_interval.get() {
+            return false;
+        }
+        self.last_yield_processed_count = processed_count;
+        true
+    }
+
+    /// Yield once if the interval has elapsed since the last yield
+    #[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+    async fn maybe_yield<M>(&mut self, model: &M, processed_count: usize)
+    where
+        M: TryModel,
+    {
+        if !self.is_due(processed_count) {
+            return;
+        }
+        log_trace!(
+            "{}Yielding after processing {processed_count} message(s)",
+            LogLabel(model)
+        );
+        yield_now().await;
+    }
+}
+
+/// Yield once to the executor
+pub(crate) async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await;
+}
+
+/// Detects render loops by counting consecutive messages with
+/// observed intents
+pub(crate) struct RenderLoopDetector {
+    max_consecutive_observed_intents: Option<NonZeroUsize>,
+    consecutive_observed_intents: usize,
+}
+
+impl RenderLoopDetector {
+    pub(crate) const fn new(max_consecutive_observed_intents: Option<NonZeroUsize>) -> Self {
+        Self {
+            max_consecutive_observed_intents,
+            consecutive_observed_intents: 0,
+        }
+    }
+
+    /// Count the observed intents of the last message
+    ///
+    /// Returns the number of consecutive messages with observed intents
+    /// if the maximum has been exceeded.
+    pub(crate) fn exceeded(&mut self, observed_intent_count: usize) -> Option<usize> {
+        if observed_intent_count == 0 {
+            self.consecutive_observed_intents = 0;
+            return None;
+        }
+        self.consecutive_observed_intents += 1;
+        let max = self.max_consecutive_observed_intents?;
+        (self.consecutive_observed_intents > max.get()).then_some(self.consecutive_observed_intents)
+    }
+
+    #[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+    fn is_render_loop<M>(&mut self, model: &M, observed_intent_count: usize) -> bool
+    where
+        M: TryModel,
+    {
+        let Some(consecutive_observed_intents) = self.exceeded(observed_intent_count) else {
+            return false;
+        };
+        log_warn!(
+            "{}Stopping after {consecutive_observed_intents} consecutive message(s) with \
+             observed intents",
+            LogLabel(model),
+        );
+        true
+    }
+}
+
+/// Apply the given and all ready messages and render the model once
+/// afterwards
+///
+/// Returns the stop condition, if any.
+#[allow(clippy::too_many_arguments)]
+fn process_ready_messages_coalesced<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    mut message: Message<M::Intent, M::Effect>,
+    options: ProcessingOptions,
+    processed_count: &mut usize,
+    max_messages: Option<usize>,
+    render_loop_detector: &mut RenderLoopDetector,
+    middleware: &mut impl Middleware<M>,
+    observer: &mut impl ProcessingObserver<M>,
+    render_hint_sum: Option<&mut M::RenderHint>,
+    on_intent_rejected: &mut impl FnMut(&M, M::IntentRejected) -> ControlFlow<M::IntentRejected>,
+) -> Option<MessagesConsumed<M::IntentRejected, M::ApplyError>>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let mut burst_applied = EffectChainApplied::<M::RenderHint, M::ApplyError>::new();
+    let stopped = loop {
+        log_debug!("{}Processing message: {message:?}", LogLabel(model));
+        *processed_count += 1;
+        let message_applied = apply_message_with_middleware(
+            task_context,
+            model,
+            message,
+            options.max_effect_chain_len,
+            middleware,
+            observer,
+        );
+        // Rendering is deferred until all ready messages have been applied.
+        observer.on_message_processed();
+        match message_applied {
+            Ok(chain_applied) => {
+                let EffectChainApplied {
+                    render_hint,
+                    tasks_spawned,
+                    effect_discarded: _,
+                    effect_chain_exceeded,
+                    effect_failed,
+                } = chain_applied;
+                burst_applied.render_hint += render_hint;
+                burst_applied.tasks_spawned += tasks_spawned;
+                if let Some(apply_error) = effect_failed {
+                    log_debug!(
+                        "{}Stopping after effect failed: {apply_error:?}",
+                        LogLabel(model)
+                    );
+                    break Some(MessagesConsumed::EffectFailed(apply_error));
+                }
+                if effect_chain_exceeded {
+                    log_debug!("{}Stopping after effect chain exceeded", LogLabel(model));
+                    break Some(MessagesConsumed::EffectChainExceeded);
+                }
+            }
+            Err(intent_rejected) => {
+                if let ControlFlow::Break(intent_rejected) =
+                    continue_after_intent_rejected(model, intent_rejected, on_intent_rejected)
+                {
+                    break Some(MessagesConsumed::IntentRejected(intent_rejected));
+                }
+            }
+        }
+        match MessageBudget::check(model, max_messages, *processed_count, task_context) {
+            MessageBudget::Available => (),
+            MessageBudget::Exhausted => break Some(MessagesConsumed::BudgetExhausted),
+            // Continue as if no next message is ready without trying
+            // to receive it.
+            MessageBudget::ExhaustedChannelEmpty => break None,
+        }
+        match Transport::try_recv(message_rx) {
+            Ok(next_message) => message = next_message,
+            Err(TryRecvError::Closed) => {
+                log_debug!("{}Stopping after message channel closed", LogLabel(model));
+                break Some(MessagesConsumed::ChannelClosed);
+            }
+            Err(TryRecvError::Empty) => break None,
+        }
+    };
+    if let Some(render_hint_sum) = render_hint_sum {
+        *render_hint_sum += burst_applied.render_hint.clone();
+    }
+    // All changes are rendered, even if stopping afterwards.
+    let (message_processed, observed_intent_count) =
+        render_effect_chain(task_context, model, render_model, burst_applied, observer);
+    if stopped.is_some() {
+        return stopped;
+    }
+    if render_loop_detector.is_render_loop(model, observed_intent_count) {
+        return Some(MessagesConsumed::RenderLoopDetected);
+    }
+    if matches!(message_processed, MessageProcessed::NoProgress { .. }) {
+        return Some(stop_after_no_progress(model, task_context.is_idle()));
+    }
+    None
+}
+
+/// Run the message loop of a model
+///
+/// Invokes [`TryModel::on_start()`] before and [`TryModel::on_stop()`] after
+/// consuming messages. The result of [`TryModel::on_start()`] is processed
+/// like the result of any other effect. No messages are consumed if
+/// applying the resulting effects either fails or exceeds the maximum
+/// length of the effect chain.
+///
+/// Messages are consumed while the model is idle and busy in turns until
+/// either an intent is rejected, applying an effect fails, or no more
+/// messages could arrive. The latter is reported as
+/// [`MessagesConsumed::ChannelClosed`] after all message ports except
+/// the one of `task_context` have been dropped, including those that have
+/// been passed to spawned tasks, and the channel is empty. The port of
+/// `task_context` doesn't keep the loop running. This requires a
+/// [`MessageTransport`] that reports the number of pending messages.
+///
+/// [`TryModel::on_stop()`] is not invoked if the returned future is dropped
+/// before completion.
+///
+#[cfg_attr(feature = "std", doc = "See also: [`run_model`], [`consume_messages`]")]
+#[cfg_attr(not(feature = "std"), doc = "See also: [`consume_messages`]")]
+pub async fn run_model_with_context<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    run_model_internal(
+        message_rx,
+        task_context,
+        model,
+        render_model,
+        ProcessingOptions::default(),
+        &mut (),
+        &mut (),
+    )
+    .await
+}
+
+pub(crate) async fn run_model_internal<M, R, T, Transport>(
+    message_rx: &mut MessageReceiver<M::Intent, M::Effect, Transport>,
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    options: ProcessingOptions,
+    middleware: &mut impl Middleware<M>,
+    observer: &mut impl ProcessingObserver<M>,
+) -> MessagesConsumed<M::IntentRejected, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    log_debug!("{}Starting model: {model:?}", LogLabel(model));
+    let effect_applied = model.on_start();
+    let chain_applied = apply_effect_chain(
+        task_context,
+        model,
+        effect_applied,
+        options.max_effect_chain_len,
+        observer,
+    );
+    // Otherwise the outcome is irrelevant, because consuming messages
+    // always starts by awaiting the next message.
+    let started_failed =
+        match render_effect_chain(task_context, model, render_model, chain_applied, observer) {
+            (MessageProcessed::EffectFailed(apply_error), _) => {
+                Some(MessagesConsumed::EffectFailed(apply_error))
+            }
+            (MessageProcessed::EffectChainExceeded, _) => {
+                Some(MessagesConsumed::EffectChainExceeded)
+            }
+            _ => None,
+        };
+    if let Some(messages_consumed) = started_failed {
+        log_debug!(
+            "{}Stopping model after starting failed: {messages_consumed:?}",
+            LogLabel(model)
+        );
+        model.on_stop();
+        return messages_consumed;
+    }
+    // The message port of the context must not prevent the loop from
+    // stopping after all other ports have been dropped.
+    let own_port = task_context.message_port().downgrade();
+    let messages_consumed = loop {
+        let messages_consumed = consume_messages_internal(
+            message_rx,
+            task_context,
+            model,
+            render_model,
+            options,
+            None,
+            pin!(poll_fn(|cx| own_port.poll_last_port_idle(cx))),
+            middleware,
+            observer,
+            None,
+            &mut stop_on_intent_rejected,
+        )
+        .await;
+        match messages_consumed {
+            MessagesConsumed::NoProgress | MessagesConsumed::Quiescent => {
+                log_debug!("{}Awaiting more messages", LogLabel(model));
+            }
+            MessagesConsumed::Stopped => {
+                log_debug!(
+                    "{}No more messages after all other message ports have been dropped",
+                    LogLabel(model)
+                );
+                break MessagesConsumed::ChannelClosed;
+            }
+            messages_consumed => break messages_consumed,
+        }
+    };
+    log_debug!("{}Stopping model: {messages_consumed:?}", LogLabel(model));
+    model.on_stop();
+    messages_consumed
+}
+
+/// Run the message loop of a model on a new message channel
+///
+/// Creates a bounded message channel with the given capacity and the
+/// [`TaskContext`] for `task_executor`. The [`MessagePort`] of the channel
+/// is passed to `with_message_port` before the message loop starts, e.g.
+/// for submitting the first intent.
+///
+/// Returns the model together with the condition that stopped consuming
+/// messages. The model keeps running until all message ports have been
+/// dropped, including those that have been passed to spawned tasks, see
+/// [`run_model_with_context`].
+///
+/// Requires the `std` feature.
+///
+/// See also: [`run_model_with_context`]
+#[cfg(feature = "std")]
+pub async fn run_model<M, R, T>(
+    mut model: M,
+    mut render_model: R,
+    task_executor: T,
+    channel_capacity: usize,
+    with_message_port: impl FnOnce(MessagePort<M::Intent, M::Effect>),
+) -> (M, MessagesConsumed<M::IntentRejected, M::ApplyError>)
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+{
+    let (message_port, mut message_rx) = message_channel(channel_capacity).split();
+    let mut task_context = TaskContext::new(task_executor, message_port.clone());
+    with_message_port(message_port);
+    let messages_consumed = run_model_with_context(
+        &mut message_rx,
+        &mut task_context,
+        &mut model,
+        &mut render_model,
+    )
+    .await;
+    (model, messages_consumed)
+}
@@ -0,0 +1,382 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use std::{
+    fmt,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    logging::{log_trace, log_warn},
+    BoxedTaskFuture, MaybeDebug, MessageTransport, ModelRender, ModelRenderHint, TaskContext,
+    TaskExecutor, TryModel,
+};
+
+/// Source of the current time
+///
+/// Could be replaced by a manually controlled clock for testing.
+pub trait Clock {
+    /// The current time
+    fn now(&self) -> Instant;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Requests a deferred render after a delay
+///
+/// Implemented for all `FnMut(Duration)` closures. The unit type `()`
+/// doesn't request deferred renders, i.e. the host is responsible for
+/// requesting them.
+///
+/// See also: [`render_due_scheduler()`]
+pub trait RenderScheduler {
+    /// Request rendering the model after `delay` has elapsed
+    fn schedule_render(&mut self, delay: Duration);
+}
+
+impl RenderScheduler for () {
+    fn schedule_render(&mut self, _delay: Duration) {}
+}
+
+impl<F> RenderScheduler for F
+where
+    F: FnMut(Duration),
+{
+    fn schedule_render(&mut self, delay: Duration) {
+        self(delay);
+    }
+}
+
+/// Submit a "render due" intent after a delay
+///
+/// Spawns a task that awaits `sleep(delay)`, e.g. `tokio::time::sleep`,
+/// and then submits the intent that is created by `render_due`. The model
+/// must request rendering when handling this intent, e.g. by accepting it
+/// with [`crate::EffectApplied::render_now()`].
+///
+/// The task is created from a [`BoxedTaskFuture`], like in
+/// [`TaskContext::spawn_fallible_task()`].
+pub fn render_due_scheduler<T, Intent, Effect, Transport, S>(
+    task_context: TaskContext<T, Intent, Effect, Transport>,
+    sleep: impl Fn(Duration) -> S,
+    render_due: impl Fn() -> Intent,
+) -> impl RenderScheduler
+where
+    S: Future<Output = ()> + Send + 'static,
+    Intent: MaybeDebug + Send + 'static,
+    Effect: MaybeDebug + Send + 'static,
+    T: TaskExecutor<T, Transport, Intent = Intent, Effect = Effect> + Clone,
+    T::Task: From<BoxedTaskFuture>,
+    Transport: MessageTransport<Intent, Effect> + 'static,
+    Transport::Sender: Send + Sync,
+{
+    move |delay| {
+        let sleep = sleep(delay);
+        let intent = render_due();
+        let mut message_port = task_context.clone_port();
+        let task_future: BoxedTaskFuture = Box::pin(async move {
+            sleep.await;
+            if message_port.submit_intent_async(intent).await.is_err() {
+                log_warn!("Failed to submit render due intent - channel is closed");
+            }
+        });
+        task_context.spawn_task(task_future);
+    }
+}
+
+type RenderHintOf<R> = <<R as ModelRender>::Model as TryModel>::RenderHint;
+
+/// Renders the model at most once within a minimum interval
+///
+/// Renders that are requested within the interval after the last render
+/// are suppressed and their render hints are accumulated. The deferred
+/// render is requested from the [`RenderScheduler`] once, i.e. the final
+/// state is rendered after the quiet period with the accumulated render
+/// hints.
+///
+/// ```
+/// use std::{
+///     cell::{Cell, RefCell},
+///     convert::Infallible,
+///     rc::Rc,
+///     time::{Duration, Instant},
+/// };
+///
+/// use infect::{
+///     Clock, DebouncedRender, EffectApplied, IntentHandled, Model, ModelChanged, ModelRender,
+/// };
+///
+/// #[derive(Debug)]
+/// struct Counter(u32);
+///
+/// #[derive(Debug)]
+/// struct RenderDue;
+///
+/// impl Model for Counter {
+///     type Intent = RenderDue;
+///     type IntentRejected = Infallible;
+///     type Effect = u32;
+///     type Task = ();
+///     type RenderHint = ModelChanged;
+///
+///     fn handle_intent(
+///         &mut self,
+///         RenderDue: RenderDue,
+///     ) -> IntentHandled<Infallible, u32, (), ModelChanged> {
+///         IntentHandled::Accepted(EffectApplied::render_now())
+///     }
+///
+///     fn apply_effect(&mut self, effect: u32) -> EffectApplied<u32, (), ModelChanged> {
+///         self.0 += effect;
+///         EffectApplied::maybe_changed()
+///     }
+/// }
+///
+/// #[derive(Default)]
+/// struct Renderer(Vec<u32>);
+///
+/// impl ModelRender for Renderer {
+///     type Model = Counter;
+///
+///     fn render_model(&mut self, model: &Counter, _hint: ModelChanged) -> Option<RenderDue> {
+///         self.0.push(model.0);
+///         None
+///     }
+/// }
+///
+/// #[derive(Clone)]
+/// struct ManualClock(Rc<Cell<Instant>>);
+///
+/// impl ManualClock {
+///     fn advance(&self, duration: Duration) {
+///         self.0.set(self.0.get() + duration);
+///     }
+/// }
+///
+/// impl Clock for ManualClock {
+///     fn now(&self) -> Instant {
+///         self.0.get()
+///     }
+/// }
+///
+/// let clock = ManualClock(Rc::new(Cell::new(Instant::now())));
+/// let scheduled = Rc::new(RefCell::new(Vec::new()));
+/// let mut render_model = DebouncedRender::with_clock(
+///     Renderer::default(),
+///     Duration::from_millis(100),
+///     clock.clone(),
+///     {
+///         let scheduled = Rc::clone(&scheduled);
+///         move |delay| scheduled.borrow_mut().push(delay)
+///     },
+/// );
+/// let mut model = Counter(0);
+///
+/// // The first render is not deferred.
+/// model.apply_effect(1);
+/// let _ = render_model.render_model(&model, ModelChanged::MaybeChanged);
+/// assert_eq!(render_model.inner().0, [1]);
+///
+/// // Subsequent renders within the interval are deferred once.
+/// for _ in 0..3 {
+///     clock.advance(Duration::from_millis(10));
+///     model.apply_effect(1);
+///     let _ = render_model.render_model(&model, ModelChanged::MaybeChanged);
+/// }
+/// assert_eq!(render_model.inner().0, [1]);
+/// assert_eq!(*scheduled.borrow(), [Duration::from_millis(90)]);
+///
+/// // The scheduled "render due" intent renders the final state.
+/// clock.advance(Duration::from_millis(70));
+/// let IntentHandled::Accepted(effect_applied) = model.handle_intent(RenderDue) else {
+///     unreachable!();
+/// };
+/// let _ = render_model.render_model(&model, effect_applied.render_hint);
+/// assert_eq!(render_model.inner().0, [1, 4]);
+/// ```
+pub struct DebouncedRender<R, C = SystemClock, S = ()>
+where
+    R: ModelRender,
+{
+    render_model: R,
+    min_interval: Duration,
+    clock: C,
+    render_scheduler: S,
+    last_rendered_at: Option<Instant>,
+    pending_render_hint: RenderHintOf<R>,
+    render_scheduled: bool,
+}
+
+impl<R, S> DebouncedRender<R, SystemClock, S>
+where
+    R: ModelRender,
+    S: RenderScheduler,
+{
+    /// Create a new instance that uses the [`SystemClock`]
+    ///
+    /// Deferred renders are requested from `render_scheduler`, e.g.
+    /// by [`render_due_scheduler()`].
+    #[must_use]
+    pub fn new(render_model: R, min_interval: Duration, render_scheduler: S) -> Self {
+        Self::with_clock(render_model, min_interval, SystemClock, render_scheduler)
+    }
+}
+
+impl<R, C, S> DebouncedRender<R, C, S>
+where
+    R: ModelRender,
+    C: Clock,
+    S: RenderScheduler,
+{
+    /// Create a new instance with a custom [`Clock`]
+    ///
+    /// See also: [`Self::new()`]
+    #[must_use]
+    pub fn with_clock(
+        render_model: R,
+        min_interval: Duration,
+        clock: C,
+        render_scheduler: S,
+    ) -> Self {
+        Self {
+            render_model,
+            min_interval,
+            clock,
+            render_scheduler,
+            last_rendered_at: None,
+            pending_render_hint: Default::default(),
+            render_scheduled: false,
+        }
+    }
+
+    /// The wrapped renderer
+    #[must_use]
+    pub const fn inner(&self) -> &R {
+        &self.render_model
+    }
+
+    /// The wrapped renderer for manipulating it directly
+    #[must_use]
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.render_model
+    }
+
+    /// Consume the wrapper and return the wrapped renderer
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.render_model
+    }
+
+    /// The time when the deferred render is due
+    ///
+    /// Returns `None` if no render has been deferred.
+    #[must_use]
+    pub fn render_due_at(&self) -> Option<Instant> {
+        if !self.pending_render_hint.should_render_model() {
+            return None;
+        }
+        let Some(last_rendered_at) = self.last_rendered_at else {
+            return Some(self.clock.now());
+        };
+        Some(last_rendered_at + self.min_interval)
+    }
+
+    /// Perform the deferred render if it is due
+    ///
+    /// Returns the observed intents of the wrapped renderer.
+    pub fn render_pending<'a, 'b>(
+        &'a mut self,
+        model: &'b R::Model,
+    ) -> impl IntoIterator<Item = <R::Model as TryModel>::Intent> + use<'a, 'b, R, C, S> {
+        let observed_intents = if self.pending_render_hint.should_render_model() {
+            self.render_debounced(model, Default::default())
+        } else {
+            None
+        };
+        observed_intents.into_iter().flatten()
+    }
+
+    fn render_debounced<'a, 'b>(
+        &'a mut self,
+        model: &'b R::Model,
+        hint: RenderHintOf<R>,
+    ) -> Option<impl IntoIterator<Item = <R::Model as TryModel>::Intent> + use<'a, 'b, R, C, S>>
+    {
+        self.pending_render_hint += hint;
+        let now = self.clock.now();
+        if let Some(last_rendered_at) = self.last_rendered_at {
+            let render_due_at = last_rendered_at + self.min_interval;
+            if now < render_due_at {
+                log_trace!("Deferring render");
+                if !self.render_scheduled {
+                    self.render_scheduled = true;
+                    self.render_scheduler.schedule_render(render_due_at - now);
+                }
+                return None;
+            }
+        }
+        self.last_rendered_at = Some(now);
+        self.render_scheduled = false;
+        let hint = core::mem::take(&mut self.pending_render_hint);
+        Some(self.render_model.render_model(model, hint))
+    }
+}
+
+impl<R, C, S> ModelRender for DebouncedRender<R, C, S>
+where
+    R: ModelRender,
+    C: Clock,
+    S: RenderScheduler,
+{
+    type Model = R::Model;
+
+    fn should_render(&self, model: &Self::Model, hint: &RenderHintOf<R>) -> bool {
+        self.render_model.should_render(model, hint)
+    }
+
+    fn render_model(
+        &mut self,
+        model: &Self::Model,
+        hint: RenderHintOf<R>,
+    ) -> impl IntoIterator<Item = <Self::Model as TryModel>::Intent> {
+        self.render_debounced(model, hint).into_iter().flatten()
+    }
+
+    fn accept_observed_intent(&self, intent: &<Self::Model as TryModel>::Intent) -> bool {
+        self.render_model.accept_observed_intent(intent)
+    }
+}
+
+impl<R, C, S> fmt::Debug for DebouncedRender<R, C, S>
+where
+    R: ModelRender + fmt::Debug,
+    C: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            render_model,
+            min_interval,
+            clock,
+            render_scheduler: _,
+            last_rendered_at,
+            pending_render_hint: _,
+            render_scheduled,
+        } = self;
+        f.debug_struct("DebouncedRender")
+            .field("render_model", render_model)
+            .field("min_interval", min_interval)
+            .field("clock", clock)
+            .field("last_rendered_at", last_rendered_at)
+            .field("render_scheduled", render_scheduled)
+            .finish_non_exhaustive()
+    }
+}
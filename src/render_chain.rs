@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::vec::Vec;
+
+use crate::{ModelRender, TryModel};
+
+type RenderHintOf<R> = <<R as ModelRender>::Model as TryModel>::RenderHint;
+
+type IntentOf<R> = <<R as ModelRender>::Model as TryModel>::Intent;
+
+/// Render the model if requested and collect the accepted observed intents
+fn render_accepted<R>(
+    render_model: &mut R,
+    model: &R::Model,
+    hint: RenderHintOf<R>,
+    observed_intents: &mut Vec<IntentOf<R>>,
+) where
+    R: ModelRender,
+{
+    if !render_model.should_render(model, &hint) {
+        return;
+    }
+    let intents: Vec<_> = render_model.render_model(model, hint).into_iter().collect();
+    observed_intents.extend(
+        intents
+            .into_iter()
+            .filter(|intent| render_model.accept_observed_intent(intent)),
+    );
+}
+
+/// Renders the same model with two renderers in order
+///
+/// The model is rendered if any of the renderers wants to render it.
+/// Each renderer is consulted separately and only invoked if it wants
+/// to render the model. The observed intents of the first renderer are
+/// enqueued before those of the second renderer. Each renderer decides
+/// about accepting its own observed intents.
+///
+/// Could be nested for chaining more than two renderers.
+///
+/// See also: [`render_chain()`], [`RenderAll`]
+#[derive(Debug, Clone, Default)]
+pub struct RenderChain<A, B> {
+    /// The first renderer
+    pub first: A,
+
+    /// The second renderer
+    pub second: B,
+}
+
+/// Chain two renderers
+///
+/// See also: [`RenderChain`]
+#[must_use]
+pub const fn render_chain<A, B>(first: A, second: B) -> RenderChain<A, B>
+where
+    A: ModelRender,
+    B: ModelRender<Model = A::Model>,
+{
+    RenderChain { first, second }
+}
+
+impl<A, B> ModelRender for RenderChain<A, B>
+where
+    A: ModelRender,
+    B: ModelRender<Model = A::Model>,
+{
+    type Model = A::Model;
+
+    fn should_render(&self, model: &Self::Model, hint: &RenderHintOf<A>) -> bool {
+        let Self { first, second } = self;
+        first.should_render(model, hint) || second.should_render(model, hint)
+    }
+
+    fn render_model(
+        &mut self,
+        model: &Self::Model,
+        hint: RenderHintOf<A>,
+    ) -> impl IntoIterator<Item = IntentOf<A>> {
+        let Self { first, second } = self;
+        let mut observed_intents = Vec::new();
+        render_accepted(first, model, hint.clone(), &mut observed_intents);
+        render_accepted(second, model, hint, &mut observed_intents);
+        observed_intents
+    }
+}
+
+/// Renders the same model with multiple renderers of the same type in order
+///
+/// The model is rendered if any of the renderers wants to render it.
+/// Each renderer is consulted separately and only invoked if it wants
+/// to render the model. The observed intents are enqueued in the order
+/// of the renderers. Each renderer decides about accepting its own
+/// observed intents.
+///
+/// See also: [`RenderChain`]
+#[derive(Debug, Clone)]
+pub struct RenderAll<R> {
+    /// The renderers
+    pub renders: Vec<R>,
+}
+
+impl<R> RenderAll<R> {
+    /// Create a new instance
+    #[must_use]
+    pub const fn new(renders: Vec<R>) -> Self {
+        Self { renders }
+    }
+}
+
+impl<R> Default for RenderAll<R> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<R> FromIterator<R> for RenderAll<R> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<R> ModelRender for RenderAll<R>
+where
+    R: ModelRender,
+{
+    type Model = R::Model;
+
+    fn should_render(&self, model: &Self::Model, hint: &RenderHintOf<R>) -> bool {
+        self.renders
+            .iter()
+            .any(|render| render.should_render(model, hint))
+    }
+
+    fn render_model(
+        &mut self,
+        model: &Self::Model,
+        hint: RenderHintOf<R>,
+    ) -> impl IntoIterator<Item = IntentOf<R>> {
+        let mut observed_intents = Vec::new();
+        for render in &mut self.renders {
+            render_accepted(render, model, hint.clone(), &mut observed_intents);
+        }
+        observed_intents
+    }
+}
@@ -0,0 +1,411 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use std::fmt;
+
+use crate::{
+    message_channel, processing::run_model_internal, MaybeDebug, MessagePort, MessageReceiver,
+    MessagesConsumed, Middleware, ModelRender, NoRender, OverflowPolicy, ProcessingObserver,
+    ProcessingOptions, TaskContext, TaskExecutor, TryModel,
+};
+
+type MessagePortOf<M> = MessagePort<<M as TryModel>::Intent, <M as TryModel>::Effect>;
+
+/// A model together with its message channel, renderer and task executor
+///
+/// Assembled by a [`RuntimeBuilder`] that wires all components in the
+/// correct order. Incomplete or mismatching combinations are rejected
+/// at compile time, i.e. [`RuntimeBuilder::build()`] is only available
+/// after a compatible task executor has been provided.
+///
+/// ```no_run
+/// # use std::marker::PhantomData;
+/// # use infect::{
+/// #     MaybeDebug, Message, Middleware, MiddlewareOutcome, ModelRender, ProcessingStats,
+/// #     Runtime, SubmitError, TaskExecutor, TryModel,
+/// # };
+/// # struct AccessCheck<M>(PhantomData<M>);
+/// # impl<M> AccessCheck<M> {
+/// #     fn new(_user: &str) -> Self {
+/// #         Self(PhantomData)
+/// #     }
+/// # }
+/// # impl<M: TryModel> Middleware<M> for AccessCheck<M> {
+/// #     fn before(
+/// #         &mut self,
+/// #         message: Message<M::Intent, M::Effect>,
+/// #     ) -> MiddlewareOutcome<M::Intent, M::Effect, M::IntentRejected> {
+/// #         MiddlewareOutcome::Continue(message)
+/// #     }
+/// # }
+/// # async fn example<M, R, T>(
+/// #     model: M,
+/// #     render_model: R,
+/// #     task_executor: T,
+/// #     channel_capacity: usize,
+/// #     user: &str,
+/// #     start: M::Intent,
+/// # ) -> Result<(), SubmitError<M::Intent, M::Effect>>
+/// # where
+/// #     M: TryModel + MaybeDebug,
+/// #     R: ModelRender<Model = M>,
+/// #     T: TaskExecutor<T, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+/// # {
+/// let (mut message_port, mut runtime) = Runtime::builder(model, channel_capacity)
+///     .render_model(render_model)
+///     .task_executor(task_executor)
+///     .observer(ProcessingStats::default())
+///     .middleware(AccessCheck::new(user))
+///     .build();
+/// message_port.submit_intent(start)?;
+/// let messages_consumed = runtime.run().await;
+/// let (model, stats) = runtime.into_inner();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Requires the `std` feature.
+///
+/// See also: [`crate::run_model()`]
+pub struct Runtime<M, R, T, O = (), W = ()>
+where
+    M: TryModel,
+{
+    model: M,
+    render_model: R,
+    task_context: TaskContext<T, M::Intent, M::Effect>,
+    message_rx: MessageReceiver<M::Intent, M::Effect>,
+    options: ProcessingOptions,
+    observer: O,
+    middleware: W,
+}
+
+impl<M> Runtime<M, NoRender<M>, (), (), ()>
+where
+    M: TryModel,
+{
+    /// Start building a new runtime
+    ///
+    /// The model is not rendered unless a renderer is provided.
+    #[must_use]
+    pub fn builder(
+        model: M,
+        channel_capacity: usize,
+    ) -> RuntimeBuilder<M, NoRender<M>, (), (), ()> {
+        RuntimeBuilder {
+            model,
+            render_model: NoRender::new(),
+            task_executor: (),
+            channel_capacity,
+            overflow_policy: OverflowPolicy::default(),
+            options: ProcessingOptions::default(),
+            observer: (),
+            middleware: (),
+        }
+    }
+}
+
+impl<M, R, T, O, W> Runtime<M, R, T, O, W>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    O: ProcessingObserver<M>,
+    W: Middleware<M>,
+{
+    /// Run the message loop of the model
+    ///
+    /// Like [`crate::run_model_with_context()`], but passes messages
+    /// through the middleware, processes them with the configured options,
+    /// and notifies the observer.
+    ///
+    /// Runs until all message ports have been dropped, including those
+    /// that have been passed to spawned tasks, or until the model stops
+    /// otherwise. Could be invoked again for resuming the model, e.g.
+    /// after an intent has been rejected. [`TryModel::on_start()`] and
+    /// [`TryModel::on_stop()`] are invoked on every run.
+    ///
+    /// Returns the condition that stopped consuming messages.
+    pub async fn run(&mut self) -> MessagesConsumed<M::IntentRejected, M::ApplyError> {
+        let Self {
+            model,
+            render_model,
+            task_context,
+            message_rx,
+            options,
+            observer,
+            middleware,
+        } = self;
+        run_model_internal(
+            message_rx,
+            task_context,
+            model,
+            render_model,
+            *options,
+            middleware,
+            observer,
+        )
+        .await
+    }
+}
+
+impl<M, R, T, O, W> Runtime<M, R, T, O, W>
+where
+    M: TryModel,
+{
+    /// The model
+    #[must_use]
+    pub const fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// The observer
+    #[must_use]
+    pub const fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// Recover the model and the observer
+    #[must_use]
+    pub fn into_inner(self) -> (M, O) {
+        let Self {
+            model,
+            render_model: _,
+            task_context: _,
+            message_rx: _,
+            options: _,
+            observer,
+            middleware: _,
+        } = self;
+        (model, observer)
+    }
+}
+
+impl<M, R, T, O, W> fmt::Debug for Runtime<M, R, T, O, W>
+where
+    M: TryModel,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            model: _,
+            render_model: _,
+            task_context: _,
+            message_rx: _,
+            options,
+            observer: _,
+            middleware: _,
+        } = self;
+        f.debug_struct("Runtime")
+            .field("options", options)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Assembles a [`Runtime`]
+///
+/// See also: [`Runtime::builder()`]
+pub struct RuntimeBuilder<M, R, T, O, W> {
+    model: M,
+    render_model: R,
+    task_executor: T,
+    channel_capacity: usize,
+    overflow_policy: OverflowPolicy,
+    options: ProcessingOptions,
+    observer: O,
+    middleware: W,
+}
+
+impl<M, R, T, O, W> RuntimeBuilder<M, R, T, O, W>
+where
+    M: TryModel,
+{
+    /// Render the model
+    #[must_use]
+    pub fn render_model<R2>(self, render_model: R2) -> RuntimeBuilder<M, R2, T, O, W>
+    where
+        R2: ModelRender<Model = M>,
+    {
+        let Self {
+            model,
+            render_model: _,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware,
+        } = self;
+        RuntimeBuilder {
+            model,
+            render_model,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware,
+        }
+    }
+
+    /// Spawn tasks
+    ///
+    /// Required for building the runtime.
+    #[must_use]
+    pub fn task_executor<T2>(self, task_executor: T2) -> RuntimeBuilder<M, R, T2, O, W>
+    where
+        T2: TaskExecutor<T2, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    {
+        let Self {
+            model,
+            render_model,
+            task_executor: _,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware,
+        } = self;
+        RuntimeBuilder {
+            model,
+            render_model,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware,
+        }
+    }
+
+    /// Observe the processing of messages
+    #[must_use]
+    pub fn observer<O2>(self, observer: O2) -> RuntimeBuilder<M, R, T, O2, W>
+    where
+        O2: ProcessingObserver<M>,
+    {
+        let Self {
+            model,
+            render_model,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer: _,
+            middleware,
+        } = self;
+        RuntimeBuilder {
+            model,
+            render_model,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware,
+        }
+    }
+
+    /// Pass all messages through middleware before processing them
+    ///
+    /// Multiple middleware could be combined into a tuple.
+    #[must_use]
+    pub fn middleware<W2>(self, middleware: W2) -> RuntimeBuilder<M, R, T, O, W2>
+    where
+        W2: Middleware<M>,
+    {
+        let Self {
+            model,
+            render_model,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware: _,
+        } = self;
+        RuntimeBuilder {
+            model,
+            render_model,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware,
+        }
+    }
+
+    /// Configure the [`OverflowPolicy`] of the returned [`MessagePort`]
+    #[must_use]
+    pub const fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Configure the [`ProcessingOptions`]
+    #[must_use]
+    pub const fn options(mut self, options: ProcessingOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<M, R, T, O, W> RuntimeBuilder<M, R, T, O, W>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    O: ProcessingObserver<M>,
+    W: Middleware<M>,
+{
+    /// Create the message channel and the runtime
+    ///
+    /// Returns the [`MessagePort`] for submitting messages together with
+    /// the runtime.
+    #[must_use]
+    pub fn build(self) -> (MessagePortOf<M>, Runtime<M, R, T, O, W>) {
+        let Self {
+            model,
+            render_model,
+            task_executor,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer,
+            middleware,
+        } = self;
+        let (mut message_port, message_rx) = message_channel(channel_capacity).split();
+        message_port.set_overflow_policy(overflow_policy);
+        let task_context = TaskContext::new(task_executor, message_port.clone());
+        let runtime = Runtime {
+            model,
+            render_model,
+            task_context,
+            message_rx,
+            options,
+            observer,
+            middleware,
+        };
+        (message_port, runtime)
+    }
+}
+
+impl<M, R, T, O, W> fmt::Debug for RuntimeBuilder<M, R, T, O, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            model: _,
+            render_model: _,
+            task_executor: _,
+            channel_capacity,
+            overflow_policy,
+            options,
+            observer: _,
+            middleware: _,
+        } = self;
+        f.debug_struct("RuntimeBuilder")
+            .field("channel_capacity", channel_capacity)
+            .field("overflow_policy", overflow_policy)
+            .field("options", options)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,227 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::sync::Arc;
+use core::{
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll},
+};
+
+use crate::{
+    logging::log_debug, ChannelClosed, Message, MessageChannel, MessagePort, MessageReceiver,
+    MessageSender, MessageTransport, MpscTransport, TryRecvError, TrySendError,
+};
+
+/// A value stamped with a sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sequenced<T> {
+    /// The sequence number
+    pub sequence: u64,
+
+    /// The value
+    pub value: T,
+}
+
+/// A [`MessageTransport`] that stamps each message with a sequence number
+///
+/// All senders share a single, monotonically increasing sequence number.
+/// The sequence number of each received message is logged, i.e. the order
+/// in which concurrent tasks submitted their messages could be
+/// reconstructed when debugging race conditions.
+///
+/// A sequence number is consumed even if sending the message fails,
+/// i.e. gaps indicate dropped messages.
+///
+/// The inner transport carries [`Sequenced`] intents and effects.
+///
+/// See also: [`sequenced_message_channel()`]
+#[derive(Debug)]
+pub struct SequencedTransport<Transport = MpscTransport> {
+    _phantom: PhantomData<fn() -> Transport>,
+}
+
+/// A [`MessagePort`] that stamps each message with a sequence number
+///
+/// See also: [`SequencedTransport`]
+pub type SequencedMessagePort<Intent, Effect, Transport = MpscTransport> =
+    MessagePort<Intent, Effect, SequencedTransport<Transport>>;
+
+/// Wrap a message channel for stamping messages with sequence numbers
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// # use infect::{message_channel, sequenced_message_channel, SequencedMessagePort};
+/// # let capacity = 10;
+/// let (mut message_port, mut message_rx) =
+///     sequenced_message_channel(message_channel(capacity)).split();
+/// # let _: &SequencedMessagePort<(), ()> = &message_port;
+/// ```
+#[must_use]
+pub fn sequenced_message_channel<Intent, Effect, Transport>(
+    channel: MessageChannel<Sequenced<Intent>, Sequenced<Effect>, Transport>,
+) -> MessageChannel<Intent, Effect, SequencedTransport<Transport>>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    let (sender, receiver) = channel.into_parts();
+    let message_tx = SequencedMessageSender {
+        sender,
+        next_sequence: Arc::new(AtomicU64::new(0)),
+    };
+    let message_rx = SequencedMessageReceiver {
+        receiver,
+        last_sequence: None,
+    };
+    MessageChannel::new(message_tx, message_rx)
+}
+
+/// Sender of a [`SequencedTransport`]
+pub struct SequencedMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    sender: MessageSender<Sequenced<Intent>, Sequenced<Effect>, Transport>,
+    next_sequence: Arc<AtomicU64>,
+}
+
+impl<Intent, Effect, Transport> Clone for SequencedMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    fn clone(&self) -> Self {
+        let Self {
+            sender,
+            next_sequence,
+        } = self;
+        Self {
+            sender: sender.clone(),
+            next_sequence: Arc::clone(next_sequence),
+        }
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for SequencedMessageSender<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            sender: _,
+            next_sequence,
+        } = self;
+        f.debug_struct("SequencedMessageSender")
+            .field("next_sequence", next_sequence)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receiver of a [`SequencedTransport`]
+pub struct SequencedMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    receiver: MessageReceiver<Sequenced<Intent>, Sequenced<Effect>, Transport>,
+    last_sequence: Option<u64>,
+}
+
+impl<Intent, Effect, Transport> SequencedMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    /// The sequence number of the last received message
+    ///
+    /// Returns `None` if no message has been received yet.
+    #[must_use]
+    pub const fn last_sequence(&self) -> Option<u64> {
+        self.last_sequence
+    }
+
+    fn unstamp(
+        &mut self,
+        message: Message<Sequenced<Intent>, Sequenced<Effect>>,
+    ) -> Message<Intent, Effect> {
+        let (sequence, message) = unstamp_message(message);
+        log_debug!("Received message #{sequence}");
+        self.last_sequence = Some(sequence);
+        message
+    }
+}
+
+impl<Intent, Effect, Transport> fmt::Debug for SequencedMessageReceiver<Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            receiver: _,
+            last_sequence,
+        } = self;
+        f.debug_struct("SequencedMessageReceiver")
+            .field("last_sequence", last_sequence)
+            .finish_non_exhaustive()
+    }
+}
+
+fn unstamp_message<Intent, Effect>(
+    message: Message<Sequenced<Intent>, Sequenced<Effect>>,
+) -> (u64, Message<Intent, Effect>) {
+    match message {
+        Message::Intent(Sequenced { sequence, value }) => (sequence, Message::Intent(value)),
+        Message::Effect(Sequenced { sequence, value }) => (sequence, Message::Effect(value)),
+    }
+}
+
+impl<Intent, Effect, Transport> MessageTransport<Intent, Effect> for SequencedTransport<Transport>
+where
+    Transport: MessageTransport<Sequenced<Intent>, Sequenced<Effect>>,
+{
+    type Sender = SequencedMessageSender<Intent, Effect, Transport>;
+    type Receiver = SequencedMessageReceiver<Intent, Effect, Transport>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        let sequence = sender.next_sequence.fetch_add(1, Ordering::Relaxed);
+        let message = message.map(
+            |value| Sequenced { sequence, value },
+            |value| Sequenced { sequence, value },
+        );
+        Transport::try_send(&mut sender.sender, message).map_err(|err| match err {
+            TrySendError::Full(message) => TrySendError::Full(unstamp_message(message).1),
+            TrySendError::Disconnected(message) => {
+                TrySendError::Disconnected(unstamp_message(message).1)
+            }
+        })
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        Transport::poll_ready(&mut sender.sender, cx)
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        Transport::is_closed(&sender.sender)
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Transport::pending_message_count(&sender.sender)
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        Transport::poll_next(&mut receiver.receiver, cx)
+            .map(|message| message.map(|message| receiver.unstamp(message)))
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        let message = Transport::try_recv(&mut receiver.receiver)?;
+        Ok(receiver.unstamp(message))
+    }
+}
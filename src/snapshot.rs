@@ -0,0 +1,131 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::VecDeque;
+use core::num::NonZeroUsize;
+
+use crate::{
+    process_message, MaybeDebug, Message, MessageProcessed, MessageTransport, ModelRender,
+    TaskContext, TaskExecutor, TryModel,
+};
+
+/// Capture and restore the state of a model
+///
+/// Enables stepping backward through previous states, e.g. for
+/// time-travel debugging, without requiring the model to be `Clone`.
+pub trait Snapshot {
+    /// The captured state
+    type Snapshot;
+
+    /// Capture the current state
+    #[must_use]
+    fn snapshot(&self) -> Self::Snapshot;
+
+    /// Restore a previously captured state
+    fn restore(&mut self, snapshot: Self::Snapshot);
+}
+
+/// Ring buffer with the most recent snapshots
+///
+/// The oldest snapshot is discarded when pushing a new snapshot
+/// into a full buffer.
+#[derive(Debug, Clone)]
+pub struct SnapshotHistory<S> {
+    capacity: NonZeroUsize,
+    snapshots: VecDeque<S>,
+}
+
+impl<S> SnapshotHistory<S> {
+    /// Create an empty history with a maximum number of snapshots
+    #[must_use]
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            snapshots: VecDeque::with_capacity(capacity.get()),
+        }
+    }
+
+    /// The maximum number of snapshots
+    #[must_use]
+    pub const fn capacity(&self) -> NonZeroUsize {
+        self.capacity
+    }
+
+    /// The number of snapshots
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Check if the history is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Append a new snapshot
+    ///
+    /// Returns the oldest snapshot if it has been discarded.
+    pub fn push(&mut self, snapshot: S) -> Option<S> {
+        let discarded = if self.snapshots.len() < self.capacity.get() {
+            None
+        } else {
+            self.snapshots.pop_front()
+        };
+        self.snapshots.push_back(snapshot);
+        discarded
+    }
+
+    /// Remove and return the most recent snapshot
+    pub fn pop(&mut self) -> Option<S> {
+        self.snapshots.pop_back()
+    }
+
+    /// The snapshot at the given index, starting with the oldest snapshot
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&S> {
+        self.snapshots.get(index)
+    }
+
+    /// The most recent snapshot
+    #[must_use]
+    pub fn latest(&self) -> Option<&S> {
+        self.snapshots.back()
+    }
+
+    /// Iterate over all snapshots, starting with the oldest snapshot
+    #[must_use]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &S> + ExactSizeIterator {
+        self.snapshots.iter()
+    }
+
+    /// Discard all snapshots
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+/// Process a single message and capture a snapshot of the model afterwards
+///
+/// A snapshot is captured after every processed message, independent of
+/// the outcome.
+///
+/// See also: [`process_message`]
+#[must_use]
+pub fn process_message_with_snapshot<M, R, T, Transport>(
+    task_context: &mut TaskContext<T, M::Intent, M::Effect, Transport>,
+    model: &mut M,
+    render_model: &mut R,
+    message: Message<M::Intent, M::Effect>,
+    history: &mut SnapshotHistory<M::Snapshot>,
+) -> MessageProcessed<M::IntentRejected, M::RenderHint, M::ApplyError>
+where
+    M: TryModel + Snapshot + MaybeDebug,
+    R: ModelRender<Model = M>,
+    T: TaskExecutor<T, Transport, Intent = M::Intent, Effect = M::Effect, Task = M::Task> + Clone,
+    Transport: MessageTransport<M::Intent, M::Effect>,
+{
+    let message_processed = process_message(task_context, model, render_model, message);
+    history.push(model.snapshot());
+    message_processed
+}
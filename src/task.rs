@@ -1,66 +1,974 @@
 // SPDX-FileCopyrightText: The infect authors
 // SPDX-License-Identifier: MPL-2.0
 
-use std::{fmt, rc::Rc, sync::Arc};
+use alloc::{boxed::Box, rc::Rc, string::String, sync::Arc, vec::Vec};
+use core::{
+    fmt,
+    future::{poll_fn, Future},
+    num::NonZeroU32,
+    pin::pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::{
+    any::Any,
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
 
-use crate::{Message, MessagePort};
+use futures_util::task::AtomicWaker;
 
-/// Task execution context
+use crate::{
+    logging::{log_debug, log_warn},
+    BoxedTaskFuture, MaybeDebug, Message, MessageChannel, MessagePort, MessageSender,
+    MessageTransport, MpscTransport, SubmitError,
+};
+
+/// Identifies a tracked task
+#[cfg_attr(feature = "std", doc = "")]
+#[cfg_attr(
+    feature = "std",
+    doc = "See also: [`TaskContext::with_task_tracking()`]"
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u64);
+
+/// Delay before retrying a failed attempt
+///
+/// See also: [`RetryPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backoff {
+    /// Retry immediately
+    #[default]
+    Immediate,
+
+    /// Wait for a fixed duration
+    Fixed(Duration),
+
+    /// Double the delay after each failed attempt
+    Exponential {
+        /// The delay after the first failed attempt
+        initial: Duration,
+
+        /// The upper bound of the delay
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// The delay after the given number of failed attempts
+    #[must_use]
+    pub fn delay(&self, failed_attempts: NonZeroU32) -> Duration {
+        match *self {
+            Self::Immediate => Duration::ZERO,
+            Self::Fixed(delay) => delay,
+            Self::Exponential { initial, max } => 2u32
+                .checked_pow(failed_attempts.get() - 1)
+                .map_or(max, |factor| initial.saturating_mul(factor).min(max)),
+        }
+    }
+}
+
+/// Controls how often and when a failed task is retried
+///
+/// See also: [`TaskContext::spawn_retrying_task()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one
+    pub max_attempts: NonZeroU32,
+
+    /// The delay between consecutive attempts
+    pub backoff: Backoff,
+}
+
+/// Failure of a task
+///
+/// Convention for reporting failed tasks: Effect types that implement
+/// `From<TaskError>` could be submitted when a task fails or panics,
+/// i.e. the model is notified instead of waiting forever.
+#[cfg_attr(feature = "std", doc = "")]
+#[cfg_attr(
+    feature = "std",
+    doc = "See also: [`TaskContext::spawn_reporting_task()`]"
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskError {
+    /// The task finished with an error
+    Failed(String),
+
+    /// The task panicked
+    ///
+    /// Contains the panic message if it is a string.
+    Panicked(Option<String>),
+}
+
+impl TaskError {
+    /// Create a new instance from the payload of a panic
+    ///
+    /// See also: [`std::panic::catch_unwind()`]
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => Some(*message),
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .map(|message| (*message).into()),
+        };
+        Self::Panicked(message)
+    }
+}
+
+/// Task tracking is disabled
+///
+#[cfg_attr(
+    feature = "std",
+    doc = "See also: [`TaskContext::with_task_tracking()`]"
+)]
+#[cfg_attr(
+    not(feature = "std"),
+    doc = "Task tracking requires the `std` feature."
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskTrackingDisabled;
+
+impl From<String> for TaskError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+impl From<&str> for TaskError {
+    fn from(message: &str) -> Self {
+        Self::Failed(message.into())
+    }
+}
+
+/// Catch panics while polling a future
+///
+/// The future is dropped after it panicked.
+#[cfg(feature = "std")]
+pub(crate) async fn catch_task_panic<T>(future: impl Future<Output = T>) -> Result<T, TaskError> {
+    let mut future = pin!(future);
+    poll_fn(|cx| {
+        // The future is never polled again after it panicked.
+        match catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(poll) => poll.map(Ok),
+            Err(payload) => Poll::Ready(Err(TaskError::from_panic(payload))),
+        }
+    })
+    .await
+}
+
+/// Shared state of a tracked task
+#[derive(Debug, Default)]
+pub(crate) struct TrackedTask {
+    cancelled: AtomicBool,
+    cancel_waker: AtomicWaker,
+    finish_waker: AtomicWaker,
+}
+
+impl TrackedTask {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn poll_cancelled(&self, cx: &mut Context<'_>) -> Poll<()> {
+        // Register before checking the flag to avoid missing a wake-up.
+        self.cancel_waker.register(cx.waker());
+        if self.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        self.cancel_waker.wake();
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct TaskRegistryState {
+    next_task_id: u64,
+    pending_tasks: HashMap<TaskId, Arc<TrackedTask>>,
+    idle_wakers: Vec<Waker>,
+}
+
+#[cfg(feature = "std")]
+impl TaskRegistryState {
+    #[must_use]
+    fn take_idle_wakers(&mut self) -> Vec<Waker> {
+        if !self.pending_tasks.is_empty() {
+            return Vec::new();
+        }
+        core::mem::take(&mut self.idle_wakers)
+    }
+}
+
+/// Registry of in-flight tasks that is shared by all contexts
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+struct TaskRegistry {
+    state: Arc<Mutex<TaskRegistryState>>,
+}
+
+#[cfg(feature = "std")]
+impl TaskRegistry {
+    fn lock(&self) -> MutexGuard<'_, TaskRegistryState> {
+        // The state remains consistent even if a panic occurred while locked.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn register_task(&self) -> TaskGuard {
+        let mut state = self.lock();
+        let task_id = TaskId(state.next_task_id);
+        state.next_task_id += 1;
+        let task = Arc::<TrackedTask>::default();
+        state.pending_tasks.insert(task_id, Arc::clone(&task));
+        TaskGuard {
+            task_id,
+            task,
+            registry: self.clone(),
+        }
+    }
+
+    fn unregister_task(&self, task_id: TaskId) -> Vec<Waker> {
+        let mut state = self.lock();
+        state.pending_tasks.remove(&task_id);
+        state.take_idle_wakers()
+    }
+
+    fn cancel_tasks(&self, filter: impl Fn(TaskId) -> bool) -> usize {
+        let (cancelled_tasks, idle_wakers) = {
+            let mut state = self.lock();
+            let cancelled_tasks = state
+                .pending_tasks
+                .extract_if(|task_id, _| filter(*task_id))
+                .map(|(_, task)| task)
+                .collect::<Vec<_>>();
+            (cancelled_tasks, state.take_idle_wakers())
+        };
+        for task in &cancelled_tasks {
+            task.cancel();
+            task.finish_waker.wake();
+        }
+        for waker in idle_wakers {
+            waker.wake();
+        }
+        cancelled_tasks.len()
+    }
+
+    fn pending_task_count(&self) -> usize {
+        self.lock().pending_tasks.len()
+    }
+
+    fn poll_task_finished(&self, task_id: TaskId, cx: &mut Context<'_>) -> Poll<()> {
+        let state = self.lock();
+        let Some(task) = state.pending_tasks.get(&task_id) else {
+            return Poll::Ready(());
+        };
+        // Tasks are only removed while locked and woken afterwards.
+        task.finish_waker.register(cx.waker());
+        Poll::Pending
+    }
+
+    fn poll_idle(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.lock();
+        if state.pending_tasks.is_empty() {
+            return Poll::Ready(());
+        }
+        if !state
+            .idle_wakers
+            .iter()
+            .any(|waker| waker.will_wake(cx.waker()))
+        {
+            state.idle_wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// Tracking tasks requires a `Mutex` that is not available without `std`
+///
+/// Uninhabited, i.e. task tracking is never enabled.
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone)]
+enum TaskRegistry {}
+
+#[cfg(not(feature = "std"))]
+impl TaskRegistry {
+    fn register_task(&self) -> TaskGuard {
+        match *self {}
+    }
+
+    fn unregister_task(&self, _task_id: TaskId) -> Vec<Waker> {
+        match *self {}
+    }
+
+    fn cancel_tasks(&self, _filter: impl Fn(TaskId) -> bool) -> usize {
+        match *self {}
+    }
+
+    fn pending_task_count(&self) -> usize {
+        match *self {}
+    }
+
+    fn poll_task_finished(&self, _task_id: TaskId, _cx: &mut Context<'_>) -> Poll<()> {
+        match *self {}
+    }
+
+    fn poll_idle(&self, _cx: &mut Context<'_>) -> Poll<()> {
+        match *self {}
+    }
+}
+
+/// Unregisters a task when dropped
 #[derive(Debug)]
-pub struct TaskContext<TaskExecutor, Intent, Effect> {
+struct TaskGuard {
+    task_id: TaskId,
+    task: Arc<TrackedTask>,
+    registry: TaskRegistry,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let idle_wakers = self.registry.unregister_task(self.task_id);
+        self.task.finish_waker.wake();
+        for waker in idle_wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Parts of a [`TaskContext`] that are shared with spawned tasks
+#[derive(Clone)]
+struct SharedTaskContext<TaskExecutor> {
+    task_executor: TaskExecutor,
+    task_registry: Option<TaskRegistry>,
+}
+
+/// Task execution context
+///
+/// The task executor is shared with the contexts of all spawned tasks,
+/// i.e. it is not cloned when spawning a task. Each context owns a clone
+/// of the message port that could be used mutably without affecting
+/// other contexts.
+pub struct TaskContext<TaskExecutor, Intent, Effect, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    shared: Arc<SharedTaskContext<TaskExecutor>>,
+    message_port: MessagePort<Intent, Effect, Transport>,
+
+    // Shared by all clones of the context that has been passed to a
+    // tracked task.
+    task_guard: Option<Arc<TaskGuard>>,
+}
+
+impl<TaskExecutor, Intent, Effect, Transport> TaskContext<TaskExecutor, Intent, Effect, Transport>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance
+    #[must_use]
+    pub fn new(
+        task_executor: TaskExecutor,
+        message_port: MessagePort<Intent, Effect, Transport>,
+    ) -> Self {
+        let shared = SharedTaskContext {
+            task_executor,
+            task_registry: None,
+        };
+        Self {
+            shared: Arc::new(shared),
+            message_port,
+            task_guard: None,
+        }
+    }
+
+    /// Create a new instance with a message port for the given sender
+    ///
+    /// See also: [`MessagePort::new_with_transport()`]
+    #[must_use]
+    pub fn with_sender(
+        task_executor: TaskExecutor,
+        message_tx: MessageSender<Intent, Effect, Transport>,
+    ) -> Self {
+        Self::new(task_executor, MessagePort::new_with_transport(message_tx))
+    }
+
+    /// Create a new instance for submitting messages into a channel
+    ///
+    /// The message port is connected to a clone of the channel's sender,
+    /// i.e. the channel remains usable for receiving messages.
+    #[must_use]
+    pub fn from_channel(
+        task_executor: TaskExecutor,
+        message_channel: &MessageChannel<Intent, Effect, Transport>,
+    ) -> Self {
+        Self::with_sender(task_executor, message_channel.sender().clone())
+    }
+
     /// A task executor for spawning sub-tasks
-    pub task_executor: TaskExecutor,
+    #[must_use]
+    pub fn task_executor(&self) -> &TaskExecutor {
+        &self.shared.task_executor
+    }
 
     /// A message port for submitting the task's side-effect
-    pub message_port: MessagePort<Intent, Effect>,
+    ///
+    /// See also: [`Self::message_port_mut()`]
+    #[must_use]
+    pub fn message_port(&self) -> &MessagePort<Intent, Effect, Transport> {
+        &self.message_port
+    }
+
+    /// A mutable message port for submitting the task's side-effect
+    #[must_use]
+    pub fn message_port_mut(&mut self) -> &mut MessagePort<Intent, Effect, Transport> {
+        &mut self.message_port
+    }
+
+    /// Clone the message port
+    ///
+    /// The returned port could be moved into a future that submits
+    /// messages concurrently while the context is still used for
+    /// spawning sub-tasks, e.g.
+    ///
+    #[cfg_attr(feature = "std", doc = "```")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// # use infect::{message_channel, TaskContext};
+    /// # let (message_port, _message_rx) = message_channel::<(), &str>(1).split();
+    /// # let context = TaskContext::new((), message_port);
+    /// let mut message_port = context.clone_port();
+    /// let submit_progress = async move {
+    ///     message_port.submit_effect_async("progress").await
+    /// };
+    /// // The context remains usable, e.g. for spawning sub-tasks.
+    /// assert!(!context.message_port().is_closed());
+    /// # drop(submit_progress);
+    /// ```
+    #[must_use]
+    pub fn clone_port(&self) -> MessagePort<Intent, Effect, Transport> {
+        self.message_port.clone()
+    }
+
+    /// Split the context into its task executor and message port
+    ///
+    /// Both parts could then be used independently without borrowing
+    /// the context.
+    ///
+    /// The task of a tracked context is considered as finished after
+    /// all of its contexts have been split or dropped. The guard that keeps
+    /// the task pending is owned by the context and not by its parts,
+    /// because a task executor and a message port are not bound to any
+    /// particular task. A running task should keep its context and use
+    /// [`Self::clone_port()`] instead, otherwise it is no longer included
+    /// in [`Self::pending_task_count()`] and [`Self::join_all()`] might
+    /// finish prematurely.
+    #[must_use]
+    pub fn split(self) -> (TaskExecutor, MessagePort<Intent, Effect, Transport>)
+    where
+        TaskExecutor: Clone,
+    {
+        let Self {
+            shared,
+            message_port,
+            task_guard: _,
+        } = self;
+        let SharedTaskContext {
+            task_executor,
+            task_registry: _,
+        } = Arc::unwrap_or_clone(shared);
+        (task_executor, message_port)
+    }
+
+    /// Enable tracking of spawned tasks
+    ///
+    /// A tracked task is considered as finished when the context that has
+    /// been passed to it has been dropped, including all of its clones.
+    ///
+    /// Enabling task tracking after tasks have been spawned has no effect
+    /// on those tasks.
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn with_task_tracking(mut self) -> Self
+    where
+        TaskExecutor: Clone,
+    {
+        if self.shared.task_registry.is_none() {
+            Arc::make_mut(&mut self.shared).task_registry = Some(TaskRegistry::default());
+        }
+        self
+    }
+
+    /// The number of tracked tasks that have not finished yet
+    ///
+    /// Returns 0 if task tracking is disabled.
+    #[must_use]
+    pub fn pending_task_count(&self) -> usize {
+        self.shared
+            .task_registry
+            .as_ref()
+            .map_or(0, TaskRegistry::pending_task_count)
+    }
+
+    /// Check if the system has settled
+    ///
+    /// The system has settled if neither tracked tasks nor messages are
+    /// pending.
+    ///
+    /// Returns `None` if unknown, i.e. if task tracking is disabled or if
+    /// the [`MessageTransport`] doesn't report the number of pending
+    /// messages, see [`MessagePort::pending_message_count()`]. The bounded
+    /// and unbounded mpsc transports report the number of pending messages.
+    /// Messages that have already been received and are buffered by the
+    /// receiver, e.g. by a [`crate::PriorityTransport`], are not considered.
+    ///
+    /// See also: [`crate::MessagesConsumed::Quiescent`]
+    #[must_use]
+    pub fn is_quiescent(&self) -> Option<bool> {
+        let task_registry = self.shared.task_registry.as_ref()?;
+        let pending_message_count = self.message_port.pending_message_count()?;
+        Some(task_registry.pending_task_count() == 0 && pending_message_count == 0)
+    }
+
+    /// Task tracking is enabled and no tracked tasks are pending
+    pub(crate) fn is_idle(&self) -> bool {
+        self.shared
+            .task_registry
+            .as_ref()
+            .is_some_and(|task_registry| task_registry.pending_task_count() == 0)
+    }
+
+    /// Wait until all tracked tasks have finished
+    ///
+    /// Finishes immediately if task tracking is disabled.
+    ///
+    /// Never finishes when awaited by a tracked task, because the task
+    /// would wait for itself.
+    pub async fn join_all(&self) {
+        let Some(task_registry) = &self.shared.task_registry else {
+            return;
+        };
+        poll_fn(|cx| task_registry.poll_idle(cx)).await;
+    }
+
+    /// Cancel a tracked task
+    ///
+    /// The task is removed from the registry immediately and is no longer
+    /// considered as pending. Executors like [`crate::SpawningTaskExecutor`]
+    /// abort the task by dropping its future. Tasks could also observe
+    /// their cancellation through [`Self::is_cancelled()`] and
+    /// [`Self::cancelled()`].
+    ///
+    /// Returns `true` if the task has been cancelled or `false` if it
+    /// has already finished or is unknown.
+    #[allow(clippy::must_use_candidate)] // invoked for its side effect
+    pub fn cancel_task(&self, task_id: TaskId) -> bool {
+        self.shared
+            .task_registry
+            .as_ref()
+            .is_some_and(|task_registry| {
+                task_registry.cancel_tasks(|pending_task_id| pending_task_id == task_id) > 0
+            })
+    }
+
+    /// Cancel all tracked tasks
+    ///
+    /// Returns the number of cancelled tasks.
+    ///
+    /// See also: [`Self::cancel_task()`]
+    #[allow(clippy::must_use_candidate)] // invoked for its side effect
+    pub fn cancel_all_tasks(&self) -> usize {
+        self.shared
+            .task_registry
+            .as_ref()
+            .map_or(0, |task_registry| task_registry.cancel_tasks(|_| true))
+    }
+
+    /// Check if the current task has been cancelled
+    ///
+    /// Always returns `false` if the context has not been passed to a
+    /// tracked task.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.task_guard
+            .as_ref()
+            .is_some_and(|task_guard| task_guard.task.is_cancelled())
+    }
+
+    /// Wait until the current task has been cancelled
+    ///
+    /// Never finishes if the context has not been passed to a tracked task.
+    pub async fn cancelled(&self) {
+        let Some(task) = self.tracked_task() else {
+            return core::future::pending().await;
+        };
+        poll_fn(|cx| task.poll_cancelled(cx)).await;
+    }
+
+    pub(crate) fn tracked_task(&self) -> Option<Arc<TrackedTask>> {
+        self.task_guard
+            .as_ref()
+            .map(|task_guard| Arc::clone(&task_guard.task))
+    }
 }
 
-impl<TaskExecutor, Intent, Effect> TaskContext<TaskExecutor, Intent, Effect>
+impl<TaskExecutor, Intent, Effect, Transport> TaskContext<TaskExecutor, Intent, Effect, Transport>
 where
-    Intent: fmt::Debug,
-    Effect: fmt::Debug,
-    TaskExecutor: crate::TaskExecutor<TaskExecutor, Intent = Intent, Effect = Effect> + Clone,
+    Intent: MaybeDebug,
+    Effect: MaybeDebug,
+    TaskExecutor:
+        crate::TaskExecutor<TaskExecutor, Transport, Intent = Intent, Effect = Effect> + Clone,
+    Transport: MessageTransport<Intent, Effect>,
 {
     /// [`MessagePort::submit_message()`]
-    pub fn submit_message(&mut self, message: impl Into<Message<Intent, Effect>>) {
-        self.message_port.submit_message(message);
+    ///
+    /// # Errors
+    ///
+    /// See [`MessagePort::submit_message()`].
+    pub fn submit_message(
+        &mut self,
+        message: impl Into<Message<Intent, Effect>>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        self.message_port_mut().submit_message(message)
     }
 
     /// [`MessagePort::submit_intent()`]
-    pub fn submit_intent(&mut self, intent: impl Into<Intent>) {
-        self.message_port.submit_intent(intent);
+    ///
+    /// # Errors
+    ///
+    /// See [`MessagePort::submit_intent()`].
+    pub fn submit_intent(
+        &mut self,
+        intent: impl Into<Intent>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        self.message_port_mut().submit_intent(intent)
     }
 
     /// [`MessagePort::submit_effect()`]
-    pub fn submit_effect(&mut self, effect: impl Into<Effect>) {
-        self.message_port.submit_effect(effect);
+    ///
+    /// # Errors
+    ///
+    /// See [`MessagePort::submit_effect()`].
+    pub fn submit_effect(
+        &mut self,
+        effect: impl Into<Effect>,
+    ) -> Result<(), SubmitError<Intent, Effect>> {
+        self.message_port_mut().submit_effect(effect)
     }
 
     /// [`TaskExecutor::spawn_task()`]
-    pub fn spawn_task(&self, task: impl Into<TaskExecutor::Task>) {
-        let context = self.clone();
-        self.task_executor.spawn_task(context, task.into());
+    ///
+    /// Returns the [`TaskId`] if task tracking is enabled.
+    pub fn spawn_task(&self, task: impl Into<TaskExecutor::Task>) -> Option<TaskId> {
+        let task_guard = self
+            .shared
+            .task_registry
+            .as_ref()
+            .map(TaskRegistry::register_task);
+        let task_id = task_guard.as_ref().map(|task_guard| task_guard.task_id);
+        self.spawn_task_with_guard(task.into(), task_guard);
+        task_id
+    }
+
+    fn spawn_task_with_guard(&self, task: TaskExecutor::Task, task_guard: Option<TaskGuard>) {
+        let Self {
+            shared,
+            message_port,
+            task_guard: _,
+        } = self;
+        let context = Self {
+            shared: Arc::clone(shared),
+            message_port: message_port.clone(),
+            task_guard: task_guard.map(Arc::new),
+        };
+        shared.task_executor.spawn_task(context, task);
+    }
+
+    /// Spawn a fallible future as a task and submit its outcome as an effect
+    ///
+    /// The result of `future` is mapped into an effect by either `on_ok`
+    /// or `on_err`. The effect is then submitted with backpressure, see
+    /// [`MessagePort::submit_effect_async()`].
+    ///
+    /// Requires a task type that could be created from a [`BoxedTaskFuture`],
+    /// e.g. for a [`crate::SpawningTaskExecutor`] that spawns those futures.
+    ///
+    /// Returns the [`TaskId`] if task tracking is enabled.
+    pub fn spawn_fallible_task<T, E>(
+        &self,
+        future: impl Future<Output = Result<T, E>> + Send + 'static,
+        on_ok: impl FnOnce(T) -> Effect + Send + 'static,
+        on_err: impl FnOnce(E) -> Effect + Send + 'static,
+    ) -> Option<TaskId>
+    where
+        Intent: Send + 'static,
+        Effect: Send + 'static,
+        TaskExecutor::Task: From<BoxedTaskFuture>,
+        Transport: 'static,
+        Transport::Sender: Send + Sync,
+    {
+        let mut message_port = self.clone_port();
+        let task_future: BoxedTaskFuture = Box::pin(async move {
+            let effect = match future.await {
+                Ok(ok) => on_ok(ok),
+                Err(err) => on_err(err),
+            };
+            if message_port.submit_effect_async(effect).await.is_err() {
+                log_warn!("Failed to submit outcome of fallible task - channel is closed");
+            }
+        });
+        self.spawn_task(task_future)
+    }
+
+    /// Spawn a task that is cancelled after a timeout
+    ///
+    /// The `timer` future determines the timeout, e.g.
+    /// `tokio::time::sleep(duration)`. If it finishes before the task
+    /// then the task is cancelled and `on_timeout` is submitted as an
+    /// effect, see [`Self::cancel_task()`].
+    ///
+    /// Cancellation is cooperative and not an abort: The task is only
+    /// stopped if the executor drops its future after it has been
+    /// cancelled, like [`crate::SpawningTaskExecutor`], or if the task
+    /// observes its cancellation, see [`Self::is_cancelled()`]. Otherwise
+    /// the task keeps running after the timeout and might still submit
+    /// messages after `on_timeout`. A future that blocks while being
+    /// polled is never stopped.
+    ///
+    /// The timer is awaited by a separate task that is created from a
+    /// [`BoxedTaskFuture`], like in [`Self::spawn_fallible_task()`].
+    /// This task is spawned by the executor directly and is not tracked,
+    /// i.e. it is neither included in [`Self::pending_task_count()`] nor
+    /// affected by [`Self::cancel_all_tasks()`].
+    ///
+    /// Returns the [`TaskId`] of the spawned task.
+    ///
+    /// # Errors
+    ///
+    /// Fails without spawning the task if task tracking is disabled,
+    /// because untracked tasks could not be cancelled.
+    #[must_use = "the task is not spawned if task tracking is disabled"]
+    // Task tracking is not available without std.
+    #[cfg_attr(not(feature = "std"), allow(unreachable_code, unused_variables))]
+    pub fn spawn_task_with_timeout(
+        &self,
+        task: impl Into<TaskExecutor::Task>,
+        timer: impl Future<Output = ()> + Send + 'static,
+        on_timeout: impl Into<Effect>,
+    ) -> Result<TaskId, TaskTrackingDisabled>
+    where
+        Intent: Send + 'static,
+        Effect: Send + 'static,
+        TaskExecutor::Task: From<BoxedTaskFuture>,
+        Transport: 'static,
+        Transport::Sender: Send + Sync,
+    {
+        let Some(task_registry) = self.shared.task_registry.clone() else {
+            log_warn!("Not spawning task with timeout - task tracking is disabled");
+            return Err(TaskTrackingDisabled);
+        };
+        let task_guard = task_registry.register_task();
+        let task_id = task_guard.task_id;
+        self.spawn_task_with_guard(task.into(), Some(task_guard));
+        let on_timeout = on_timeout.into();
+        let mut message_port = self.clone_port();
+        let timeout_future: BoxedTaskFuture = Box::pin(async move {
+            let mut timer = pin!(timer);
+            let timed_out = poll_fn(|cx| {
+                if task_registry.poll_task_finished(task_id, cx).is_ready() {
+                    return Poll::Ready(false);
+                }
+                timer.as_mut().poll(cx).map(|()| true)
+            })
+            .await;
+            if !timed_out
+                || task_registry.cancel_tasks(|pending_task_id| pending_task_id == task_id) == 0
+            {
+                // The task has finished in the meantime.
+                return;
+            }
+            log_debug!("Cancelled task {task_id:?} after timeout");
+            if message_port.submit_effect_async(on_timeout).await.is_err() {
+                log_warn!("Failed to submit timeout effect - channel is closed");
+            }
+        });
+        // The timeout task itself is not tracked.
+        self.spawn_task_with_guard(timeout_future.into(), None);
+        Ok(task_id)
+    }
+
+    /// Spawn a fallible future as a task that is retried on failure
+    ///
+    /// `make_future` creates a new future for each attempt. The value of
+    /// a successful attempt is submitted as an effect. Failed attempts are
+    /// retried according to `policy` and `on_exhausted` is submitted as an
+    /// effect when all attempts have failed.
+    ///
+    /// The delay between attempts is awaited by invoking `sleep`, e.g.
+    /// `tokio::time::sleep`. It is not invoked for zero delays.
+    ///
+    /// The retrying task is created from a [`BoxedTaskFuture`], like in
+    /// [`Self::spawn_fallible_task()`].
+    ///
+    /// Returns the [`TaskId`] if task tracking is enabled.
+    pub fn spawn_retrying_task<F, T, E, S>(
+        &self,
+        make_future: impl Fn() -> F + Send + 'static,
+        policy: RetryPolicy,
+        sleep: impl Fn(Duration) -> S + Send + 'static,
+        on_exhausted: impl Into<Effect>,
+    ) -> Option<TaskId>
+    where
+        F: Future<Output = Result<T, E>> + Send,
+        T: Into<Effect>,
+        S: Future<Output = ()> + Send,
+        Intent: Send + 'static,
+        Effect: Send + 'static,
+        TaskExecutor::Task: From<BoxedTaskFuture>,
+        Transport: 'static,
+        Transport::Sender: Send + Sync,
+    {
+        let RetryPolicy {
+            max_attempts,
+            backoff,
+        } = policy;
+        let on_exhausted = on_exhausted.into();
+        let mut message_port = self.clone_port();
+        let task_future: BoxedTaskFuture = Box::pin(async move {
+            let mut failed_attempts = NonZeroU32::MIN;
+            let effect = loop {
+                if let Ok(ok) = make_future().await {
+                    break ok.into();
+                }
+                if failed_attempts >= max_attempts {
+                    log_debug!("Giving up after {failed_attempts} failed attempt(s)");
+                    break on_exhausted;
+                }
+                let delay = backoff.delay(failed_attempts);
+                log_debug!("Retrying after {failed_attempts} failed attempt(s) in {delay:?}");
+                if !delay.is_zero() {
+                    sleep(delay).await;
+                }
+                failed_attempts = failed_attempts.saturating_add(1);
+            };
+            if message_port.submit_effect_async(effect).await.is_err() {
+                log_warn!("Failed to submit outcome of retrying task - channel is closed");
+            }
+        });
+        self.spawn_task(task_future)
+    }
+
+    /// Spawn a future as a task and report its failure as an effect
+    ///
+    /// If `future` finishes with an error or panics then the corresponding
+    /// [`TaskError`] is converted into an effect and submitted with
+    /// backpressure, see [`MessagePort::submit_effect_async()`]. Nothing
+    /// is submitted if the task succeeds.
+    ///
+    /// The task is created from a [`BoxedTaskFuture`], like in
+    /// [`Self::spawn_fallible_task()`].
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// Returns the [`TaskId`] if task tracking is enabled.
+    #[cfg(feature = "std")]
+    pub fn spawn_reporting_task<E>(
+        &self,
+        future: impl Future<Output = Result<(), E>> + Send + 'static,
+    ) -> Option<TaskId>
+    where
+        E: Into<TaskError>,
+        Intent: Send + 'static,
+        Effect: From<TaskError> + Send + 'static,
+        TaskExecutor::Task: From<BoxedTaskFuture>,
+        Transport: 'static,
+        Transport::Sender: Send + Sync,
+    {
+        let mut message_port = self.clone_port();
+        let task_future: BoxedTaskFuture = Box::pin(async move {
+            let task_error = match catch_task_panic(future).await {
+                Ok(Ok(())) => return,
+                Ok(Err(err)) => err.into(),
+                Err(task_error) => task_error,
+            };
+            log_debug!("Reporting task error: {task_error:?}");
+            if message_port
+                .submit_effect_async(Effect::from(task_error))
+                .await
+                .is_err()
+            {
+                log_warn!("Failed to submit task error - channel is closed");
+            }
+        });
+        self.spawn_task(task_future)
     }
 }
 
-impl<TaskExecutor, Intent, Effect> Clone for TaskContext<TaskExecutor, Intent, Effect>
+impl<TaskExecutor, Intent, Effect, Transport> Clone
+    for TaskContext<TaskExecutor, Intent, Effect, Transport>
 where
-    TaskExecutor: Clone,
+    Transport: MessageTransport<Intent, Effect>,
 {
     fn clone(&self) -> Self {
         let Self {
-            task_executor,
+            shared,
             message_port,
+            task_guard,
         } = self;
         Self {
-            task_executor: task_executor.clone(),
+            shared: Arc::clone(shared),
             message_port: message_port.clone(),
+            task_guard: task_guard.clone(),
         }
     }
 }
 
+impl<TaskExecutor, Intent, Effect, Transport> fmt::Debug
+    for TaskContext<TaskExecutor, Intent, Effect, Transport>
+where
+    TaskExecutor: fmt::Debug,
+    Transport: MessageTransport<Intent, Effect>,
+    MessagePort<Intent, Effect, Transport>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            shared,
+            message_port,
+            task_guard,
+        } = self;
+        let SharedTaskContext {
+            task_executor,
+            task_registry,
+        } = &**shared;
+        f.debug_struct("TaskContext")
+            .field("task_executor", task_executor)
+            .field("message_port", message_port)
+            .field("task_registry", task_registry)
+            .field(
+                "task_id",
+                &task_guard.as_ref().map(|task_guard| task_guard.task_id),
+            )
+            .finish()
+    }
+}
+
 /// Spawn concurrent tasks
-pub trait TaskExecutor<T> {
+pub trait TaskExecutor<T, Transport = MpscTransport>
+where
+    Transport: MessageTransport<Self::Intent, Self::Effect>,
+{
     /// The intent type
     type Intent;
 
@@ -82,31 +990,84 @@ pub trait TaskExecutor<T> {
     ///
     /// Tasks can submit (feedback) messages for triggering side-effects
     /// and spawn new (sub-)tasks through `context`.
-    fn spawn_task(&self, context: TaskContext<T, Self::Intent, Self::Effect>, task: Self::Task);
+    fn spawn_task(
+        &self,
+        context: TaskContext<T, Self::Intent, Self::Effect, Transport>,
+        task: Self::Task,
+    );
+}
+
+impl<T, Transport> TaskExecutor<Rc<T>, Transport> for Rc<T>
+where
+    T: TaskExecutor<Rc<T>, Transport>,
+    Transport: MessageTransport<T::Intent, T::Effect>,
+{
+    type Intent = T::Intent;
+    type Effect = T::Effect;
+    type Task = T::Task;
+
+    fn spawn_task(
+        &self,
+        context: TaskContext<Self, Self::Intent, Self::Effect, Transport>,
+        task: Self::Task,
+    ) {
+        T::spawn_task(self, context, task);
+    }
+}
+
+impl<T, Transport> TaskExecutor<Arc<T>, Transport> for Arc<T>
+where
+    T: TaskExecutor<Arc<T>, Transport>,
+    Transport: MessageTransport<T::Intent, T::Effect>,
+{
+    type Intent = T::Intent;
+    type Effect = T::Effect;
+    type Task = T::Task;
+
+    fn spawn_task(
+        &self,
+        context: TaskContext<Self, Self::Intent, Self::Effect, Transport>,
+        task: Self::Task,
+    ) {
+        T::spawn_task(self, context, task);
+    }
 }
 
-impl<T> TaskExecutor<Rc<T>> for Rc<T>
+// Boxed trait objects are not supported, because the executor type
+// of the context would be infinitely recursive, i.e.
+// `Box<dyn TaskExecutor<Box<dyn TaskExecutor<...>>>>`.
+impl<T, Transport> TaskExecutor<Box<T>, Transport> for Box<T>
 where
-    T: TaskExecutor<Rc<T>>,
+    T: TaskExecutor<Box<T>, Transport> + ?Sized,
+    Transport: MessageTransport<T::Intent, T::Effect>,
 {
     type Intent = T::Intent;
     type Effect = T::Effect;
     type Task = T::Task;
 
-    fn spawn_task(&self, context: TaskContext<Self, Self::Intent, Self::Effect>, task: Self::Task) {
+    fn spawn_task(
+        &self,
+        context: TaskContext<Self, Self::Intent, Self::Effect, Transport>,
+        task: Self::Task,
+    ) {
         T::spawn_task(self, context, task);
     }
 }
 
-impl<T> TaskExecutor<Arc<T>> for Arc<T>
+impl<'a, T, Transport> TaskExecutor<&'a T, Transport> for &'a T
 where
-    T: TaskExecutor<Arc<T>>,
+    T: TaskExecutor<&'a T, Transport> + ?Sized,
+    Transport: MessageTransport<T::Intent, T::Effect>,
 {
     type Intent = T::Intent;
     type Effect = T::Effect;
     type Task = T::Task;
 
-    fn spawn_task(&self, context: TaskContext<Self, Self::Intent, Self::Effect>, task: Self::Task) {
+    fn spawn_task(
+        &self,
+        context: TaskContext<Self, Self::Intent, Self::Effect, Transport>,
+        task: Self::Task,
+    ) {
         T::spawn_task(self, context, task);
     }
 }
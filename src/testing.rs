@@ -0,0 +1,488 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Utilities for testing models
+//!
+//! Requires the `testing` feature.
+
+use alloc::{rc::Rc, vec::Vec};
+use core::{
+    cell::RefCell,
+    convert::Infallible,
+    fmt,
+    marker::PhantomData,
+    task::{Context, Poll},
+};
+
+use crate::{
+    process_message, process_message_no_render, unbounded_message_channel, ChannelClosed,
+    MaybeDebug, Message, MessagePort, MessageProcessed, MessageReceiver, MessageSender,
+    MessageTransport, ModelRender, SubmitError, TaskContext, TaskExecutor, TryModel, TryRecvError,
+    TrySendError, UnboundedMessageReceiver, UnboundedMpscTransport,
+};
+
+/// A [`TaskExecutor`] that records spawned tasks instead of executing them
+///
+/// All clones share the same recorded tasks, i.e. a clone could be kept
+/// for inspecting the tasks that have been spawned through a
+/// [`TaskContext`].
+pub struct RecordingTaskExecutor<Intent, Effect, Task> {
+    tasks: Rc<RefCell<Vec<Task>>>,
+    _phantom: PhantomData<fn() -> (Intent, Effect)>,
+}
+
+impl<Intent, Effect, Task> RecordingTaskExecutor<Intent, Effect, Task> {
+    /// Create a new instance
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            tasks: Default::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Remove and return all recorded tasks in the order they were spawned
+    #[must_use]
+    pub fn drain_tasks(&self) -> Vec<Task> {
+        self.tasks.take()
+    }
+}
+
+impl<Intent, Effect, Task> Default for RecordingTaskExecutor<Intent, Effect, Task> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Intent, Effect, Task> Clone for RecordingTaskExecutor<Intent, Effect, Task> {
+    fn clone(&self) -> Self {
+        let Self { tasks, _phantom } = self;
+        Self {
+            tasks: Rc::clone(tasks),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Intent, Effect, Task> fmt::Debug for RecordingTaskExecutor<Intent, Effect, Task>
+where
+    Task: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { tasks, _phantom } = self;
+        f.debug_struct("RecordingTaskExecutor")
+            .field("tasks", tasks)
+            .finish()
+    }
+}
+
+impl<Intent, Effect, Task, Transport> TaskExecutor<Self, Transport>
+    for RecordingTaskExecutor<Intent, Effect, Task>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    type Intent = Intent;
+    type Effect = Effect;
+    type Task = Task;
+
+    fn spawn_task(&self, _context: TaskContext<Self, Intent, Effect, Transport>, task: Task) {
+        self.tasks.borrow_mut().push(task);
+    }
+}
+
+type TestTaskContext<M> = TaskContext<
+    RecordingTaskExecutor<<M as TryModel>::Intent, <M as TryModel>::Effect, <M as TryModel>::Task>,
+    <M as TryModel>::Intent,
+    <M as TryModel>::Effect,
+    UnboundedMpscTransport,
+>;
+
+type TestStepOutcome<M> = StepOutcome<
+    <M as TryModel>::IntentRejected,
+    <M as TryModel>::RenderHint,
+    <M as TryModel>::Task,
+    <M as TryModel>::ApplyError,
+>;
+
+/// Outcome of [`TestDriver::step()`]
+#[derive(Debug)]
+pub struct StepOutcome<IntentRejected, RenderHint, Task, ApplyError = Infallible> {
+    /// The outcome of processing the message
+    pub message_processed: MessageProcessed<IntentRejected, RenderHint, ApplyError>,
+
+    /// All tasks that have been spawned while processing the message
+    pub spawned_tasks: Vec<Task>,
+}
+
+/// Synchronous driver for testing a [`TryModel`]
+///
+/// Submitted messages are processed one by one when invoking
+/// [`Self::step()`], without the need for an async runtime. Spawned
+/// tasks are recorded and never executed. The model is not rendered.
+pub struct TestDriver<M: TryModel> {
+    model: M,
+    message_rx: UnboundedMessageReceiver<M::Intent, M::Effect>,
+    task_context: TestTaskContext<M>,
+}
+
+impl<M> TestDriver<M>
+where
+    M: TryModel,
+{
+    /// Create a new instance
+    #[must_use]
+    pub fn new(model: M) -> Self {
+        let (message_port, message_rx) = unbounded_message_channel().split();
+        let task_context = TaskContext::new(RecordingTaskExecutor::new(), message_port);
+        Self {
+            model,
+            message_rx,
+            task_context,
+        }
+    }
+
+    /// The model
+    #[must_use]
+    pub const fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// The model for manipulating it directly
+    #[must_use]
+    pub fn model_mut(&mut self) -> &mut M {
+        &mut self.model
+    }
+
+    /// Consume the driver and return the model
+    #[must_use]
+    pub fn into_model(self) -> M {
+        let Self {
+            model,
+            message_rx: _,
+            task_context: _,
+        } = self;
+        model
+    }
+
+    /// A message port for submitting messages from outside
+    #[must_use]
+    pub fn message_port(&self) -> MessagePort<M::Intent, M::Effect, UnboundedMpscTransport> {
+        self.task_context.clone_port()
+    }
+}
+
+impl<M> TestDriver<M>
+where
+    M: TryModel,
+    M::Intent: MaybeDebug,
+    M::Effect: MaybeDebug,
+{
+    /// Submit a message
+    ///
+    /// # Errors
+    ///
+    /// See [`MessagePort::submit_message()`].
+    pub fn submit_message(
+        &mut self,
+        message: impl Into<Message<M::Intent, M::Effect>>,
+    ) -> Result<(), SubmitError<M::Intent, M::Effect>> {
+        self.task_context.submit_message(message)
+    }
+
+    /// Submit an intent
+    ///
+    /// # Errors
+    ///
+    /// See [`MessagePort::submit_intent()`].
+    pub fn submit_intent(
+        &mut self,
+        intent: impl Into<M::Intent>,
+    ) -> Result<(), SubmitError<M::Intent, M::Effect>> {
+        self.task_context.submit_intent(intent)
+    }
+
+    /// Submit an effect
+    ///
+    /// # Errors
+    ///
+    /// See [`MessagePort::submit_effect()`].
+    pub fn submit_effect(
+        &mut self,
+        effect: impl Into<M::Effect>,
+    ) -> Result<(), SubmitError<M::Intent, M::Effect>> {
+        self.task_context.submit_effect(effect)
+    }
+
+    /// Process the next pending message
+    ///
+    /// Returns `None` if no message is pending.
+    pub fn step(&mut self) -> Option<TestStepOutcome<M>> {
+        let message = self.message_rx.try_recv().ok()?;
+        let message_processed =
+            process_message_no_render(&mut self.task_context, &mut self.model, message);
+        let spawned_tasks = self.task_context.task_executor().drain_tasks();
+        Some(StepOutcome {
+            message_processed,
+            spawned_tasks,
+        })
+    }
+}
+
+/// Outcome of [`replay_messages()`]
+#[derive(Debug)]
+pub struct MessagesReplayed<M, IntentRejected, RenderHint, ApplyError = Infallible> {
+    /// The final model
+    pub model: M,
+
+    /// The number of messages that have been replayed
+    ///
+    /// Includes the diverging message, if any.
+    pub replayed_count: usize,
+
+    /// The outcome of the last replayed message if replaying diverged
+    ///
+    /// Either [`MessageProcessed::IntentRejected`],
+    /// [`MessageProcessed::EffectChainExceeded`], or
+    /// [`MessageProcessed::EffectFailed`].
+    pub divergence: Option<MessageProcessed<IntentRejected, RenderHint, ApplyError>>,
+}
+
+/// Replay a recorded sequence of messages
+///
+/// Processes all messages one after another, starting with the given
+/// model. Replaying stops at the first message that could not be
+/// applied, i.e. if an intent has been rejected, if the effect chain
+/// has been exceeded, or if applying an effect failed. This could be
+/// expected if the recorded message caused the same outcome when it was
+/// processed originally. In this case replaying could be continued with
+/// the returned model and the remaining messages.
+///
+/// The model is rendered as usual. Spawned tasks are not executed and
+/// all messages that are submitted while replaying, e.g. observed intents
+/// after rendering the model, are discarded. They are expected to be
+/// part of the recorded sequence, e.g. when captured by a
+/// [`RecordingTransport`].
+///
+/// Comparing the final model with the model at the end of the recording
+/// verifies that processing messages is deterministic.
+pub fn replay_messages<M, R>(
+    model: M,
+    render_model: &mut R,
+    messages: impl IntoIterator<Item = Message<M::Intent, M::Effect>>,
+) -> MessagesReplayed<M, M::IntentRejected, M::RenderHint, M::ApplyError>
+where
+    M: TryModel + MaybeDebug,
+    R: ModelRender<Model = M>,
+{
+    let mut model = model;
+    let (message_port, mut message_rx) = unbounded_message_channel().split();
+    let mut task_context: TestTaskContext<M> =
+        TaskContext::new(RecordingTaskExecutor::new(), message_port);
+    let mut replayed_count = 0;
+    let mut divergence = None;
+    for message in messages {
+        replayed_count += 1;
+        let message_processed =
+            process_message(&mut task_context, &mut model, render_model, message);
+        // Discard all spawned tasks and submitted messages.
+        drop(task_context.task_executor().drain_tasks());
+        while message_rx.try_recv().is_ok() {}
+        if matches!(
+            message_processed,
+            MessageProcessed::IntentRejected(_)
+                | MessageProcessed::EffectChainExceeded
+                | MessageProcessed::EffectFailed(_)
+        ) {
+            divergence = Some(message_processed);
+            break;
+        }
+    }
+    MessagesReplayed {
+        model,
+        replayed_count,
+        divergence,
+    }
+}
+
+impl<M> fmt::Debug for TestDriver<M>
+where
+    M: TryModel + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            model,
+            message_rx: _,
+            task_context: _,
+        } = self;
+        f.debug_struct("TestDriver")
+            .field("model", model)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Destination for recorded messages
+///
+/// See also: [`RecordingTransport`]
+pub trait MessageSink<Intent, Effect> {
+    /// Record a message after it has been received
+    fn record_message(&mut self, message: &Message<Intent, Effect>);
+}
+
+impl<Intent, Effect> MessageSink<Intent, Effect> for Vec<Message<Intent, Effect>>
+where
+    Intent: Clone,
+    Effect: Clone,
+{
+    fn record_message(&mut self, message: &Message<Intent, Effect>) {
+        self.push(message.clone());
+    }
+}
+
+/// A [`MessageTransport`] that records all received messages
+///
+/// Wraps the receiver of an inner transport into a [`RecordingReceiver`]
+/// that passes every received message to a [`MessageSink`] before
+/// returning it. The order of messages is preserved. Messages are only
+/// recorded when they are actually returned to the caller, i.e. receiving
+/// messages remains cancellation safe.
+///
+/// The sender is shared with the inner transport:
+///
+/// ```no_run
+/// # use infect::{
+/// #     consume_messages, message_channel, testing::{RecordingReceiver, RecordingTransport},
+/// #     MaybeDebug, Message, MessagePort, ModelRender, MpscTransport, TaskContext, TaskExecutor,
+/// #     TryModel,
+/// # };
+/// # type Transport<M> =
+/// #     RecordingTransport<MpscTransport, Vec<Message<<M as TryModel>::Intent, <M as TryModel>::Effect>>>;
+/// # async fn example<M, R, T>(mut model: M, mut render_model: R, task_executor: T)
+/// # where
+/// #     M: TryModel<Intent: Clone, Effect: Clone> + MaybeDebug,
+/// #     R: ModelRender<Model = M>,
+/// #     T: TaskExecutor<T, Transport<M>, Intent = M::Intent, Effect = M::Effect, Task = M::Task>
+/// #         + Clone,
+/// # {
+/// # let capacity = 10;
+/// let (message_tx, message_rx) = message_channel(capacity).into_parts();
+/// let mut message_rx = RecordingReceiver::new(message_rx, Vec::new());
+/// let message_port =
+///     MessagePort::<_, _, RecordingTransport<MpscTransport, _>>::new_with_transport(message_tx);
+/// let mut task_context = TaskContext::new(task_executor, message_port);
+/// consume_messages(&mut message_rx, &mut task_context, &mut model, &mut render_model).await;
+/// let recorded_messages = message_rx.into_sink();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecordingTransport<Transport, Sink> {
+    _phantom: PhantomData<fn() -> (Transport, Sink)>,
+}
+
+/// Receiver of a [`RecordingTransport`]
+pub struct RecordingReceiver<Intent, Effect, Transport, Sink>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    receiver: MessageReceiver<Intent, Effect, Transport>,
+    sink: Sink,
+}
+
+impl<Intent, Effect, Transport, Sink> RecordingReceiver<Intent, Effect, Transport, Sink>
+where
+    Transport: MessageTransport<Intent, Effect>,
+{
+    /// Create a new instance
+    #[must_use]
+    pub const fn new(receiver: MessageReceiver<Intent, Effect, Transport>, sink: Sink) -> Self {
+        Self { receiver, sink }
+    }
+
+    /// The sink with all messages that have been recorded
+    #[must_use]
+    pub const fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    /// The sink for manipulating it directly, e.g. for draining it
+    #[must_use]
+    pub fn sink_mut(&mut self) -> &mut Sink {
+        &mut self.sink
+    }
+
+    /// Consume the receiver and return the sink
+    #[must_use]
+    pub fn into_sink(self) -> Sink {
+        let Self { receiver: _, sink } = self;
+        sink
+    }
+
+    /// Consume the receiver and return both the inner receiver and the sink
+    #[must_use]
+    pub fn into_parts(self) -> (MessageReceiver<Intent, Effect, Transport>, Sink) {
+        let Self { receiver, sink } = self;
+        (receiver, sink)
+    }
+}
+
+impl<Intent, Effect, Transport, Sink> fmt::Debug
+    for RecordingReceiver<Intent, Effect, Transport, Sink>
+where
+    Transport: MessageTransport<Intent, Effect>,
+    Sink: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { receiver: _, sink } = self;
+        f.debug_struct("RecordingReceiver")
+            .field("sink", sink)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Intent, Effect, Transport, Sink> MessageTransport<Intent, Effect>
+    for RecordingTransport<Transport, Sink>
+where
+    Transport: MessageTransport<Intent, Effect>,
+    Sink: MessageSink<Intent, Effect>,
+{
+    type Sender = MessageSender<Intent, Effect, Transport>;
+    type Receiver = RecordingReceiver<Intent, Effect, Transport, Sink>;
+
+    fn try_send(
+        sender: &mut Self::Sender,
+        message: Message<Intent, Effect>,
+    ) -> Result<(), TrySendError<Intent, Effect>> {
+        Transport::try_send(sender, message)
+    }
+
+    fn poll_ready(
+        sender: &mut Self::Sender,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ChannelClosed>> {
+        Transport::poll_ready(sender, cx)
+    }
+
+    fn is_closed(sender: &Self::Sender) -> bool {
+        Transport::is_closed(sender)
+    }
+
+    fn pending_message_count(sender: &Self::Sender) -> Option<usize> {
+        Transport::pending_message_count(sender)
+    }
+
+    fn poll_next(
+        receiver: &mut Self::Receiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Message<Intent, Effect>>> {
+        let next_message = Transport::poll_next(&mut receiver.receiver, cx);
+        if let Poll::Ready(Some(message)) = &next_message {
+            receiver.sink.record_message(message);
+        }
+        next_message
+    }
+
+    fn try_recv(receiver: &mut Self::Receiver) -> Result<Message<Intent, Effect>, TryRecvError> {
+        let message = Transport::try_recv(&mut receiver.receiver)?;
+        receiver.sink.record_message(&message);
+        Ok(message)
+    }
+}
@@ -0,0 +1,247 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+use std::{
+    fmt,
+    future::poll_fn,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{ModelRender, Snapshot, TryModel};
+
+#[derive(Debug)]
+struct WatchState<T> {
+    latest: Option<Arc<T>>,
+    version: u64,
+    closed: bool,
+    wakers: Vec<Waker>,
+}
+
+#[derive(Debug)]
+struct WatchShared<T> {
+    state: Mutex<WatchState<T>>,
+}
+
+impl<T> WatchShared<T> {
+    fn lock(&self) -> MutexGuard<'_, WatchState<T>> {
+        // The state remains consistent even if a panic occurred while locked.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn publish(&self, value: T) {
+        let wakers = {
+            let mut state = self.lock();
+            state.latest = Some(Arc::new(value));
+            state.version += 1;
+            std::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        let wakers = {
+            let mut state = self.lock();
+            state.closed = true;
+            std::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Publishes the model for watchers instead of rendering it
+///
+/// Each time the model is rendered a value is derived from the model
+/// and published to all [`ModelWatch`]ers. The model is only rendered
+/// if the render hint requests it, i.e. unchanged models are not
+/// published.
+///
+/// Enables pull-based rendering, e.g. for signal-based user interfaces.
+///
+/// Requires the `watch` feature.
+pub struct WatchRender<M, T> {
+    shared: Arc<WatchShared<T>>,
+    to_value: fn(&M) -> T,
+}
+
+impl<C> WatchRender<C, C>
+where
+    C: Clone,
+{
+    /// Publish clones of the model
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_fn(C::clone)
+    }
+}
+
+impl<C> Default for WatchRender<C, C>
+where
+    C: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> WatchRender<M, M::Snapshot>
+where
+    M: Snapshot,
+{
+    /// Publish snapshots of the model
+    #[must_use]
+    pub fn with_snapshot() -> Self {
+        Self::with_fn(M::snapshot)
+    }
+}
+
+impl<M, T> WatchRender<M, T> {
+    /// Publish values that are derived from the model
+    #[must_use]
+    pub fn with_fn(to_value: fn(&M) -> T) -> Self {
+        let state = WatchState {
+            latest: None,
+            version: 0,
+            closed: false,
+            wakers: Vec::new(),
+        };
+        Self {
+            shared: Arc::new(WatchShared {
+                state: Mutex::new(state),
+            }),
+            to_value,
+        }
+    }
+
+    /// Create a new watcher
+    ///
+    /// The watcher considers the latest published value, if any, as
+    /// already seen.
+    #[must_use]
+    pub fn watch(&self) -> ModelWatch<T> {
+        let seen_version = self.shared.lock().version;
+        ModelWatch {
+            shared: Arc::clone(&self.shared),
+            seen_version,
+        }
+    }
+}
+
+impl<M, T> Drop for WatchRender<M, T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl<M, T> fmt::Debug for WatchRender<M, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            shared,
+            to_value: _,
+        } = self;
+        f.debug_struct("WatchRender")
+            .field("shared", shared)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<M, T> ModelRender for WatchRender<M, T>
+where
+    M: TryModel,
+{
+    type Model = M;
+
+    fn render_model(
+        &mut self,
+        model: &Self::Model,
+        _hint: M::RenderHint,
+    ) -> impl IntoIterator<Item = M::Intent> {
+        self.shared.publish((self.to_value)(model));
+        None
+    }
+}
+
+/// Receives the values published by a [`WatchRender`]
+///
+/// Only the latest value is retained. Intermediate values are skipped
+/// if the watcher doesn't keep up.
+///
+/// Requires the `watch` feature.
+pub struct ModelWatch<T> {
+    shared: Arc<WatchShared<T>>,
+    seen_version: u64,
+}
+
+impl<T> ModelWatch<T> {
+    /// The latest published value
+    ///
+    /// Returns `None` if no value has been published yet. Doesn't mark
+    /// the value as seen.
+    #[must_use]
+    pub fn latest(&self) -> Option<Arc<T>> {
+        self.shared.lock().latest.clone()
+    }
+
+    /// Poll for a value that has not been seen yet
+    ///
+    /// Returns `Poll::Ready(None)` after the [`WatchRender`] has been
+    /// dropped and all values have been seen.
+    pub fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<Option<Arc<T>>> {
+        let mut state = self.shared.lock();
+        if state.version != self.seen_version {
+            self.seen_version = state.version;
+            return Poll::Ready(state.latest.clone());
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    /// Wait for a value that has not been seen yet
+    ///
+    /// Returns `None` after the [`WatchRender`] has been dropped and
+    /// all values have been seen.
+    pub async fn changed(&mut self) -> Option<Arc<T>> {
+        poll_fn(|cx| self.poll_changed(cx)).await
+    }
+}
+
+impl<T> Clone for ModelWatch<T> {
+    fn clone(&self) -> Self {
+        let Self {
+            shared,
+            seen_version,
+        } = self;
+        Self {
+            shared: Arc::clone(shared),
+            seen_version: *seen_version,
+        }
+    }
+}
+
+impl<T> fmt::Debug for ModelWatch<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            shared,
+            seen_version,
+        } = self;
+        f.debug_struct("ModelWatch")
+            .field("shared", shared)
+            .field("seen_version", seen_version)
+            .finish()
+    }
+}
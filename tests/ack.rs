@@ -0,0 +1,155 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Acknowledging intents after they have been handled.
+
+#![cfg(feature = "std")]
+
+use std::{
+    future::Future,
+    num::NonZeroUsize,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use infect::{
+    ack_message_channel, consume_messages_with_observer, message_channel, Ack, AckTransport,
+    EffectApplied, IntentHandled, MessageTransport as _, Model, ModelChanged, NoRender,
+    ProcessingOptions, TaskContext, TaskExecutor,
+};
+
+#[derive(Debug, Default)]
+struct Counter {
+    count: u32,
+}
+
+#[derive(Debug)]
+enum Intent {
+    Increment,
+    Reject,
+    Runaway,
+}
+
+/// Applies itself again forever
+#[derive(Debug)]
+struct Runaway;
+
+#[derive(Debug)]
+struct Task;
+
+impl Model for Counter {
+    type Intent = Intent;
+    type IntentRejected = ();
+    type Effect = Runaway;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        match intent {
+            Intent::Increment => {
+                self.count += 1;
+                IntentHandled::accepted_changed()
+            }
+            Intent::Reject => IntentHandled::rejected(()),
+            Intent::Runaway => IntentHandled::accepted(
+                EffectApplied::<Runaway, Task, ModelChanged>::unchanged_next(Runaway),
+            ),
+        }
+    }
+
+    fn apply_effect(
+        &mut self,
+        Runaway: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.count += 1;
+        EffectApplied::maybe_changed_next(Runaway)
+    }
+}
+
+/// The model never spawns tasks
+#[derive(Clone)]
+struct NoTasks;
+
+impl TaskExecutor<NoTasks, AckTransport> for NoTasks {
+    type Intent = Intent;
+    type Effect = Runaway;
+    type Task = Task;
+
+    fn spawn_task(
+        &self,
+        _context: TaskContext<NoTasks, Intent, Runaway, AckTransport>,
+        Task: Task,
+    ) {
+        unreachable!();
+    }
+}
+
+fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    pin!(future).poll(&mut Context::from_waker(Waker::noop()))
+}
+
+/// Submit a single intent with an acknowledgement and consume all messages
+fn ack(intent: Intent) -> Ack {
+    let (mut message_port, mut message_rx) = ack_message_channel(message_channel(10)).split();
+    let mut intent_acks = message_rx.intent_acks();
+    let mut intent_ack = message_port.submit_intent_ack(intent).unwrap();
+    assert!(poll(&mut intent_ack).is_pending());
+    let mut task_context = TaskContext::new(NoTasks, message_port);
+    let Poll::Ready(_) = poll(&mut pin!(consume_messages_with_observer(
+        &mut message_rx,
+        &mut task_context,
+        &mut Counter::default(),
+        &mut NoRender::new(),
+        ProcessingOptions {
+            max_effect_chain_len: NonZeroUsize::new(2),
+            ..Default::default()
+        },
+        &mut intent_acks,
+    ))) else {
+        panic!("consuming messages is pending");
+    };
+    let Poll::Ready(ack) = poll(&mut intent_ack) else {
+        panic!("acknowledgement is still pending");
+    };
+    ack
+}
+
+#[test]
+fn accepted_after_the_intent_has_been_handled() {
+    assert_eq!(Ack::Accepted, ack(Intent::Increment));
+}
+
+#[test]
+fn rejected_if_the_model_rejected_the_intent() {
+    assert_eq!(Ack::Rejected, ack(Intent::Reject));
+}
+
+#[test]
+fn failed_if_the_effect_chain_has_been_exceeded() {
+    assert_eq!(Ack::Failed, ack(Intent::Runaway));
+}
+
+#[test]
+fn dropped_if_the_intent_has_never_been_handled() {
+    let (mut message_port, message_rx) =
+        ack_message_channel::<Intent, Runaway, _>(message_channel(10)).split();
+    let mut intent_ack = message_port.submit_intent_ack(Intent::Increment).unwrap();
+    drop(message_rx);
+    assert_eq!(Poll::Ready(Ack::Dropped), poll(&mut intent_ack));
+}
+
+#[test]
+fn dropped_if_the_next_intent_has_been_received_without_processing() {
+    let (mut message_port, mut message_rx) =
+        ack_message_channel::<Intent, Runaway, _>(message_channel(10)).split();
+    let mut first_ack = message_port.submit_intent_ack(Intent::Increment).unwrap();
+    let mut second_ack = message_port.submit_intent_ack(Intent::Increment).unwrap();
+    assert!(AckTransport::try_recv(&mut message_rx).is_ok());
+    assert!(poll(&mut first_ack).is_pending());
+    assert!(AckTransport::try_recv(&mut message_rx).is_ok());
+    assert_eq!(Poll::Ready(Ack::Dropped), poll(&mut first_ack));
+    assert!(poll(&mut second_ack).is_pending());
+}
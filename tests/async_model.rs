@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Consuming messages of an `AsyncModel` with custom options.
+
+#![cfg(feature = "std")]
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    num::NonZeroUsize,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use infect::{
+    consume_messages_async_with_options, message_channel, AsyncModel, AsyncModelRender,
+    EffectApplied, InlineTaskExecutor, IntentHandled, MessagesConsumed, ModelChanged,
+    ProcessingOptions, TaskContext,
+};
+
+#[derive(Debug, Default)]
+struct Counter {
+    count: u32,
+}
+
+#[derive(Debug)]
+enum Intent {
+    Increment,
+}
+
+/// Applies itself again forever
+#[derive(Debug)]
+struct Runaway;
+
+#[derive(Debug)]
+struct Task;
+
+impl AsyncModel for Counter {
+    type Intent = Intent;
+    type IntentRejected = Infallible;
+    type Effect = Runaway;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    async fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        match intent {
+            Intent::Increment => {
+                self.count += 1;
+                IntentHandled::accepted_changed()
+            }
+        }
+    }
+
+    async fn apply_effect(
+        &mut self,
+        Runaway: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.count += 1;
+        EffectApplied::maybe_changed_next(Runaway)
+    }
+}
+
+/// Observes a new intent after each rendering
+#[derive(Default)]
+struct Render {
+    renders: u32,
+}
+
+impl AsyncModelRender for Render {
+    type Model = Counter;
+
+    fn render_model(
+        &mut self,
+        _model: &Counter,
+        _hint: ModelChanged,
+    ) -> impl IntoIterator<Item = Intent> {
+        self.renders += 1;
+        Some(Intent::Increment)
+    }
+}
+
+type Executor = InlineTaskExecutor<Intent, Runaway, Task>;
+
+fn poll_ready<F: Future>(future: F) -> F::Output {
+    let Poll::Ready(output) = pin!(future).poll(&mut Context::from_waker(Waker::noop())) else {
+        panic!("future is pending");
+    };
+    output
+}
+
+fn consume(
+    message: impl FnOnce(&mut TaskContext<Executor, Intent, Runaway>),
+    options: ProcessingOptions,
+) -> (Counter, Render, MessagesConsumed<Infallible>) {
+    let (message_port, mut message_rx) = message_channel(10).split();
+    let mut task_context = TaskContext::new(Executor::new(|_context, Task| async {}), message_port);
+    message(&mut task_context);
+    let mut model = Counter::default();
+    let mut render = Render::default();
+    let messages_consumed = poll_ready(consume_messages_async_with_options(
+        &mut message_rx,
+        &mut task_context,
+        &mut model,
+        &mut render,
+        options,
+    ));
+    (model, render, messages_consumed)
+}
+
+#[test]
+fn stops_after_the_effect_chain_exceeded() {
+    let (model, render, messages_consumed) = consume(
+        |task_context| task_context.submit_effect(Runaway).unwrap(),
+        ProcessingOptions {
+            max_effect_chain_len: NonZeroUsize::new(3),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        messages_consumed,
+        MessagesConsumed::EffectChainExceeded
+    ));
+    // The initial effect and 3 next effects.
+    assert_eq!(4, model.count);
+    // All changes are rendered before stopping.
+    assert_eq!(1, render.renders);
+}
+
+#[test]
+fn stops_after_a_render_loop_has_been_detected() {
+    for coalesce_renders in [false, true] {
+        let (model, render, messages_consumed) = consume(
+            |task_context| task_context.submit_intent(Intent::Increment).unwrap(),
+            ProcessingOptions {
+                max_consecutive_observed_intents: NonZeroUsize::new(2),
+                coalesce_renders,
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            messages_consumed,
+            MessagesConsumed::RenderLoopDetected
+        ));
+        assert_eq!(3, model.count);
+        assert_eq!(3, render.renders);
+    }
+}
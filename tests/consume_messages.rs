@@ -0,0 +1,331 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Consuming messages from the default, bounded message channel.
+
+#![cfg(feature = "std")]
+
+use std::{
+    convert::Infallible,
+    future::{pending, ready, Future},
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use infect::{
+    consume_messages, consume_messages_bounded, consume_messages_coalesced, consume_messages_until,
+    consume_messages_with_render_hint, consume_messages_with_stats, message_channel, EffectApplied,
+    InlineTaskExecutor, IntentHandled, MessagePort, MessageReceiver, MessagesConsumed, Model,
+    ModelChanged, ModelRender, NoRender, ProcessingOptions, ProcessingStats, TaskContext,
+};
+
+#[derive(Debug, Default)]
+struct Counter {
+    count: u32,
+}
+
+#[derive(Debug)]
+enum Intent {
+    Increment,
+}
+
+#[derive(Debug)]
+enum Effect {
+    Increment,
+    IncrementAndSpawn,
+}
+
+#[derive(Debug)]
+struct Task;
+
+impl Model for Counter {
+    type Intent = Intent;
+    type IntentRejected = Infallible;
+    type Effect = Effect;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        match intent {
+            Intent::Increment => {
+                self.count += 1;
+                IntentHandled::accepted_changed()
+            }
+        }
+    }
+
+    fn apply_effect(
+        &mut self,
+        effect: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.count += 1;
+        match effect {
+            Effect::Increment => EffectApplied::maybe_changed(),
+            Effect::IncrementAndSpawn => EffectApplied::maybe_changed_task(Task),
+        }
+    }
+}
+
+/// Records the count of each rendering
+#[derive(Default)]
+struct Render {
+    rendered_counts: Vec<u32>,
+}
+
+impl ModelRender for Render {
+    type Model = Counter;
+
+    fn render_model(
+        &mut self,
+        model: &Counter,
+        _hint: ModelChanged,
+    ) -> impl IntoIterator<Item = Intent> {
+        self.rendered_counts.push(model.count);
+        None
+    }
+}
+
+type Executor = InlineTaskExecutor<Intent, Effect, Task>;
+
+struct Fixture {
+    message_port: MessagePort<Intent, Effect>,
+    message_rx: MessageReceiver<Intent, Effect>,
+    task_context: TaskContext<Executor, Intent, Effect>,
+    model: Counter,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let (message_port, message_rx) = message_channel(10).split();
+        let task_executor = Executor::new(|_context, Task| async {});
+        let task_context = TaskContext::new(task_executor, message_port.clone());
+        Self {
+            message_port,
+            message_rx,
+            task_context,
+            model: Counter::default(),
+        }
+    }
+
+    fn consume_bounded(&mut self, max_messages: usize) -> MessagesConsumed<Infallible> {
+        let Self {
+            message_port: _,
+            message_rx,
+            task_context,
+            model,
+        } = self;
+        poll_ready(consume_messages_bounded(
+            message_rx,
+            task_context,
+            model,
+            &mut NoRender::new(),
+            max_messages,
+        ))
+    }
+
+    fn consume_until(&mut self, stop: impl Future) -> MessagesConsumed<Infallible> {
+        let Self {
+            message_port: _,
+            message_rx,
+            task_context,
+            model,
+        } = self;
+        poll_ready(consume_messages_until(
+            message_rx,
+            task_context,
+            model,
+            &mut NoRender::new(),
+            stop,
+        ))
+    }
+}
+
+fn poll_ready<F: Future>(future: F) -> F::Output {
+    let Poll::Ready(output) = pin!(future).poll(&mut Context::from_waker(Waker::noop())) else {
+        panic!("future is pending");
+    };
+    output
+}
+
+#[test]
+fn bounded_stops_with_budget_exhausted_if_messages_are_pending() {
+    let mut fixture = Fixture::new();
+    for _ in 0..3 {
+        fixture
+            .message_port
+            .submit_effect(Effect::Increment)
+            .unwrap();
+    }
+    assert!(matches!(
+        fixture.consume_bounded(2),
+        MessagesConsumed::BudgetExhausted
+    ));
+    assert_eq!(2, fixture.model.count);
+    assert_eq!(Some(1), fixture.message_port.pending_message_count());
+}
+
+#[test]
+fn bounded_stops_like_unbounded_if_the_channel_is_empty() {
+    let mut fixture = Fixture::new();
+    fixture
+        .message_port
+        .submit_effect(Effect::Increment)
+        .unwrap();
+    assert!(matches!(
+        fixture.consume_bounded(1),
+        MessagesConsumed::NoProgress
+    ));
+    fixture
+        .message_port
+        .submit_effect(Effect::IncrementAndSpawn)
+        .unwrap();
+    assert!(matches!(
+        fixture.consume_bounded(1),
+        MessagesConsumed::Pending
+    ));
+    assert_eq!(2, fixture.model.count);
+    assert_eq!(Some(0), fixture.message_port.pending_message_count());
+}
+
+#[test]
+fn render_hint_accumulates_accepted_intents_and_applied_effects() {
+    let mut fixture = Fixture::new();
+    fixture
+        .message_port
+        .submit_intent(Intent::Increment)
+        .unwrap();
+    let Fixture {
+        message_port: _,
+        message_rx,
+        task_context,
+        model,
+    } = &mut fixture;
+    let mut render_hint = ModelChanged::default();
+    assert!(matches!(
+        poll_ready(consume_messages_with_render_hint(
+            message_rx,
+            task_context,
+            model,
+            &mut NoRender::new(),
+            ProcessingOptions::default(),
+            &mut render_hint,
+        )),
+        MessagesConsumed::NoProgress
+    ));
+    assert_eq!(1, fixture.model.count);
+    assert_eq!(ModelChanged::MaybeChanged, render_hint);
+}
+
+#[test]
+fn stats_are_returned_for_a_single_pass() {
+    let mut fixture = Fixture::new();
+    fixture
+        .message_port
+        .submit_intent(Intent::Increment)
+        .unwrap();
+    fixture
+        .message_port
+        .submit_effect(Effect::IncrementAndSpawn)
+        .unwrap();
+    fixture
+        .message_port
+        .submit_effect(Effect::Increment)
+        .unwrap();
+    let Fixture {
+        message_port: _,
+        message_rx,
+        task_context,
+        model,
+    } = &mut fixture;
+    let (messages_consumed, stats) = poll_ready(consume_messages_with_stats(
+        message_rx,
+        task_context,
+        model,
+        &mut NoRender::new(),
+        ProcessingOptions::default(),
+    ));
+    assert!(matches!(messages_consumed, MessagesConsumed::NoProgress));
+    assert_eq!(
+        ProcessingStats {
+            messages: 3,
+            intents: 1,
+            rejections: 0,
+            effects: 2,
+            tasks_spawned: 1,
+            renders: 3,
+        },
+        stats
+    );
+}
+
+#[test]
+fn until_stops_without_receiving_pending_messages() {
+    let mut fixture = Fixture::new();
+    fixture
+        .message_port
+        .submit_effect(Effect::Increment)
+        .unwrap();
+    assert!(matches!(
+        fixture.consume_until(ready(())),
+        MessagesConsumed::Stopped
+    ));
+    assert_eq!(0, fixture.model.count);
+    assert_eq!(Some(1), fixture.message_port.pending_message_count());
+    // Resume consuming the remaining messages.
+    assert!(matches!(
+        fixture.consume_until(pending::<()>()),
+        MessagesConsumed::NoProgress
+    ));
+    assert_eq!(1, fixture.model.count);
+}
+
+#[test]
+fn coalesced_renders_once_after_draining_all_ready_messages() {
+    for coalesce_renders in [false, true] {
+        let mut fixture = Fixture::new();
+        fixture
+            .message_port
+            .submit_intent(Intent::Increment)
+            .unwrap();
+        fixture
+            .message_port
+            .submit_effect(Effect::Increment)
+            .unwrap();
+        fixture
+            .message_port
+            .submit_intent(Intent::Increment)
+            .unwrap();
+        let Fixture {
+            message_port: _,
+            message_rx,
+            task_context,
+            model,
+        } = &mut fixture;
+        let mut render = Render::default();
+        let messages_consumed = if coalesce_renders {
+            poll_ready(consume_messages_coalesced(
+                message_rx,
+                task_context,
+                model,
+                &mut render,
+            ))
+        } else {
+            poll_ready(consume_messages(
+                message_rx,
+                task_context,
+                model,
+                &mut render,
+            ))
+        };
+        assert!(matches!(messages_consumed, MessagesConsumed::NoProgress));
+        assert_eq!(3, fixture.model.count);
+        if coalesce_renders {
+            assert_eq!(vec![3], render.rendered_counts);
+        } else {
+            assert_eq!(vec![1, 2, 3], render.rendered_counts);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Awaiting the effects that respond to correlated requests.
+
+#![cfg(feature = "std")]
+
+use std::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use infect::{
+    consume_messages_with_observer, correlated_message_channel, message_channel, Correlatable,
+    CorrelatedTransport, CorrelationId, EffectApplied, IntentHandled, MessagesConsumed, Model,
+    ModelChanged, NoEffectResponse, NoRender, ProcessingOptions, TaskContext, TaskExecutor,
+};
+
+#[derive(Debug, Default)]
+struct Counter {
+    count: u32,
+}
+
+#[derive(Debug)]
+enum Request {
+    Increment,
+    Ignore,
+    Reject,
+}
+
+#[derive(Debug)]
+struct Intent {
+    request: Request,
+    correlation_id: Option<CorrelationId>,
+}
+
+impl From<Request> for Intent {
+    fn from(request: Request) -> Self {
+        Self {
+            request,
+            correlation_id: None,
+        }
+    }
+}
+
+impl Correlatable for Intent {
+    fn correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id
+    }
+
+    fn with_correlation_id(self, correlation_id: Option<CorrelationId>) -> Self {
+        Self {
+            correlation_id,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Incremented {
+    correlation_id: Option<CorrelationId>,
+}
+
+impl Correlatable for Incremented {
+    fn correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id
+    }
+
+    fn with_correlation_id(self, correlation_id: Option<CorrelationId>) -> Self {
+        Self { correlation_id }
+    }
+}
+
+#[derive(Debug)]
+struct Task;
+
+impl Model for Counter {
+    type Intent = Intent;
+    type IntentRejected = ();
+    type Effect = Incremented;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        let Intent {
+            request,
+            correlation_id,
+        } = intent;
+        match request {
+            Request::Increment => {
+                IntentHandled::accepted(
+                    EffectApplied::<Incremented, Task, ModelChanged>::unchanged_next(Incremented {
+                        correlation_id,
+                    }),
+                )
+            }
+            Request::Ignore => IntentHandled::accepted_unchanged(),
+            Request::Reject => IntentHandled::rejected(()),
+        }
+    }
+
+    fn apply_effect(
+        &mut self,
+        _effect: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.count += 1;
+        EffectApplied::maybe_changed()
+    }
+}
+
+/// The model never spawns tasks
+#[derive(Clone)]
+struct NoTasks;
+
+impl TaskExecutor<NoTasks, CorrelatedTransport> for NoTasks {
+    type Intent = Intent;
+    type Effect = Incremented;
+    type Task = Task;
+
+    fn spawn_task(
+        &self,
+        _context: TaskContext<NoTasks, Intent, Incremented, CorrelatedTransport>,
+        Task: Task,
+    ) {
+        unreachable!();
+    }
+}
+
+fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    pin!(future).poll(&mut Context::from_waker(Waker::noop()))
+}
+
+/// Submit a single request and consume all messages
+fn request(request: Request) -> (Counter, Result<Incremented, NoEffectResponse>) {
+    let (mut message_port, mut message_rx) =
+        correlated_message_channel(message_channel(10)).split();
+    let mut effect_responses = message_rx.effect_responses();
+    let mut effect_response = message_port.submit_and_await_effect(request).unwrap();
+    assert!(poll(&mut effect_response).is_pending());
+    let mut task_context = TaskContext::new(NoTasks, message_port);
+    let mut model = Counter::default();
+    let Poll::Ready(messages_consumed) = poll(&mut pin!(consume_messages_with_observer(
+        &mut message_rx,
+        &mut task_context,
+        &mut model,
+        &mut NoRender::new(),
+        ProcessingOptions::default(),
+        &mut effect_responses,
+    ))) else {
+        panic!("consuming messages is pending");
+    };
+    assert!(matches!(
+        messages_consumed,
+        MessagesConsumed::NoProgress | MessagesConsumed::IntentRejected(())
+    ));
+    let Poll::Ready(response) = poll(&mut effect_response) else {
+        panic!("request is still pending");
+    };
+    (model, response)
+}
+
+#[test]
+fn resolves_with_the_responding_effect() {
+    let (model, response) = request(Request::Increment);
+    assert!(response.unwrap().correlation_id.is_some());
+    assert_eq!(1, model.count);
+}
+
+#[test]
+fn fails_if_the_intent_has_been_rejected() {
+    let (model, response) = request(Request::Reject);
+    assert_eq!(NoEffectResponse::IntentRejected, response.unwrap_err());
+    assert_eq!(0, model.count);
+}
+
+#[test]
+fn fails_if_no_responding_effect_has_been_applied() {
+    let (model, response) = request(Request::Ignore);
+    assert_eq!(NoEffectResponse::Unanswered, response.unwrap_err());
+    assert_eq!(0, model.count);
+}
+
+#[test]
+fn fails_if_the_receiver_has_been_dropped() {
+    let (mut message_port, message_rx) =
+        correlated_message_channel::<Intent, Incremented, _>(message_channel(10)).split();
+    let mut effect_response = message_port
+        .submit_and_await_effect(Request::Increment)
+        .unwrap();
+    drop(message_rx);
+    assert_eq!(
+        Poll::Ready(Err(NoEffectResponse::ChannelClosed)),
+        poll(&mut effect_response).map(|response| response.map(|_| ()))
+    );
+}
@@ -0,0 +1,100 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Dropping duplicate intents that are still pending.
+
+#![cfg(feature = "std")]
+
+use infect::{
+    dedup_message_channel, message_channel, DedupMessagePort, DedupMessageReceiver,
+    DedupMessageSender, DedupTransport, Message, MessagePort, MessageTransport, MpscTransport,
+    TrySendError,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Intent {
+    Refresh,
+    Load(u32),
+}
+
+type Transport = DedupTransport<MpscTransport>;
+
+type Fixture = (
+    DedupMessagePort<Intent, u32>,
+    DedupMessageSender<Intent, u32, MpscTransport>,
+    DedupMessageReceiver<Intent, u32, MpscTransport>,
+);
+
+fn fixture() -> Fixture {
+    let (message_tx, message_rx) = dedup_message_channel(message_channel(10)).into_parts();
+    let message_port = MessagePort::new_with_transport(message_tx.clone());
+    (message_port, message_tx, message_rx)
+}
+
+fn recv_all(
+    message_rx: &mut DedupMessageReceiver<Intent, u32, MpscTransport>,
+) -> Vec<Message<Intent, u32>> {
+    std::iter::from_fn(|| Transport::try_recv(message_rx).ok()).collect()
+}
+
+#[test]
+fn drops_identical_pending_intents() {
+    let (mut message_port, message_tx, mut message_rx) = fixture();
+    message_port
+        .submit_intents([
+            Intent::Refresh,
+            Intent::Load(1),
+            Intent::Refresh,
+            Intent::Load(2),
+            Intent::Load(1),
+        ])
+        .unwrap();
+    assert_eq!(3, message_tx.pending_intent_count());
+    assert_eq!(
+        vec![
+            Message::Intent(Intent::Refresh),
+            Message::Intent(Intent::Load(1)),
+            Message::Intent(Intent::Load(2)),
+        ],
+        recv_all(&mut message_rx)
+    );
+    assert_eq!(0, message_tx.pending_intent_count());
+}
+
+#[test]
+fn accepts_an_intent_again_after_it_has_been_received() {
+    let (mut message_port, message_tx, mut message_rx) = fixture();
+    message_port.submit_intent(Intent::Refresh).unwrap();
+    assert_eq!(
+        Ok(Message::Intent(Intent::Refresh)),
+        Transport::try_recv(&mut message_rx)
+    );
+    message_port.submit_intent(Intent::Refresh).unwrap();
+    assert_eq!(1, message_tx.pending_intent_count());
+    assert_eq!(
+        vec![Message::Intent(Intent::Refresh)],
+        recv_all(&mut message_rx)
+    );
+}
+
+#[test]
+fn never_drops_effects() {
+    let (mut message_port, _message_tx, mut message_rx) = fixture();
+    message_port.submit_effect(1_u32).unwrap();
+    message_port.submit_effect(1_u32).unwrap();
+    assert_eq!(
+        vec![Message::Effect(1), Message::Effect(1)],
+        recv_all(&mut message_rx)
+    );
+}
+
+#[test]
+fn forgets_intents_that_could_not_be_sent() {
+    let (mut message_port, message_tx, message_rx) = fixture();
+    drop(message_rx);
+    assert!(matches!(
+        message_port.submit_intent(Intent::Refresh),
+        Err(TrySendError::Disconnected(Message::Intent(Intent::Refresh)))
+    ));
+    assert_eq!(0, message_tx.pending_intent_count());
+}
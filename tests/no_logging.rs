@@ -0,0 +1,98 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Types that do not implement `Debug` are accepted if the
+//! `logging` feature is disabled.
+
+#![cfg(all(feature = "std", not(feature = "logging")))]
+
+use std::rc::Rc;
+
+use infect::{
+    message_channel, process_message, EffectApplied, IntentHandled, Message, MessagePort,
+    MessageProcessed, Model, ModelChanged, ModelRender, TaskContext, TaskExecutor,
+};
+
+struct Intent;
+
+struct IntentRejected;
+
+struct Effect;
+
+struct Task(Box<dyn FnOnce()>);
+
+#[derive(Default)]
+struct Counter {
+    count: usize,
+}
+
+impl Model for Counter {
+    type Intent = Intent;
+    type IntentRejected = IntentRejected;
+    type Effect = Effect;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        Intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        IntentHandled::Accepted(EffectApplied::unchanged_next(Effect))
+    }
+
+    fn apply_effect(
+        &mut self,
+        Effect: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.count += 1;
+        EffectApplied::maybe_changed_task(Task(Box::new(|| {})))
+    }
+}
+
+struct Render;
+
+impl ModelRender for Render {
+    type Model = Counter;
+
+    fn render_model(
+        &mut self,
+        _model: &Counter,
+        _hint: ModelChanged,
+    ) -> impl IntoIterator<Item = Intent> {
+        None
+    }
+}
+
+struct Executor;
+
+impl TaskExecutor<Rc<Executor>> for Executor {
+    type Intent = Intent;
+    type Effect = Effect;
+    type Task = Task;
+
+    fn spawn_task(&self, _context: TaskContext<Rc<Executor>, Intent, Effect>, task: Task) {
+        (task.0)();
+    }
+}
+
+#[test]
+fn process_message_without_debug() {
+    let (message_tx, _message_rx) = message_channel(1).into_parts();
+    let mut task_context = TaskContext::new(Rc::new(Executor), MessagePort::new(message_tx));
+    let mut model = Counter::default();
+    let processed = process_message(
+        &mut task_context,
+        &mut model,
+        &mut Render,
+        Message::Intent(Intent),
+    );
+    assert!(matches!(
+        processed,
+        MessageProcessed::Progressing {
+            tasks_spawned: 1,
+            rendered: true,
+            render_hint: ModelChanged::MaybeChanged,
+        }
+    ));
+    assert_eq!(1, model.count);
+}
@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Delivering messages in the order of their priority.
+
+#![cfg(feature = "std")]
+
+use std::num::NonZeroUsize;
+
+use infect::{
+    message_channel, priority_level_channel, Message, MessagePort, MessageTransport, MpscTransport,
+    Priority, PriorityLevelTransport, PriorityMessageReceiver, PriorityTransport, TryRecvError,
+    TrySendError,
+};
+
+type Transport = PriorityTransport<MpscTransport>;
+
+fn priority_channel(
+    max_buffered_messages: usize,
+) -> (
+    MessagePort<u32, u32, Transport>,
+    PriorityMessageReceiver<u32, u32, MpscTransport>,
+) {
+    let (message_tx, message_rx) = message_channel(10).into_parts();
+    let message_rx = PriorityMessageReceiver::new(
+        message_rx,
+        NonZeroUsize::new(max_buffered_messages).unwrap(),
+    );
+    (MessagePort::new_with_transport(message_tx), message_rx)
+}
+
+fn recv_all(
+    message_rx: &mut PriorityMessageReceiver<u32, u32, MpscTransport>,
+) -> Vec<Message<u32, u32>> {
+    std::iter::from_fn(|| Transport::try_recv(message_rx).ok()).collect()
+}
+
+#[test]
+fn effects_are_delivered_before_intents_in_submission_order() {
+    let (mut message_port, mut message_rx) = priority_channel(10);
+    message_port.submit_intent(1_u32).unwrap();
+    message_port.submit_effect(2_u32).unwrap();
+    message_port.submit_intent(3_u32).unwrap();
+    message_port.submit_effect(4_u32).unwrap();
+    assert_eq!(
+        vec![
+            Message::Effect(2),
+            Message::Effect(4),
+            Message::Intent(1),
+            Message::Intent(3),
+        ],
+        recv_all(&mut message_rx)
+    );
+    assert_eq!(0, message_rx.buffered_message_count());
+}
+
+#[test]
+fn effects_are_only_preferred_within_the_buffered_window() {
+    let (mut message_port, mut message_rx) = priority_channel(2);
+    for intent in 1..=3_u32 {
+        message_port.submit_intent(intent).unwrap();
+    }
+    message_port.submit_effect(4_u32).unwrap();
+    assert_eq!(Ok(Message::Intent(1)), Transport::try_recv(&mut message_rx));
+    // Messages beyond the window remain in the inner channel and
+    // count as pending for backpressure.
+    assert_eq!(1, message_rx.buffered_message_count());
+    assert_eq!(Some(2), message_port.pending_message_count());
+    assert_eq!(Ok(Message::Intent(2)), Transport::try_recv(&mut message_rx));
+    assert_eq!(Ok(Message::Effect(4)), Transport::try_recv(&mut message_rx));
+    assert_eq!(Ok(Message::Intent(3)), Transport::try_recv(&mut message_rx));
+    assert_eq!(
+        Err(TryRecvError::Empty),
+        Transport::try_recv(&mut message_rx)
+    );
+}
+
+#[test]
+fn buffered_messages_are_delivered_after_the_channel_has_been_closed() {
+    let (mut message_port, mut message_rx) = priority_channel(10);
+    message_port.submit_intent(1_u32).unwrap();
+    message_port.submit_effect(2_u32).unwrap();
+    drop(message_port);
+    assert_eq!(Ok(Message::Effect(2)), Transport::try_recv(&mut message_rx));
+    assert_eq!(Ok(Message::Intent(1)), Transport::try_recv(&mut message_rx));
+    assert_eq!(
+        Err(TryRecvError::Closed),
+        Transport::try_recv(&mut message_rx)
+    );
+}
+
+type LevelTransport = PriorityLevelTransport<MpscTransport>;
+
+#[test]
+fn messages_with_a_higher_priority_level_are_delivered_first() {
+    let (mut message_port, mut message_rx) =
+        priority_level_channel::<u32, u32, _>(|| message_channel(10)).split();
+    message_port
+        .submit_message_with_priority(Message::Intent(1_u32), Priority::Low)
+        .unwrap();
+    message_port.submit_intent(2_u32).unwrap();
+    message_port
+        .submit_message_with_priority(Message::Effect(3_u32), Priority::High)
+        .unwrap();
+    message_port
+        .submit_message_with_priority(Message::Intent(4_u32), Priority::Normal)
+        .unwrap();
+    message_port
+        .submit_message_with_priority(Message::Intent(5_u32), Priority::High)
+        .unwrap();
+    assert_eq!(Some(5), message_port.pending_message_count());
+    let messages: Vec<_> =
+        std::iter::from_fn(|| LevelTransport::try_recv(&mut message_rx).ok()).collect();
+    assert_eq!(
+        vec![
+            Message::Effect(3),
+            Message::Intent(5),
+            Message::Intent(2),
+            Message::Intent(4),
+            Message::Intent(1),
+        ],
+        messages
+    );
+    drop(message_port);
+    assert_eq!(
+        Err(TryRecvError::Closed),
+        LevelTransport::try_recv(&mut message_rx)
+    );
+}
+
+#[test]
+fn each_priority_level_has_a_separate_capacity() {
+    let (mut message_port, _message_rx) =
+        priority_level_channel::<u32, u32, _>(|| message_channel(1)).split();
+    let full = (0..100_u32)
+        .map(|intent| message_port.submit_intent(intent))
+        .find_map(Result::err);
+    assert!(matches!(full, Some(TrySendError::Full(_))));
+    assert!(message_port
+        .submit_message_with_priority(Message::Intent(0_u32), Priority::High)
+        .is_ok());
+}
@@ -0,0 +1,193 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Lifecycle of the message loop that is driven by `run_model()`.
+
+#![cfg(feature = "std")]
+
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use infect::{
+    run_model, EffectApplied, IntentHandled, MessagePort, MessagesConsumed, Model, ModelChanged,
+    NoRender, TaskContext, TaskExecutor,
+};
+
+#[derive(Debug, Default)]
+struct Lifecycle {
+    started: u32,
+    stopped: u32,
+    count: u32,
+}
+
+#[derive(Debug)]
+enum Intent {
+    Increment,
+    Reject,
+}
+
+#[derive(Debug)]
+enum Effect {
+    Increment,
+    IncrementAndSpawn,
+}
+
+#[derive(Debug)]
+struct Task;
+
+impl Model for Lifecycle {
+    type Intent = Intent;
+    type IntentRejected = ();
+    type Effect = Effect;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        match intent {
+            Intent::Increment => IntentHandled::accepted(
+                EffectApplied::<Effect, Task, ModelChanged>::unchanged_next(Effect::Increment),
+            ),
+            Intent::Reject => IntentHandled::rejected(()),
+        }
+    }
+
+    fn apply_effect(
+        &mut self,
+        effect: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.count += 1;
+        match effect {
+            Effect::Increment => EffectApplied::maybe_changed(),
+            Effect::IncrementAndSpawn => EffectApplied::maybe_changed_task(Task),
+        }
+    }
+
+    fn on_start(&mut self) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.started += 1;
+        EffectApplied::unchanged()
+    }
+
+    fn on_stop(&mut self) {
+        self.stopped += 1;
+    }
+}
+
+type SpawnedContext = TaskContext<Spawner, Intent, Effect>;
+
+/// Keeps the contexts of all spawned tasks until they are finished
+#[derive(Clone, Default)]
+struct Spawner {
+    tasks: Rc<RefCell<Vec<SpawnedContext>>>,
+}
+
+impl TaskExecutor<Spawner> for Spawner {
+    type Intent = Intent;
+    type Effect = Effect;
+    type Task = Task;
+
+    fn spawn_task(&self, context: SpawnedContext, Task: Task) {
+        self.tasks.borrow_mut().push(context);
+    }
+}
+
+type RunModel = Pin<Box<dyn Future<Output = (Lifecycle, MessagesConsumed<()>)>>>;
+
+fn start(spawner: Spawner) -> (MessagePort<Intent, Effect>, RunModel) {
+    let message_port = Rc::new(RefCell::new(None));
+    let mut run = Box::pin(run_model(
+        Lifecycle::default(),
+        NoRender::new(),
+        spawner,
+        10,
+        {
+            let message_port = Rc::clone(&message_port);
+            move |port| *message_port.borrow_mut() = Some(port)
+        },
+    )) as RunModel;
+    assert!(poll(run.as_mut()).is_pending());
+    let message_port = message_port.borrow_mut().take().unwrap();
+    (message_port, run)
+}
+
+fn poll<F: Future + ?Sized>(future: Pin<&mut F>) -> Poll<F::Output> {
+    future.poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn keeps_running_while_idle_until_all_ports_have_been_dropped() {
+    let (mut message_port, mut run) = start(Spawner::default());
+    for _ in 0..3 {
+        message_port.submit_intent(Intent::Increment).unwrap();
+        message_port.submit_effect(Effect::Increment).unwrap();
+        assert!(poll(run.as_mut()).is_pending());
+    }
+    let cloned_port = message_port.clone();
+    drop(message_port);
+    assert!(poll(run.as_mut()).is_pending());
+    drop(cloned_port);
+    let Poll::Ready((model, messages_consumed)) = poll(run.as_mut()) else {
+        panic!("model is still running");
+    };
+    assert!(matches!(messages_consumed, MessagesConsumed::ChannelClosed));
+    assert_eq!(6, model.count);
+    assert_eq!(1, model.started);
+    assert_eq!(1, model.stopped);
+}
+
+#[test]
+fn consumes_pending_messages_after_all_ports_have_been_dropped() {
+    let (mut message_port, mut run) = start(Spawner::default());
+    message_port.submit_effect(Effect::Increment).unwrap();
+    message_port.submit_effect(Effect::Increment).unwrap();
+    drop(message_port);
+    let Poll::Ready((model, messages_consumed)) = poll(run.as_mut()) else {
+        panic!("model is still running");
+    };
+    assert!(matches!(messages_consumed, MessagesConsumed::ChannelClosed));
+    assert_eq!(2, model.count);
+}
+
+#[test]
+fn keeps_running_while_spawned_tasks_could_submit_messages() {
+    let spawner = Spawner::default();
+    let (mut message_port, mut run) = start(spawner.clone());
+    message_port
+        .submit_effect(Effect::IncrementAndSpawn)
+        .unwrap();
+    drop(message_port);
+    assert!(poll(run.as_mut()).is_pending());
+    let mut task_context = spawner.tasks.borrow_mut().pop().unwrap();
+    task_context.submit_effect(Effect::Increment).unwrap();
+    assert!(poll(run.as_mut()).is_pending());
+    drop(task_context);
+    let Poll::Ready((model, messages_consumed)) = poll(run.as_mut()) else {
+        panic!("model is still running");
+    };
+    assert!(matches!(messages_consumed, MessagesConsumed::ChannelClosed));
+    assert_eq!(2, model.count);
+}
+
+#[test]
+fn stops_after_an_intent_has_been_rejected() {
+    let (mut message_port, mut run) = start(Spawner::default());
+    message_port.submit_intent(Intent::Reject).unwrap();
+    message_port.submit_effect(Effect::Increment).unwrap();
+    let Poll::Ready((model, messages_consumed)) = poll(run.as_mut()) else {
+        panic!("model is still running");
+    };
+    assert!(matches!(
+        messages_consumed,
+        MessagesConsumed::IntentRejected(())
+    ));
+    assert_eq!(0, model.count);
+    assert_eq!(1, model.stopped);
+    assert_eq!(Some(1), message_port.pending_message_count());
+}
@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Driving a model through a `Runtime`.
+
+#![cfg(feature = "std")]
+
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    task::{Context, Poll, Waker},
+};
+
+use infect::{
+    EffectApplied, InlineTaskExecutor, IntentHandled, MessagesConsumed, Model, ModelChanged,
+    ProcessingStats, Runtime,
+};
+
+#[derive(Debug, Default)]
+struct Counter {
+    started: u32,
+    stopped: u32,
+    count: u32,
+}
+
+#[derive(Debug)]
+enum Intent {
+    Increment,
+    Reject,
+}
+
+#[derive(Debug)]
+struct Increment;
+
+#[derive(Debug)]
+struct Task;
+
+impl Model for Counter {
+    type Intent = Intent;
+    type IntentRejected = ();
+    type Effect = Increment;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        match intent {
+            Intent::Increment => {
+                IntentHandled::accepted(
+                    EffectApplied::<Increment, Task, ModelChanged>::unchanged_next(Increment),
+                )
+            }
+            Intent::Reject => IntentHandled::rejected(()),
+        }
+    }
+
+    fn apply_effect(
+        &mut self,
+        Increment: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.count += 1;
+        EffectApplied::maybe_changed()
+    }
+
+    fn on_start(&mut self) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        self.started += 1;
+        EffectApplied::unchanged()
+    }
+
+    fn on_stop(&mut self) {
+        self.stopped += 1;
+    }
+}
+
+type Executor = InlineTaskExecutor<Intent, Increment, Task>;
+
+fn poll<F: Future>(future: &mut Pin<&mut F>) -> Poll<F::Output> {
+    future
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn runs_through_idle_and_busy_cycles_until_the_port_is_dropped() {
+    let (mut message_port, mut runtime) = Runtime::builder(Counter::default(), 10)
+        .task_executor(Executor::new(|_context, Task| async {}))
+        .observer(ProcessingStats::default())
+        .build();
+    {
+        let mut run = pin!(runtime.run());
+        assert!(poll(&mut run).is_pending());
+        for cycle in 1..=3 {
+            for _ in 0..cycle {
+                message_port.submit_intent(Intent::Increment).unwrap();
+            }
+            // Busy until all messages have been consumed, idle afterwards.
+            assert!(poll(&mut run).is_pending());
+            assert_eq!(Some(0), message_port.pending_message_count());
+        }
+        drop(message_port);
+        let Poll::Ready(messages_consumed) = poll(&mut run) else {
+            panic!("runtime is still running");
+        };
+        assert!(matches!(messages_consumed, MessagesConsumed::ChannelClosed));
+    }
+    assert_eq!(6, runtime.model().count);
+    assert_eq!(6, runtime.observer().intents);
+    let (model, _stats) = runtime.into_inner();
+    assert_eq!(1, model.started);
+    assert_eq!(1, model.stopped);
+}
+
+#[test]
+fn resumes_after_an_intent_has_been_rejected() {
+    let (mut message_port, mut runtime) = Runtime::builder(Counter::default(), 10)
+        .task_executor(Executor::new(|_context, Task| async {}))
+        .build();
+    message_port.submit_intent(Intent::Reject).unwrap();
+    message_port.submit_intent(Intent::Increment).unwrap();
+    let Poll::Ready(messages_consumed) = poll(&mut pin!(runtime.run())) else {
+        panic!("runtime is still running");
+    };
+    assert!(matches!(
+        messages_consumed,
+        MessagesConsumed::IntentRejected(())
+    ));
+    assert_eq!(0, runtime.model().count);
+    drop(message_port);
+    let Poll::Ready(messages_consumed) = poll(&mut pin!(runtime.run())) else {
+        panic!("runtime is still running");
+    };
+    assert!(matches!(messages_consumed, MessagesConsumed::ChannelClosed));
+    let (model, ()) = runtime.into_inner();
+    assert_eq!(1, model.count);
+    assert_eq!(2, model.started);
+    assert_eq!(2, model.stopped);
+}
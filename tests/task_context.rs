@@ -0,0 +1,234 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Spawning and tracking tasks through a `TaskContext`.
+
+#![cfg(feature = "std")]
+
+use std::{
+    cell::{Cell, RefCell},
+    future::{pending, Future},
+    pin::pin,
+    rc::Rc,
+    task::{self, Poll, Waker},
+};
+
+use infect::{
+    message_channel, InlineTaskExecutor, Message, MessageReceiver, MessageTransport as _,
+    MpscTransport, TaskContext, TaskExecutor,
+};
+
+#[derive(Debug)]
+struct Task;
+
+type Context = TaskContext<Spawner, (), u32>;
+
+/// Keeps the contexts of all spawned tasks until they are finished
+#[derive(Clone, Default)]
+struct Spawner {
+    tasks: Rc<RefCell<Vec<Context>>>,
+}
+
+impl Spawner {
+    fn take_task(&self) -> Context {
+        self.tasks.borrow_mut().pop().unwrap()
+    }
+}
+
+impl TaskExecutor<Spawner> for Spawner {
+    type Intent = ();
+    type Effect = u32;
+    type Task = Task;
+
+    fn spawn_task(&self, context: Context, Task: Task) {
+        self.tasks.borrow_mut().push(context);
+    }
+}
+
+fn tracked_context() -> (Spawner, Context, MessageReceiver<(), u32>) {
+    let spawner = Spawner::default();
+    let (message_port, message_rx) = message_channel(10).split();
+    let context = TaskContext::new(spawner.clone(), message_port).with_task_tracking();
+    (spawner, context, message_rx)
+}
+
+fn try_recv_effect(message_rx: &mut MessageReceiver<(), u32>) -> Option<u32> {
+    match MpscTransport::try_recv(message_rx) {
+        Ok(Message::Effect(effect)) => Some(effect),
+        _ => None,
+    }
+}
+
+fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    pin!(future).poll(&mut task::Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn untracked_tasks_have_no_id() {
+    let spawner = Spawner::default();
+    let (message_port, _message_rx) = message_channel::<(), u32>(10).split();
+    let context = TaskContext::new(spawner.clone(), message_port);
+    assert_eq!(None, context.spawn_task(Task));
+    assert_eq!(0, context.pending_task_count());
+    // The task has been spawned nevertheless.
+    assert!(!spawner.take_task().is_cancelled());
+}
+
+#[test]
+fn tracked_tasks_are_pending_until_their_context_is_dropped() {
+    let (spawner, context, _message_rx) = tracked_context();
+    let first_task_id = context.spawn_task(Task).unwrap();
+    let first_task = spawner.take_task();
+    let second_task_id = context.spawn_task(Task).unwrap();
+    let second_task = spawner.take_task();
+    assert_ne!(first_task_id, second_task_id);
+    assert_eq!(2, context.pending_task_count());
+    let mut join_all = pin!(context.join_all());
+    assert!(poll(&mut join_all).is_pending());
+    drop(second_task);
+    assert_eq!(1, context.pending_task_count());
+    assert!(poll(&mut join_all).is_pending());
+    drop(first_task);
+    assert_eq!(0, context.pending_task_count());
+    assert!(poll(&mut join_all).is_ready());
+}
+
+#[test]
+fn split_finishes_a_tracked_task() {
+    let (spawner, context, mut message_rx) = tracked_context();
+    assert!(context.spawn_task(Task).is_some());
+    assert_eq!(1, context.pending_task_count());
+    let (_task_executor, mut message_port) = spawner.take_task().split();
+    assert_eq!(0, context.pending_task_count());
+    // The message port remains usable after splitting.
+    message_port.submit_effect(1_u32).unwrap();
+    assert_eq!(Some(1), try_recv_effect(&mut message_rx));
+}
+
+#[test]
+fn clone_port_keeps_a_tracked_task_pending() {
+    let (spawner, context, mut message_rx) = tracked_context();
+    assert!(context.spawn_task(Task).is_some());
+    let task_context = spawner.take_task();
+    let mut message_port = task_context.clone_port();
+    message_port.submit_effect(1_u32).unwrap();
+    assert_eq!(1, context.pending_task_count());
+    drop(task_context);
+    assert_eq!(0, context.pending_task_count());
+    // The cloned port outlives the task.
+    message_port.submit_effect(2_u32).unwrap();
+    assert_eq!(Some(1), try_recv_effect(&mut message_rx));
+    assert_eq!(Some(2), try_recv_effect(&mut message_rx));
+}
+
+#[test]
+fn is_quiescent_if_neither_tasks_nor_messages_are_pending() {
+    let (message_port, _message_rx) = message_channel::<(), u32>(10).split();
+    let untracked_context = TaskContext::new(Spawner::default(), message_port);
+    assert_eq!(None, untracked_context.is_quiescent());
+    let (spawner, mut context, mut message_rx) = tracked_context();
+    assert_eq!(Some(true), context.is_quiescent());
+    context.submit_effect(1_u32).unwrap();
+    assert_eq!(Some(false), context.is_quiescent());
+    assert_eq!(Some(1), try_recv_effect(&mut message_rx));
+    assert_eq!(Some(true), context.is_quiescent());
+    assert!(context.spawn_task(Task).is_some());
+    assert_eq!(Some(false), context.is_quiescent());
+    drop(spawner.take_task());
+    assert_eq!(Some(true), context.is_quiescent());
+}
+
+/// Counts spawned tasks, independent of the executor type of the context
+#[derive(Clone, Default)]
+struct Counting {
+    spawned: Rc<Cell<u32>>,
+}
+
+impl<T> TaskExecutor<T> for Counting {
+    type Intent = ();
+    type Effect = u32;
+    type Task = Task;
+
+    fn spawn_task(&self, _context: TaskContext<T, (), u32>, Task: Task) {
+        self.spawned.set(self.spawned.get() + 1);
+    }
+}
+
+#[test]
+fn boxed_and_borrowed_executors_delegate_spawning_tasks() {
+    let counting = Counting::default();
+    let (message_port, _message_rx) = message_channel(10).split();
+    TaskContext::new(Box::new(counting.clone()), message_port.clone()).spawn_task(Task);
+    TaskContext::new(&counting, message_port).spawn_task(Task);
+    assert_eq!(2, counting.spawned.get());
+}
+
+#[test]
+fn inline_executor_drops_a_pending_task_after_the_first_poll() {
+    let polled = Rc::new(Cell::new(0));
+    let task_executor = InlineTaskExecutor::new({
+        let polled = Rc::clone(&polled);
+        move |mut context: TaskContext<_, (), u32>, Task| {
+            let polled = Rc::clone(&polled);
+            async move {
+                polled.set(polled.get() + 1);
+                context.submit_effect(1_u32).unwrap();
+                // Never woken up.
+                pending::<()>().await;
+                context.submit_effect(2_u32).unwrap();
+            }
+        }
+    });
+    let (message_port, mut message_rx) = message_channel(10).split();
+    let context = TaskContext::new(task_executor, message_port).with_task_tracking();
+    assert!(context.spawn_task(Task).is_some());
+    assert_eq!(1, polled.get());
+    // The task has finished when its future has been dropped.
+    assert_eq!(0, context.pending_task_count());
+    assert_eq!(Some(1), try_recv_effect(&mut message_rx));
+    assert_eq!(None, try_recv_effect(&mut message_rx));
+}
+
+#[test]
+fn cancel_task_notifies_only_the_cancelled_task() {
+    let (spawner, context, _message_rx) = tracked_context();
+    let first_task_id = context.spawn_task(Task).unwrap();
+    let first_task = spawner.take_task();
+    let second_task_id = context.spawn_task(Task).unwrap();
+    let second_task = spawner.take_task();
+    let mut cancelled = pin!(first_task.cancelled());
+    assert!(poll(&mut cancelled).is_pending());
+    assert!(context.cancel_task(first_task_id));
+    assert!(first_task.is_cancelled());
+    assert!(poll(&mut cancelled).is_ready());
+    assert!(!second_task.is_cancelled());
+    // The cancelled task is no longer pending, even though it is still alive.
+    assert_eq!(1, context.pending_task_count());
+    assert!(!context.cancel_task(first_task_id));
+    drop(second_task);
+    assert!(!context.cancel_task(second_task_id));
+}
+
+#[test]
+fn cancel_all_tasks_returns_the_number_of_cancelled_tasks() {
+    let (spawner, context, _message_rx) = tracked_context();
+    assert!(context.spawn_task(Task).is_some());
+    assert!(context.spawn_task(Task).is_some());
+    let tasks = [spawner.take_task(), spawner.take_task()];
+    assert_eq!(2, context.cancel_all_tasks());
+    assert!(tasks.iter().all(TaskContext::is_cancelled));
+    assert_eq!(0, context.pending_task_count());
+    assert_eq!(0, context.cancel_all_tasks());
+}
+
+#[test]
+fn untracked_tasks_could_not_be_cancelled() {
+    let spawner = Spawner::default();
+    let (message_port, _message_rx) = message_channel::<(), u32>(10).split();
+    let context = TaskContext::new(spawner.clone(), message_port);
+    assert_eq!(None, context.spawn_task(Task));
+    assert_eq!(0, context.cancel_all_tasks());
+    let task = spawner.take_task();
+    assert!(!task.is_cancelled());
+    assert!(poll(&mut pin!(task.cancelled())).is_pending());
+}
@@ -0,0 +1,250 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Spawning task futures with a timeout or retries.
+
+#![cfg(feature = "std")]
+
+use std::{
+    cell::RefCell,
+    future::{poll_fn, ready, Future},
+    num::NonZeroU32,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use infect::{
+    message_channel, Backoff, BoxedTaskFuture, Message, MessageReceiver, MessageTransport as _,
+    MpscTransport, RetryPolicy, TaskContext, TaskExecutor, TaskTrackingDisabled,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+enum Effect {
+    Fetched(u32),
+    TimedOut,
+    GaveUp,
+}
+
+enum Task {
+    /// Waits until dropped or cancelled
+    Wait,
+    Future(BoxedTaskFuture),
+}
+
+impl From<BoxedTaskFuture> for Task {
+    fn from(future: BoxedTaskFuture) -> Self {
+        Self::Future(future)
+    }
+}
+
+type Runner = TaskContext<Executor, (), Effect>;
+
+/// Keeps all spawned tasks until they are run manually
+#[derive(Clone, Default)]
+struct Executor {
+    waiting: Rc<RefCell<Vec<Runner>>>,
+    futures: Rc<RefCell<Vec<(Runner, BoxedTaskFuture)>>>,
+}
+
+impl Executor {
+    fn take_waiting(&self) -> Runner {
+        self.waiting.borrow_mut().pop().unwrap()
+    }
+
+    /// Poll all futures once and drop those that are ready
+    fn run_once(&self) {
+        let mut context = Context::from_waker(Waker::noop());
+        let futures = self.futures.take();
+        for (runner, mut future) in futures {
+            if future.as_mut().poll(&mut context).is_pending() {
+                self.futures.borrow_mut().push((runner, future));
+            }
+        }
+    }
+}
+
+impl TaskExecutor<Executor> for Executor {
+    type Intent = ();
+    type Effect = Effect;
+    type Task = Task;
+
+    fn spawn_task(&self, context: Runner, task: Task) {
+        match task {
+            Task::Wait => self.waiting.borrow_mut().push(context),
+            Task::Future(future) => self.futures.borrow_mut().push((context, future)),
+        }
+    }
+}
+
+fn tracked_context() -> (Executor, Runner, MessageReceiver<(), Effect>) {
+    let executor = Executor::default();
+    let (message_port, message_rx) = message_channel(10).split();
+    let context = TaskContext::new(executor.clone(), message_port).with_task_tracking();
+    (executor, context, message_rx)
+}
+
+fn try_recv_effect(message_rx: &mut MessageReceiver<(), Effect>) -> Option<Effect> {
+    match MpscTransport::try_recv(message_rx) {
+        Ok(Message::Effect(effect)) => Some(effect),
+        _ => None,
+    }
+}
+
+/// A timer that expires after it has been fired manually
+fn timer() -> (Arc<AtomicBool>, impl Future<Output = ()> + Send + 'static) {
+    let fired = Arc::new(AtomicBool::new(false));
+    let timer = poll_fn({
+        let fired = Arc::clone(&fired);
+        move |_| {
+            if fired.load(Ordering::Relaxed) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    });
+    (fired, timer)
+}
+
+#[test]
+fn timeout_cancels_a_pending_task() {
+    let (executor, context, mut message_rx) = tracked_context();
+    let (fired, timer) = timer();
+    let task_id = context
+        .spawn_task_with_timeout(Task::Wait, timer, Effect::TimedOut)
+        .unwrap();
+    let task = executor.take_waiting();
+    executor.run_once();
+    assert_eq!(1, context.pending_task_count());
+    assert!(!task.is_cancelled());
+    fired.store(true, Ordering::Relaxed);
+    executor.run_once();
+    assert!(task.is_cancelled());
+    assert_eq!(0, context.pending_task_count());
+    assert!(!context.cancel_task(task_id));
+    assert_eq!(Some(Effect::TimedOut), try_recv_effect(&mut message_rx));
+}
+
+#[test]
+fn timeout_is_discarded_if_the_task_finishes_in_time() {
+    let (executor, context, mut message_rx) = tracked_context();
+    let (fired, timer) = timer();
+    assert!(context
+        .spawn_task_with_timeout(Task::Wait, timer, Effect::TimedOut)
+        .is_ok());
+    drop(executor.take_waiting());
+    executor.run_once();
+    fired.store(true, Ordering::Relaxed);
+    executor.run_once();
+    assert!(executor.futures.borrow().is_empty());
+    assert_eq!(None, try_recv_effect(&mut message_rx));
+}
+
+#[test]
+fn timeout_requires_task_tracking() {
+    let executor = Executor::default();
+    let (message_port, _message_rx) = message_channel(10).split();
+    let context = TaskContext::new(executor.clone(), message_port);
+    let (_fired, timer) = timer();
+    assert_eq!(
+        Err(TaskTrackingDisabled),
+        context.spawn_task_with_timeout(Task::Wait, timer, Effect::TimedOut)
+    );
+    assert!(executor.waiting.borrow().is_empty());
+    assert!(executor.futures.borrow().is_empty());
+}
+
+/// Spawn a retrying task that fails a number of times before succeeding
+///
+/// Returns the submitted effect, the number of attempts, and all delays
+/// between them.
+fn retry(failures: u32, policy: RetryPolicy) -> (Option<Effect>, u32, Vec<Duration>) {
+    let (executor, context, mut message_rx) = tracked_context();
+    let attempts = Arc::new(AtomicU32::new(0));
+    let delays = Arc::new(Mutex::new(Vec::new()));
+    assert!(context
+        .spawn_retrying_task(
+            {
+                let attempts = Arc::clone(&attempts);
+                move || {
+                    let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+                    ready(if attempt < failures {
+                        Err(())
+                    } else {
+                        Ok(Effect::Fetched(attempt))
+                    })
+                }
+            },
+            policy,
+            {
+                let delays = Arc::clone(&delays);
+                move |delay| {
+                    delays.lock().unwrap().push(delay);
+                    ready(())
+                }
+            },
+            Effect::GaveUp,
+        )
+        .is_some());
+    executor.run_once();
+    assert_eq!(0, context.pending_task_count());
+    let delays = delays.lock().unwrap().clone();
+    (
+        try_recv_effect(&mut message_rx),
+        attempts.load(Ordering::Relaxed),
+        delays,
+    )
+}
+
+#[test]
+fn retry_submits_the_first_successful_attempt() {
+    let (effect, attempts, delays) = retry(
+        2,
+        RetryPolicy {
+            max_attempts: NonZeroU32::new(3).unwrap(),
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(10),
+                max: Duration::from_millis(15),
+            },
+        },
+    );
+    assert_eq!(Some(Effect::Fetched(2)), effect);
+    assert_eq!(3, attempts);
+    assert_eq!(
+        vec![Duration::from_millis(10), Duration::from_millis(15)],
+        delays
+    );
+}
+
+#[test]
+fn retry_gives_up_after_the_maximum_number_of_attempts() {
+    let (effect, attempts, delays) = retry(
+        u32::MAX,
+        RetryPolicy {
+            max_attempts: NonZeroU32::new(2).unwrap(),
+            backoff: Backoff::Fixed(Duration::from_millis(10)),
+        },
+    );
+    assert_eq!(Some(Effect::GaveUp), effect);
+    assert_eq!(2, attempts);
+    assert_eq!(vec![Duration::from_millis(10)], delays);
+}
+
+#[test]
+fn retry_does_not_sleep_without_a_delay() {
+    let (effect, attempts, delays) = retry(
+        1,
+        RetryPolicy {
+            max_attempts: NonZeroU32::new(2).unwrap(),
+            backoff: Backoff::Immediate,
+        },
+    );
+    assert_eq!(Some(Effect::Fetched(1)), effect);
+    assert_eq!(2, attempts);
+    assert!(delays.is_empty());
+}
@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Driving a model synchronously with the testing utilities.
+
+#![cfg(feature = "testing")]
+
+use infect::{
+    message_channel,
+    testing::{RecordingTaskExecutor, TestDriver},
+    EffectApplied, IntentHandled, MessageProcessed, Model, ModelChanged, TaskContext,
+};
+
+#[derive(Debug, Default)]
+struct Counter {
+    count: u32,
+}
+
+#[derive(Debug)]
+enum Intent {
+    Increment,
+    Fetch,
+    Reject,
+}
+
+#[derive(Debug)]
+enum Effect {
+    Fetched(u32),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Task {
+    Fetch,
+    Notify,
+}
+
+impl Model for Counter {
+    type Intent = Intent;
+    type IntentRejected = &'static str;
+    type Effect = Effect;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        match intent {
+            Intent::Increment => {
+                self.count += 1;
+                IntentHandled::accepted_changed()
+            }
+            Intent::Fetch => IntentHandled::accepted(
+                EffectApplied::<Effect, Task, ModelChanged>::unchanged_task(Task::Fetch),
+            ),
+            Intent::Reject => IntentHandled::rejected("rejected"),
+        }
+    }
+
+    fn apply_effect(
+        &mut self,
+        effect: Self::Effect,
+    ) -> EffectApplied<Self::Effect, Self::Task, Self::RenderHint> {
+        match effect {
+            Effect::Fetched(count) => {
+                self.count = count;
+                EffectApplied::maybe_changed().with_tasks([Task::Notify, Task::Notify])
+            }
+        }
+    }
+}
+
+#[test]
+fn step_processes_pending_messages_one_by_one() {
+    let mut driver = TestDriver::new(Counter::default());
+    driver.submit_intent(Intent::Increment).unwrap();
+    driver.submit_intent(Intent::Increment).unwrap();
+    // The model is not rendered.
+    assert!(matches!(
+        driver.step().unwrap().message_processed,
+        MessageProcessed::NoProgress {
+            rendered: false,
+            render_hint: ModelChanged::MaybeChanged,
+            ..
+        }
+    ));
+    assert_eq!(1, driver.model().count);
+    assert!(driver.step().is_some());
+    assert_eq!(2, driver.model().count);
+    assert!(driver.step().is_none());
+}
+
+#[test]
+fn step_reports_rejected_intents() {
+    let mut driver = TestDriver::new(Counter::default());
+    driver.submit_intent(Intent::Reject).unwrap();
+    assert!(matches!(
+        driver.step().unwrap().message_processed,
+        MessageProcessed::IntentRejected("rejected")
+    ));
+    assert_eq!(0, driver.into_model().count);
+}
+
+#[test]
+fn step_records_spawned_tasks_without_executing_them() {
+    let mut driver = TestDriver::new(Counter::default());
+    driver.submit_intent(Intent::Fetch).unwrap();
+    let outcome = driver.step().unwrap();
+    assert!(matches!(
+        outcome.message_processed,
+        MessageProcessed::Progressing {
+            tasks_spawned: 1,
+            ..
+        }
+    ));
+    assert_eq!(vec![Task::Fetch], outcome.spawned_tasks);
+    assert!(driver.step().is_none());
+
+    // Complete the task manually.
+    driver.submit_effect(Effect::Fetched(42)).unwrap();
+    let outcome = driver.step().unwrap();
+    assert_eq!(vec![Task::Notify, Task::Notify], outcome.spawned_tasks);
+    assert_eq!(42, driver.model().count);
+}
+
+#[test]
+fn step_processes_messages_submitted_through_a_port() {
+    let mut driver = TestDriver::new(Counter::default());
+    let mut message_port = driver.message_port();
+    message_port.submit_effect(Effect::Fetched(7)).unwrap();
+    drop(message_port);
+    let outcome = driver.step().unwrap();
+    assert_eq!(2, outcome.spawned_tasks.len());
+    assert_eq!(7, driver.model().count);
+}
+
+#[test]
+fn recording_executor_shares_spawned_tasks_between_clones() {
+    let task_executor = RecordingTaskExecutor::<Intent, Effect, Task>::new();
+    let (message_port, _message_rx) = message_channel(1).split();
+    let task_context = TaskContext::new(task_executor.clone(), message_port);
+    task_context.spawn_task(Task::Fetch);
+    task_context.spawn_task(Task::Notify);
+    assert_eq!(vec![Task::Fetch, Task::Notify], task_executor.drain_tasks());
+    assert!(task_executor.drain_tasks().is_empty());
+    assert!(task_context.task_executor().drain_tasks().is_empty());
+}
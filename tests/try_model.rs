@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Processing messages of a `TryModel` with fallible effects.
+
+#![cfg(feature = "std")]
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use infect::{
+    consume_messages, message_channel, EffectApplied, InlineTaskExecutor, IntentHandled,
+    MessagesConsumed, ModelChanged, NoRender, TaskContext, TryModel,
+};
+
+/// A counter that must not overflow
+#[derive(Debug, Default)]
+struct Counter {
+    count: u8,
+}
+
+#[derive(Debug)]
+struct Overflow;
+
+#[derive(Debug)]
+struct Task;
+
+impl TryModel for Counter {
+    type Intent = Infallible;
+    type IntentRejected = Infallible;
+    type Effect = u8;
+    type ApplyError = Overflow;
+    type Task = Task;
+    type RenderHint = ModelChanged;
+
+    fn handle_intent(
+        &mut self,
+        intent: Self::Intent,
+    ) -> IntentHandled<Self::IntentRejected, Self::Effect, Self::Task, Self::RenderHint> {
+        match intent {}
+    }
+
+    fn try_apply_effect(
+        &mut self,
+        increment: Self::Effect,
+    ) -> Result<EffectApplied<Self::Effect, Self::Task, Self::RenderHint>, Self::ApplyError> {
+        self.count = self.count.checked_add(increment).ok_or(Overflow)?;
+        Ok(EffectApplied::maybe_changed())
+    }
+}
+
+fn poll_ready<F: Future>(future: F) -> F::Output {
+    let Poll::Ready(output) = pin!(future).poll(&mut Context::from_waker(Waker::noop())) else {
+        panic!("future is pending");
+    };
+    output
+}
+
+#[test]
+fn stops_after_applying_an_effect_failed() {
+    let (message_port, mut message_rx) = message_channel(10).split();
+    let task_executor = InlineTaskExecutor::new(|_context, Task| async {});
+    let mut task_context = TaskContext::new(task_executor, message_port);
+    for increment in [200, 100, 50] {
+        task_context.submit_effect(increment).unwrap();
+    }
+    let mut model = Counter::default();
+    let messages_consumed = poll_ready(consume_messages(
+        &mut message_rx,
+        &mut task_context,
+        &mut model,
+        &mut NoRender::new(),
+    ));
+    assert!(matches!(
+        messages_consumed,
+        MessagesConsumed::EffectFailed(Overflow)
+    ));
+    // The model remains unchanged after the failed effect.
+    assert_eq!(200, model.count);
+    assert_eq!(Some(1), task_context.message_port().pending_message_count());
+}
@@ -0,0 +1,47 @@
+// SPDX-FileCopyrightText: The infect authors
+// SPDX-License-Identifier: MPL-2.0
+
+//! Submitting messages through a `WeakMessagePort`.
+
+#![cfg(feature = "std")]
+
+use infect::{
+    message_channel, Message, MessageReceiver, MessageTransport as _, MpscTransport, TryRecvError,
+};
+
+fn try_recv(message_rx: &mut MessageReceiver<(), u32>) -> Result<Message<(), u32>, TryRecvError> {
+    MpscTransport::try_recv(message_rx)
+}
+
+#[test]
+fn upgraded_port_submits_into_the_same_channel() {
+    let (message_port, mut message_rx) = message_channel::<(), u32>(10).split();
+    let weak_port = message_port.downgrade();
+    let mut upgraded_port = weak_port.upgrade().unwrap();
+    upgraded_port.submit_effect(1_u32).unwrap();
+    assert_eq!(Some(1), message_port.pending_message_count());
+    assert_eq!(Ok(Message::Effect(1)), try_recv(&mut message_rx));
+}
+
+#[test]
+fn does_not_keep_the_channel_open() {
+    let (message_port, mut message_rx) = message_channel::<(), u32>(10).split();
+    let weak_port = message_port.downgrade();
+    let mut upgraded_port = weak_port.clone().upgrade().unwrap();
+    drop(message_port);
+    // Keeps the channel open until all ports have been dropped.
+    upgraded_port.submit_effect(1_u32).unwrap();
+    assert!(weak_port.upgrade().is_some());
+    drop(upgraded_port);
+    assert!(weak_port.upgrade().is_none());
+    assert_eq!(Ok(Message::Effect(1)), try_recv(&mut message_rx));
+    assert_eq!(Err(TryRecvError::Closed), try_recv(&mut message_rx));
+}
+
+#[test]
+fn could_not_be_upgraded_after_the_receiver_has_been_dropped() {
+    let (message_port, message_rx) = message_channel::<(), u32>(10).split();
+    let weak_port = message_port.downgrade();
+    drop(message_rx);
+    assert!(weak_port.upgrade().is_none());
+}